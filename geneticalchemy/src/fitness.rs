@@ -51,6 +51,6 @@ impl FitnessFunction for AlchemyFitnessFunction {
 
     fn constraint(&self, genome: &Self::Genotype) -> Constraint {
         let mix = self.get_mix(genome);
-        NotNan::new(-(mix.volume() - self.desired_volume).abs()).unwrap()
+        NotNan::new(-(mix.volume().inner() - self.desired_volume).abs()).unwrap()
     }
 }
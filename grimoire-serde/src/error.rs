@@ -0,0 +1,9 @@
+use thiserror;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Unknown modifier {1} of ingredient {0}, it's not the code of any effect in the catalogue")]
+    UnknownModifier(String, String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
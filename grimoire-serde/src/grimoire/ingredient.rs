@@ -1,69 +1,60 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use indexmap::IndexMap;
 use serde::Serialize;
-use crate::theoretical::TheoreticalWrapper;
+use crate::theoretical::{TheoreticalWrapper, WeightWrapper};
+use grimoire2::effect::Effects;
 use grimoire2::grimoire::Ingredient;
+use grimoire2::provenance::Provenance;
 
 #[derive(Serialize)]
 pub struct IngredientHumanReadable {
-    weight: bool,
+    weight: WeightWrapper,
     #[serde(skip_serializing_if = "Option::is_none")]
     skill: Option<String>,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    dh: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    dp: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    mdh: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    mdp: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    hot: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    pot: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    mhot: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    mpot: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    hl: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    pl: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    mhl: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    mpl: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    a: TheoreticalWrapper,
-    #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
-    ma: TheoreticalWrapper,    
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    tags: BTreeSet<String>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    aliases: BTreeSet<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    /// Known modifier values by code, in the order of the effect catalogue
+    #[serde(flatten)]
+    values: IndexMap<String, TheoreticalWrapper>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    provenance: BTreeMap<String, Provenance>,
 }
 
-impl From<Ingredient> for IngredientHumanReadable {
-    fn from(value: Ingredient) -> Self {
-        use grimoire2::effect::Effect;
+impl IngredientHumanReadable {
+    pub fn new(value: Ingredient, effects: &Effects) -> Self {
+        let mut values = IndexMap::default();
+        let mut provenance = BTreeMap::default();
 
-        Self {
-            weight: value.weight,
-            skill: value.skill,
-            dh: value.modifiers[Effect::DirectHealing].term.into(),
-            mdh: value.modifiers[Effect::DirectHealing].multiplier.into(),
+        for (code, effect, multiplier) in effects.modifier_codes() {
+            let modifier = &value.modifiers[effect];
+            let (x, x_provenance) = match multiplier {
+                true => (modifier.multiplier, &modifier.multiplier_provenance),
+                false => (modifier.term, &modifier.term_provenance),
+            };
 
-            dp: value.modifiers[Effect::DirectPoison].term.into(),
-            mdp: value.modifiers[Effect::DirectPoison].multiplier.into(),
+            if let Some(x) = x_provenance {
+                provenance.insert(code.clone(), x.clone());
+            }
 
-            hot: value.modifiers[Effect::HealingOverTime].term.into(),
-            mhot: value.modifiers[Effect::HealingOverTime].multiplier.into(),
+            let x: TheoreticalWrapper = x.into();
+            if !x.is_unknown() {
+                values.insert(code, x);
+            }
+        }
 
-            pot: value.modifiers[Effect::PoisonOverTime].term.into(),
-            mpot: value.modifiers[Effect::PoisonOverTime].multiplier.into(),
-            
-            hl: value.modifiers[Effect::HealingLength].term.into(),
-            mhl: value.modifiers[Effect::HealingLength].multiplier.into(),
-            
-            pl: value.modifiers[Effect::PoisonLength].term.into(),
-            mpl: value.modifiers[Effect::PoisonLength].multiplier.into(),
-            
-            a: value.modifiers[Effect::Alcohol].term.into(),
-            ma: value.modifiers[Effect::Alcohol].multiplier.into(),
+        Self {
+            weight: value.weight.into(),
+            skill: value.skill,
+            tags: value.tags,
+            aliases: value.aliases,
+            source: value.source,
+            values,
+            provenance,
         }
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,6 @@
 use serde::Serialize;
-use grimoire2::grimoire::Skill;
+use grimoire2::grimoire::{Skill, SkillCategory};
+use grimoire2::provenance::Provenance;
 
 use crate::theoretical::TheoreticalWrapper;
 
@@ -11,6 +12,10 @@ pub struct SkillHumanReadable {
     parent_2: Option<String>,
     #[serde(skip_serializing_if = "TheoreticalWrapper::is_unknown")]
     effectiveness: TheoreticalWrapper,
+    category: SkillCategory,
+    cost: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effectiveness_provenance: Option<Provenance>,
 }
 
 impl From<Skill> for SkillHumanReadable {
@@ -18,7 +23,10 @@ impl From<Skill> for SkillHumanReadable {
         Self {
             parent: value.parent,
             parent_2: value.parent_2,
-            effectiveness: value.effectiveness.into()
+            effectiveness: value.effectiveness.into(),
+            category: value.category,
+            cost: value.cost,
+            effectiveness_provenance: value.effectiveness_provenance,
         }
     }
 }
@@ -1,3 +1,4 @@
+pub mod error;
 pub mod theoretical;
 pub mod modify;
 pub mod mix;
@@ -1,18 +1,23 @@
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use serde::{Serialize, Deserialize};
 
-use grimoire2::modify::character::CharacterUpdate;
+use grimoire2::modify::character::{CharacterUpdate, CharacterUpdateCommand};
+use grimoire2::modify::command::Commands;
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct CharacterUpdateSerializable {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     remove_clades: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     add_clades: Vec<String>,
 
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     remove_skills: Vec<String>,    
-    skills: HashMap<String, u8>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    skills: IndexMap<String, u8>,
 }
 
 
@@ -28,6 +33,20 @@ impl CharacterUpdateSerializable {
         update
     }
 
+    pub fn from_update(update: &CharacterUpdate) -> Self {
+        let mut result = Self::default();
+
+        for i in 0..update.len() {
+            match &update[i] {
+                CharacterUpdateCommand::AddClade(x) => result.add_clades.push(x.clone()),
+                CharacterUpdateCommand::RemoveClade(x) => result.remove_clades.push(x.clone()),
+                CharacterUpdateCommand::SetSkill(skill, x) => { result.skills.insert(skill.clone(), *x); },
+                CharacterUpdateCommand::RemoveSkill(x) => result.remove_skills.push(x.clone()),
+            }
+        }
+
+        result
+    }
 }
 
 impl From<CharacterUpdateSerializable> for CharacterUpdate {
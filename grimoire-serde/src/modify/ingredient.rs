@@ -1,74 +1,219 @@
-use serde::{Serialize, Deserialize};
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize, Deserializer};
+use serde::de::{self, MapAccess, Visitor};
 
-use grimoire2::modify::ingredient::IngredientUpdate;
-use grimoire2::effect::Effect;
+use grimoire2::modify::command::Commands;
+use grimoire2::modify::ingredient::{IngredientUpdate, IngredientUpdateCommand};
+use grimoire2::effect::{Effect, Effects};
+use grimoire2::grimoire::Ingredient;
+use grimoire2::provenance::Provenance;
 
-use crate::theoretical::TheoreticalWrapper;
+use crate::error::{self, Error};
+use crate::theoretical::{TheoreticalWrapper, WeightWrapper};
 
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(default)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Clone, Serialize, Default)]
 pub struct IngredientUpdateSerializable {
+    #[serde(skip_serializing_if = "Option::is_none")]
     skill: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
     remove_skill: bool,
 
-    weight: Option<bool>,
-
-    dh: Option<TheoreticalWrapper>,
-    dp: Option<TheoreticalWrapper>,
-    mdh: Option<TheoreticalWrapper>,
-    mdp: Option<TheoreticalWrapper>,
-    hot: Option<TheoreticalWrapper>,
-    pot: Option<TheoreticalWrapper>,
-    mhot: Option<TheoreticalWrapper>,
-    mpot: Option<TheoreticalWrapper>,
-    hl: Option<TheoreticalWrapper>,
-    pl: Option<TheoreticalWrapper>,
-    mhl: Option<TheoreticalWrapper>,
-    mpl: Option<TheoreticalWrapper>,
-    a: Option<TheoreticalWrapper>,
-    ma: Option<TheoreticalWrapper>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weight: Option<WeightWrapper>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    remove_tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    remove_aliases: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    remove_source: bool,
+
+    /// Modifier values by code, such as `dh` or `mdh`, written next to the other fields
+    #[serde(flatten)]
+    values: IndexMap<String, TheoreticalWrapper>,
+
+    /// Provenance of every value set by this update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provenance: Option<Provenance>,
+    /// Provenance of single values by code, takes precedence over `provenance`
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    provenance_for: IndexMap<String, Provenance>,
+}
+
+
+/// Every key that isn't a field is a modifier code. The derived implementation would buffer
+/// the modifier values, which loses the YAML tags of theories
+impl<'de> Deserialize<'de> for IngredientUpdateSerializable {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct IngredientUpdateVisitor;
+
+        impl<'de> Visitor<'de> for IngredientUpdateVisitor {
+            type Value = IngredientUpdateSerializable;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an ingredient update")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut result = IngredientUpdateSerializable::default();
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "skill" => result.skill = map.next_value()?,
+                        "remove_skill" => result.remove_skill = map.next_value()?,
+                        "weight" => result.weight = map.next_value()?,
+                        "tags" => result.tags = map.next_value()?,
+                        "remove_tags" => result.remove_tags = map.next_value()?,
+                        "aliases" => result.aliases = map.next_value()?,
+                        "remove_aliases" => result.remove_aliases = map.next_value()?,
+                        "source" => result.source = map.next_value()?,
+                        "remove_source" => result.remove_source = map.next_value()?,
+                        "provenance" => result.provenance = map.next_value()?,
+                        "provenance_for" => result.provenance_for = map.next_value()?,
+                        _ => {
+                            let value = map.next_value()?;
+                            if result.values.insert(key.clone(), value).is_some() {
+                                return Err(de::Error::custom(format!("duplicate field `{key}`")));
+                            }
+                        },
+                    }
+                }
+
+                Ok(result)
+            }
+        }
+
+        deserializer.deserialize_map(IngredientUpdateVisitor)
+    }
 }
 
 
 impl IngredientUpdateSerializable {
-    pub fn to_update(&self) -> IngredientUpdate {
+    /// Modifier codes are looked up in `effects`, `name` is the name of the ingredient for
+    /// errors
+    pub fn to_update(&self, name: &str, effects: &Effects) -> error::Result<IngredientUpdate> {
         let mut update = IngredientUpdate::default();
-        
 
         if let Some(x) = self.skill.clone() { update.set_skill(&x); }
         if self.remove_skill { update.remove_skill(); }
 
-        if let Some(x) = self.weight { update.set_weight(x); }
-
-        if let Some(x) = self.dh { update.set_term(Effect::DirectHealing, x.into()); }
-        if let Some(x) = self.mdh { update.set_multiplier(Effect::DirectHealing, x.into()); }
-        
-        if let Some(x) = self.dp { update.set_term(Effect::DirectPoison, x.into()); }
-        if let Some(x) = self.mdp { update.set_multiplier(Effect::DirectPoison, x.into()); }
-
-        if let Some(x) = self.hot { update.set_term(Effect::HealingOverTime, x.into()); }
-        if let Some(x) = self.mhot { update.set_multiplier(Effect::HealingOverTime, x.into()); }
-        
-        if let Some(x) = self.pot { update.set_term(Effect::PoisonOverTime, x.into()); }
-        if let Some(x) = self.mpot { update.set_multiplier(Effect::PoisonOverTime, x.into()); }
-        
-        if let Some(x) = self.hl { update.set_term(Effect::HealingLength, x.into()); }
-        if let Some(x) = self.mhl { update.set_multiplier(Effect::HealingLength, x.into()); }
-        
-        if let Some(x) = self.pl { update.set_term(Effect::PoisonLength, x.into()); }
-        if let Some(x) = self.mpl { update.set_multiplier(Effect::PoisonLength, x.into()); }
-        
-        if let Some(x) = self.a { update.set_term(Effect::Alcohol, x.into()); }
-        if let Some(x) = self.ma { update.set_multiplier(Effect::Alcohol, x.into()); }
-
-        update
+        if let Some(x) = self.weight { update.set_weight(x.into()); }
+
+        self.tags.iter().for_each(|x| { update.add_tag(x); });
+        self.remove_tags.iter().for_each(|x| { update.remove_tag(x); });
+        self.aliases.iter().for_each(|x| { update.add_alias(x); });
+        self.remove_aliases.iter().for_each(|x| { update.remove_alias(x); });
+
+        if let Some(x) = self.source.clone() { update.set_source(&x); }
+        if self.remove_source { update.remove_source(); }
+
+        let modifier = |code: &str| effects.modifier(code)
+            .ok_or_else(|| Error::UnknownModifier(name.to_string(), code.to_string()));
+
+        for (code, x) in &self.values {
+            match modifier(code)? {
+                (effect, true) => update.set_multiplier(effect, (*x).into()),
+                (effect, false) => update.set_term(effect, (*x).into()),
+            };
+        }
+
+        // A new value replaces the provenance of the old one, even if it has none
+        let codes = self.values.keys().chain(self.provenance_for.keys().filter(|x| !self.values.contains_key(*x)));
+
+        for code in codes {
+            let provenance = match self.provenance_for.get(code) {
+                Some(x) => Some(x.clone()),
+                None => self.provenance.clone(),
+            };
+
+            match modifier(code)? {
+                (effect, true) => update.set_multiplier_provenance(effect, provenance),
+                (effect, false) => update.set_term_provenance(effect, provenance),
+            };
+        }
+
+        Ok(update)
     }
-}
 
-impl From<IngredientUpdateSerializable> for IngredientUpdate {
-    fn from(value: IngredientUpdateSerializable) -> Self {
-        value.to_update()
+    /// `ingredient` is the ingredient after the update, used to restate values whose provenance
+    /// is removed without the value changing
+    pub fn from_update(update: &IngredientUpdate, ingredient: &Ingredient, effects: &Effects) -> Self {
+        let mut result = Self::default();
+        let mut removed_provenance = Vec::default();
+
+        let code = |effect: Effect, multiplier: bool| effects.modifier_code(effect, multiplier)
+            .unwrap_or_default();
+
+        for i in 0..update.len() {
+            match &update[i] {
+                IngredientUpdateCommand::ChangeTerm(effect, x) => {
+                    result.values.insert(code(*effect, false), (*x).into());
+                },
+                IngredientUpdateCommand::ChangeMultiplier(effect, x) => {
+                    result.values.insert(code(*effect, true), (*x).into());
+                },
+                IngredientUpdateCommand::SetSkill(Some(x)) => result.skill = Some(x.clone()),
+                IngredientUpdateCommand::SetSkill(None) => result.remove_skill = true,
+                IngredientUpdateCommand::SetWeight(x) => result.weight = Some((*x).into()),
+                IngredientUpdateCommand::AddTag(x) => result.tags.push(x.clone()),
+                IngredientUpdateCommand::RemoveTag(x) => result.remove_tags.push(x.clone()),
+                IngredientUpdateCommand::AddAlias(x) => result.aliases.push(x.clone()),
+                IngredientUpdateCommand::RemoveAlias(x) => result.remove_aliases.push(x.clone()),
+                IngredientUpdateCommand::SetSource(Some(x)) => result.source = Some(x.clone()),
+                IngredientUpdateCommand::SetSource(None) => result.remove_source = true,
+                IngredientUpdateCommand::SetTermProvenance(effect, x) => {
+                    removed_provenance.push((*effect, false, x.is_none()));
+                },
+                IngredientUpdateCommand::SetMultiplierProvenance(effect, x) => {
+                    removed_provenance.push((*effect, true, x.is_none()));
+                },
+            }
+        }
+
+        // Setting a value without provenance is the only way to remove it
+        for (effect, multiplier, removed) in removed_provenance {
+            let code = code(effect, multiplier);
+
+            if !result.values.contains_key(&code) {
+                let modifier = &ingredient.modifiers[effect];
+                let value = if multiplier { modifier.multiplier } else { modifier.term };
+
+                if removed {
+                    result.values.insert(code, value.into());
+                } else {
+                    let provenance = if multiplier { &modifier.multiplier_provenance } else { &modifier.term_provenance };
+                    if let Some(x) = provenance {
+                        result.provenance_for.insert(code, x.clone());
+                    }
+                }
+            }
+        }
+
+        // Values set here would lose their provenance otherwise
+        for (code, effect, multiplier) in effects.modifier_codes() {
+            if result.values.contains_key(&code) {
+                let modifier = &ingredient.modifiers[effect];
+                let provenance = if multiplier { &modifier.multiplier_provenance } else { &modifier.term_provenance };
+
+                if let Some(x) = provenance {
+                    result.provenance_for.insert(code, x.clone());
+                }
+            }
+        }
+
+        result
     }
 }
@@ -3,38 +3,93 @@ pub mod skill;
 pub mod ingredient;
 
 
-use std::collections::HashMap;
-
 pub use character::CharacterUpdateSerializable;
 pub use skill::SkillUpdateSerializable;
 pub use ingredient::IngredientUpdateSerializable;
 
 
+use indexmap::IndexMap;
 use serde::{Serialize, Deserialize};
-use grimoire2::modify::GrimoireUpdate;
+use grimoire2::effect::{EffectDefinition, Effects};
+use grimoire2::grimoire::{Clade, Grimoire, Production, Recipe, Ruleset, SkillPointRules};
+use grimoire2::modify::{GrimoireUpdate, GrimoireUpdateCommand};
+use grimoire2::modify::character::CharacterUpdate;
+use grimoire2::modify::command::Commands;
+use grimoire2::modify::ingredient::IngredientUpdate;
+use grimoire2::modify::skill::SkillUpdate;
+
+use crate::error::Result;
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct GrimoireUpdateSerializable {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     remove_characters: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     remove_skills: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     remove_ingredients: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    remove_clades: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    remove_rules: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    remove_rulesets: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    remove_productions: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    remove_recipes: Vec<String>,
 
-    characters: HashMap<String, CharacterUpdateSerializable>,
-    skills: HashMap<String, SkillUpdateSerializable>,
-    ingredients: HashMap<String, IngredientUpdateSerializable>
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    characters: IndexMap<String, CharacterUpdateSerializable>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    skills: IndexMap<String, SkillUpdateSerializable>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    ingredients: IndexMap<String, IngredientUpdateSerializable>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    clades: IndexMap<String, Clade>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rules: Option<SkillPointRules>,
+    /// Effects added to the catalogue or redefined, by id
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    effects: IndexMap<String, EffectDefinition>,
+    /// Rulesets added or replaced, by name
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    rulesets: IndexMap<String, Ruleset>,
+    /// Name of the ruleset to put in effect
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ruleset: Option<String>,
+    /// How ingredients are made from others, by the name of the ingredient made
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    productions: IndexMap<String, Production>,
+    /// Mixes kept by name
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    recipes: IndexMap<String, Recipe>,
 }
 
 
 impl GrimoireUpdateSerializable {
-    pub fn to_update(&self) -> GrimoireUpdate {
+    /// Modifier codes are looked up in `effects`, the catalogue of the grimoire the update is
+    /// applied to, together with the effects of the update itself
+    pub fn to_update(&self, effects: &Effects) -> Result<GrimoireUpdate> {
         let mut update = GrimoireUpdate::default();
+        let mut effects = effects.clone();
+
+        for (id, definition) in &self.effects {
+            effects.insert(id, definition.clone());
+            update.effect(id, definition.clone());
+        }
 
         self.remove_characters.iter().for_each(|name| { update.remove_character(name); });
         self.remove_skills.iter().for_each(|name| { update.remove_skill(name); } );
         self.remove_ingredients.iter().for_each(|name| { update.remove_ingredient(name); } );
+        self.remove_clades.iter().for_each(|name| { update.remove_clade(name); } );
+
+        if self.remove_rules {
+            update.set_rules(None);
+        }
 
         self.characters.iter().for_each(
             |(name, ser_update)| {
@@ -48,14 +103,170 @@ impl GrimoireUpdateSerializable {
             }
         );
 
-        self.ingredients.iter().for_each(
-            |(name, ser_update)| {
-                update.ingredient(name, ser_update.clone().into());
+        for (name, ser_update) in &self.ingredients {
+            update.ingredient(name, ser_update.to_update(name, &effects)?);
+        }
+
+        self.clades.iter().for_each(
+            |(name, clade)| {
+                update.clade(name, clade.clone());
             }
         );
 
-        update
+        if self.rules.is_some() {
+            update.set_rules(self.rules);
+        }
+
+        self.remove_rulesets.iter().for_each(|name| { update.remove_ruleset(name); });
+        self.rulesets.iter().for_each(|(name, ruleset)| { update.ruleset(name, ruleset.clone()); });
+
+        if let Some(name) = &self.ruleset {
+            update.select_ruleset(name);
+        }
+
+        self.remove_productions.iter().for_each(|name| { update.remove_production(name); });
+        self.productions.iter().for_each(|(name, production)| { update.production(name, production.clone()); });
+        self.remove_recipes.iter().for_each(|name| { update.remove_recipe(name); });
+        self.recipes.iter().for_each(|(name, recipe)| { update.recipe(name, recipe.clone()); });
+
+        Ok(update)
+    }
+
+    /// Update file that turns `old` into `new`, in the same format as read by `update --from`
+    pub fn diff(old: &Grimoire, new: &Grimoire) -> Self {
+        let mut result = Self::default();
+        let update = GrimoireUpdate::diff(old, new);
+
+        for i in 0..update.len() {
+            match &update[i] {
+                GrimoireUpdateCommand::Character(name, x) => {
+                    let x = match old.characters.get(name) {
+                        Some(_) => x.clone(),
+                        None => CharacterUpdate::diff(&Default::default(), &new.characters[name]),
+                    };
+                    result.characters.insert(name.clone(), CharacterUpdateSerializable::from_update(&x));
+                },
+                GrimoireUpdateCommand::Skill(name, x) => {
+                    let x = match old.skills.get(name) {
+                        Some(_) => x.clone(),
+                        None => SkillUpdate::diff(&Default::default(), &new.skills[name]),
+                    };
+                    result.skills.insert(
+                        name.clone(), SkillUpdateSerializable::from_update(&x, &new.skills[name])
+                    );
+                },
+                GrimoireUpdateCommand::Ingredient(name, x) => {
+                    let x = match old.ingredients.get(name) {
+                        Some(_) => x.clone(),
+                        None => IngredientUpdate::diff(&Default::default(), &new.ingredients[name]),
+                    };
+                    result.ingredients.insert(
+                        name.clone(),
+                        IngredientUpdateSerializable::from_update(&x, &new.ingredients[name], &new.effects)
+                    );
+                },
+                GrimoireUpdateCommand::RemoveCharacter(name) => result.remove_characters.push(name.clone()),
+                GrimoireUpdateCommand::RemoveSkill(name) => result.remove_skills.push(name.clone()),
+                GrimoireUpdateCommand::RemoveIngredient(name) => result.remove_ingredients.push(name.clone()),
+                GrimoireUpdateCommand::Clade(name, clade) => {
+                    result.clades.insert(name.clone(), clade.clone());
+                },
+                GrimoireUpdateCommand::RemoveClade(name) => result.remove_clades.push(name.clone()),
+                GrimoireUpdateCommand::SetRules(None) => result.remove_rules = true,
+                GrimoireUpdateCommand::SetRules(x) => result.rules = *x,
+                GrimoireUpdateCommand::Effect(id, x) => {
+                    result.effects.insert(id.clone(), x.clone());
+                },
+                GrimoireUpdateCommand::Ruleset(name, x) => {
+                    result.rulesets.insert(name.clone(), x.clone());
+                },
+                GrimoireUpdateCommand::RemoveRuleset(name) => result.remove_rulesets.push(name.clone()),
+                GrimoireUpdateCommand::SelectRuleset(name) => result.ruleset = Some(name.clone()),
+                GrimoireUpdateCommand::Production(name, x) => {
+                    result.productions.insert(name.clone(), x.clone());
+                },
+                GrimoireUpdateCommand::RemoveProduction(name) => result.remove_productions.push(name.clone()),
+                GrimoireUpdateCommand::Recipe(name, x) => {
+                    result.recipes.insert(name.clone(), x.clone());
+                },
+                GrimoireUpdateCommand::RemoveRecipe(name) => result.remove_recipes.push(name.clone()),
+            }
+        }
+
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::modify::character::CharacterUpdate;
+    use grimoire2::provenance::Provenance;
+    use grimoire2::theoretical::Theoretical;
+
+    use super::*;
+
+    fn grimoire() -> Grimoire {
+        GrimoireUpdate::default()
+            .skill("Herbology", SkillUpdate::default()
+                .set_effectiveness(Theoretical::Known(0.5))
+                .set_effectiveness_provenance(Some(Provenance::new("wiki")))
+                .clone())
+            .skill("Geology", SkillUpdate::default())
+            .ingredient(
+                "Leaf", IngredientUpdate::default()
+                    .set_skill("Herbology")
+                    .add_tag("herb")
+                    .set_term(Effect::DirectHealing, Theoretical::Known(1.))
+                    .set_term_provenance(Effect::DirectHealing, Some(Provenance::new("Tashka")))
+                    .set_multiplier(Effect::DirectHealing, Theoretical::Theory(1.2))
+                    .set_multiplier_provenance(Effect::DirectHealing, Some(Provenance::new("Bob")))
+                    .clone()
+            )
+            .character("Tashka", CharacterUpdate::default().set_skill("Herbology", 50).clone())
+            .create()
     }
 
+    #[test]
+    fn test_diff_round_trip() {
+        let old = grimoire();
+        let mut new = old.clone();
+        GrimoireUpdate::default()
+            .skill("Herbology", SkillUpdate::default().set_effectiveness(Theoretical::Known(0.6)).clone())
+            .ingredient(
+                "Leaf", IngredientUpdate::default()
+                    .set_term(Effect::DirectHealing, Theoretical::Known(1.5))
+                    .set_multiplier_provenance(Effect::DirectHealing, None)
+                    .remove_tag("herb")
+                    .clone()
+            )
+            .ingredient("Stone", IngredientUpdate::default().set_skill("Geology").set_weight(Theoretical::Theory(1.5)).clone())
+            .character("Tashka", CharacterUpdate::default().remove_skill("Herbology").add_clade("Alchemist").clone())
+            .remove_skill("Geology")
+            .set_rules(Some(SkillPointRules { primary: 100, secondary: 50, tertiary: 25 }))
+            .ruleset("old", Ruleset { weight_per_volume: 12., ..Ruleset::default() })
+            .select_ruleset("old")
+            .production("Leaf", Production::new([("Stone".to_string(), 2)].into_iter().collect(), 3))
+            .recipe("Salve", Recipe::new([("Leaf".to_string(), 2)].into_iter().collect()))
+            .update(&mut new);
 
-}
\ No newline at end of file
+        let diff = GrimoireUpdateSerializable::diff(&old, &new);
+        let yaml = serde_yaml::to_string(&diff).unwrap();
+        let read: GrimoireUpdateSerializable = serde_yaml::from_str(&yaml).unwrap();
+
+        let mut updated = old.clone();
+        read.to_update(&old.effects).unwrap().update(&mut updated);
+
+        assert_eq!(updated, new, "{}", yaml);
+        assert_eq!(updated.ruleset(), new.ruleset(), "{}", yaml);
+        assert!(!yaml.contains("dp"), "{}", yaml);
+    }
+
+    #[test]
+    fn test_diff_empty() {
+        let diff = GrimoireUpdateSerializable::diff(&grimoire(), &grimoire());
+
+        assert_eq!(serde_yaml::to_string(&diff).unwrap(), "{}\n");
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Serialize, Deserialize};
 
-use grimoire2::modify::skill::SkillUpdate;
+use grimoire2::grimoire::{Skill, SkillCategory};
+use grimoire2::provenance::Provenance;
+use grimoire2::modify::command::Commands;
+use grimoire2::modify::skill::{SkillUpdate, SkillUpdateCommand};
 
 use crate::theoretical::TheoreticalWrapper;
 
@@ -9,11 +12,22 @@ use crate::theoretical::TheoreticalWrapper;
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct SkillUpdateSerializable {
+    #[serde(skip_serializing_if = "Option::is_none")]
     effectiveness: Option<TheoreticalWrapper>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     parent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     parent_2: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
     remove_parent: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
     remove_parent_2: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<SkillCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effectiveness_provenance: Option<Provenance>,
 }
 
 
@@ -25,6 +39,10 @@ impl SkillUpdateSerializable {
             update.set_effectiveness(x.into());
         }
 
+        if self.effectiveness.is_some() || self.effectiveness_provenance.is_some() {
+            update.set_effectiveness_provenance(self.effectiveness_provenance.clone());
+        }
+
         if let Some(x) = &self.parent {
             update.set_parent(x);
         }
@@ -41,9 +59,49 @@ impl SkillUpdateSerializable {
             update.remove_parent_2();
         }
 
+        if let Some(x) = self.category {
+            update.set_category(x);
+        }
+
+        if let Some(x) = self.cost {
+            update.set_cost(x);
+        }
+
         update
     }
 
+    /// `skill` is the skill after the update, used to restate the effectiveness when only its
+    /// provenance is removed
+    pub fn from_update(update: &SkillUpdate, skill: &Skill) -> Self {
+        let mut result = Self::default();
+
+        for i in 0..update.len() {
+            match &update[i] {
+                SkillUpdateCommand::SetEffectiveness(x) => result.effectiveness = Some((*x).into()),
+                SkillUpdateCommand::SetParent(Some(x)) => result.parent = Some(x.clone()),
+                SkillUpdateCommand::SetParent(None) => result.remove_parent = true,
+                SkillUpdateCommand::SetParent2(Some(x)) => result.parent_2 = Some(x.clone()),
+                SkillUpdateCommand::SetParent2(None) => result.remove_parent_2 = true,
+                SkillUpdateCommand::SetCategory(x) => result.category = Some(*x),
+                SkillUpdateCommand::SetCost(x) => result.cost = Some(*x),
+                SkillUpdateCommand::SetEffectivenessProvenance(x) => {
+                    result.effectiveness_provenance = x.clone();
+                },
+            }
+        }
+
+        if result.effectiveness_provenance.is_none() && result.effectiveness.is_none()
+            && (0..update.len()).any(|i| matches!(update[i], SkillUpdateCommand::SetEffectivenessProvenance(None)))
+        {
+            result.effectiveness = Some(skill.effectiveness.into());
+        }
+
+        if result.effectiveness.is_some() {
+            result.effectiveness_provenance = skill.effectiveness_provenance.clone();
+        }
+
+        result
+    }
 }
 
 impl From<SkillUpdateSerializable> for SkillUpdate {
@@ -1,7 +1,8 @@
+use indexmap::IndexMap;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
-use grimoire2::standalone::Mix;
+use grimoire2::standalone::{Contribution, Mix};
 use grimoire2::effect::Effect;
 
 use crate::theoretical::TheoreticalWrapper;
@@ -12,39 +13,48 @@ use crate::mix::MixIngredients;
 #[serde(deny_unknown_fields)]
 pub struct PotionSerializable {
     #[serde(skip_serializing_if = "Option::is_none")]
-    volume: Option<f64>,
+    pub volume: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    effects: Option<PotionEffectsSerializable>,
+    pub effects: Option<PotionEffectsSerializable>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    ingredients: Option<MixIngredients>,
+    pub ingredients: Option<MixIngredients>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    total_healing_raw: Option<TotalEffect>,
+    pub total_healing_raw: Option<TotalEffect>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    total_poison_raw: Option<TotalEffect>,
+    pub total_poison_raw: Option<TotalEffect>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    total_healing: Option<TotalEffect>,
+    pub total_healing: Option<TotalEffect>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    total_poison: Option<TotalEffect>
+    pub total_poison: Option<TotalEffect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breakdown: Option<PotionBreakdownSerializable>,
 }
 
 
+/// Value of every effect in the catalogue by code
+pub type PotionEffectsSerializable = IndexMap<String, TheoreticalWrapper>;
+
+
+/// Contribution of every ingredient by name, for every effect by code
+pub type PotionBreakdownSerializable = IndexMap<String, IndexMap<String, ContributionSerializable>>;
+
+
+/// Part of an ingredient in an effect: the effect is the sum of the terms times the product
+/// of the factors, times the advanced potion making bonus
 #[derive(Debug, Clone, Serialize)]
-pub struct PotionEffectsSerializable {
-    dh: TheoreticalWrapper,
-    dp: TheoreticalWrapper,
-    hot: TheoreticalWrapper,
-    pot: TheoreticalWrapper,
-    hl: TheoreticalWrapper,
-    pl: TheoreticalWrapper,
-    a: TheoreticalWrapper
+pub struct ContributionSerializable {
+    /// Lore × term × share
+    pub term: TheoreticalWrapper,
+    /// 1 + multiplier × √share
+    pub factor: TheoreticalWrapper,
 }
 
 
 #[derive(Debug, Clone, Serialize)]
 pub struct TotalEffect {
-    over_time_total: TheoreticalWrapper,
-    per_second_total: TheoreticalWrapper,
-    length_total: TheoreticalWrapper,
+    pub over_time_total: TheoreticalWrapper,
+    pub per_second_total: TheoreticalWrapper,
+    pub length_total: TheoreticalWrapper,
 }
 
 
@@ -58,6 +68,7 @@ pub struct PotionSerializableConfig {
     total_poison_raw: bool,
     total_healing: bool,
     total_poison: bool,
+    breakdown: bool,
 }
 
 
@@ -71,6 +82,7 @@ impl Default for PotionSerializableConfig {
             total_poison_raw: false,
             total_healing: false,
             total_poison: false,
+            breakdown: false,
         }
     }
 }
@@ -87,6 +99,7 @@ impl PotionSerializableConfig {
             self.total_poison_raw.then_some(self.serialize_total_poison_raw(mix));
         let total_healing = self.total_healing.then_some(self.serialize_total_healing(mix));
         let total_poison = self.total_poison.then_some(self.serialize_total_poison(mix));
+        let breakdown = self.breakdown.then_some(self.serialize_breakdown(mix));
 
         PotionSerializable { 
             volume, 
@@ -95,32 +108,20 @@ impl PotionSerializableConfig {
             total_healing_raw, 
             total_poison_raw, 
             total_healing, 
-            total_poison 
+            total_poison,
+            breakdown,
         }
     }
 
     pub fn serialize_volume(&self, mix: &Mix) -> f64 {
-        mix.volume()
+        mix.volume().inner()
     }
 
     pub fn serialize_effects(&self, mix: &Mix) -> PotionEffectsSerializable {
-        let dh = mix.effect(Effect::DirectHealing);
-        let dp = mix.effect(Effect::DirectPoison);
-        let hot = mix.effect(Effect::HealingOverTime);
-        let pot = mix.effect(Effect::PoisonOverTime);
-        let hl = mix.effect(Effect::HealingLength);
-        let pl = mix.effect(Effect::PoisonLength);
-        let a = mix.effect(Effect::Alcohol);
-
-        PotionEffectsSerializable { 
-            dh: dh.into(), 
-            dp: dp.into(), 
-            hot: hot.into(), 
-            pot: pot.into(), 
-            hl: hl.into(), 
-            pl: pl.into(), 
-            a: a.into() 
-        }
+        mix.effects()
+            .iter()
+            .map(|(effect, _, x)| (x.code.clone(), mix.effect(effect).into()))
+            .collect()
     }
 
     pub fn serialize_ingredients(&self, mix: &Mix) -> HashMap<String, u64> {
@@ -133,9 +134,9 @@ impl PotionSerializableConfig {
         let volume = mix.volume();
 
         TotalEffect { 
-            over_time_total: ((hot * hl) * volume.into()).into(), 
-            per_second_total: (hot * volume.sqrt().into()).into(), 
-            length_total: (hl * volume.sqrt().into()).into(),
+            over_time_total: ((hot * hl) * volume).into(), 
+            per_second_total: (hot * volume.map(f64::sqrt)).into(), 
+            length_total: (hl * volume.map(f64::sqrt)).into(),
         }
     } 
 
@@ -145,9 +146,9 @@ impl PotionSerializableConfig {
         let volume = mix.volume();
 
         TotalEffect { 
-            over_time_total: ((pot * pl) * volume.into()).into(), 
-            per_second_total: (pot * volume.sqrt().into()).into(), 
-            length_total: (pl * volume.sqrt().into()).into(),
+            over_time_total: ((pot * pl) * volume).into(), 
+            per_second_total: (pot * volume.map(f64::sqrt)).into(), 
+            length_total: (pl * volume.map(f64::sqrt)).into(),
         }
     }    
     
@@ -159,9 +160,9 @@ impl PotionSerializableConfig {
         let volume = mix.volume();
 
         TotalEffect { 
-            over_time_total: ((hot * hl) * volume.into() - (pot * pl) * volume.into()).into(),
-            per_second_total: (hot * volume.sqrt().into() - pot * volume.sqrt().into()).into(),
-            length_total: (hl * volume.sqrt().into()).into(),
+            over_time_total: ((hot * hl) * volume - (pot * pl) * volume).into(),
+            per_second_total: (hot * volume.map(f64::sqrt) - pot * volume.map(f64::sqrt)).into(),
+            length_total: (hl * volume.map(f64::sqrt)).into(),
         }
     }
 
@@ -173,9 +174,82 @@ impl PotionSerializableConfig {
         let volume = mix.volume();
 
         TotalEffect { 
-            over_time_total: ((pot * pl) * volume.into() - (hot * hl) * volume.into()).into(),
-            per_second_total: (pot * volume.sqrt().into() - hot * volume.sqrt().into()).into(),
-            length_total: (pl * volume.sqrt().into()).into(),
+            over_time_total: ((pot * pl) * volume - (hot * hl) * volume).into(),
+            per_second_total: (pot * volume.map(f64::sqrt) - hot * volume.map(f64::sqrt)).into(),
+            length_total: (pl * volume.map(f64::sqrt)).into(),
         }
     }    
-}
\ No newline at end of file
+
+    /// The same ingredient listed twice in the mix counts as one, its terms added up and its
+    /// factors multiplied
+    pub fn serialize_breakdown(&self, mix: &Mix) -> PotionBreakdownSerializable {
+        mix.effects()
+            .iter()
+            .map(|(effect, _, x)| {
+                let mut contributions: IndexMap<&str, Contribution> = IndexMap::default();
+
+                for ((name, _), contribution) in mix.named_ingredients_iter().zip(mix.contributions(effect)) {
+                    contributions
+                        .entry(name)
+                        .and_modify(|x| {
+                            x.term = x.term + contribution.term;
+                            x.factor = x.factor * contribution.factor;
+                        })
+                        .or_insert(contribution);
+                }
+
+                let contributions = contributions
+                    .into_iter()
+                    .map(|(name, x)| (name.to_string(), ContributionSerializable {
+                        term: x.term.into(),
+                        factor: x.factor.into(),
+                    }))
+                    .collect();
+
+                (x.code.clone(), contributions)
+            })
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::grimoire::{Character, Grimoire, Ingredient};
+    use grimoire2::standalone::OptimizedGrimoire;
+    use grimoire2::theoretical::Theoretical;
+
+    use super::*;
+
+    #[test]
+    fn test_breakdown() {
+        let mut leaf = Ingredient {
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        };
+        leaf.modifiers[Effect::DirectHealing].term = Theoretical::Known(2.);
+        let mut root = leaf.clone();
+        root.modifiers[Effect::DirectHealing].multiplier = Theoretical::Known(0.5);
+
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Leaf".to_string(), leaf);
+        grimoire.ingredients.insert("Root".to_string(), root);
+        let optimized = OptimizedGrimoire::from((&Character::default(), &grimoire));
+        let mix = Mix::new(&optimized, vec![(0, 1), (1, 2), (0, 1)]);
+
+        let config = PotionSerializableConfig { breakdown: true, ..PotionSerializableConfig::default() };
+        let potion = config.serialize_mix(&mix);
+        let breakdown = potion.breakdown.unwrap();
+
+        assert_eq!(breakdown.len(), mix.effects().len());
+        assert_eq!(breakdown["dh"].len(), 2);
+        // Leaf is listed twice, with a quarter of the mix each time
+        assert_eq!(Theoretical::<f64>::from(breakdown["dh"]["Leaf"].term).inner(), 1.);
+        assert_eq!(
+            Theoretical::from(breakdown["dh"]["Root"].factor),
+            Theoretical::Known(1. + 0.5 * 0.5f64.sqrt())
+        );
+        assert!(PotionSerializableConfig::default().serialize_mix(&mix).breakdown.is_none());
+    }
+}
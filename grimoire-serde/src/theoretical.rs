@@ -1,3 +1,6 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
 use serde::{Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::{Visitor, VariantAccess};
 use serde::de;
@@ -55,6 +58,37 @@ impl From<TheoreticalWrapper> for Theoretical<f64> {
     }
 }
 
+/// Same notation as in YAML: `2.4` or `! 2.4` for known values, `!? 2.4` for theories and
+/// `??` for unknown values
+impl FromStr for TheoreticalWrapper {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parse = |x: &str| x.trim().parse::<f64>().map_err(|_| format!("Bad value: {}", s));
+
+        if s == "??" {
+            Ok(TheoreticalWrapper::Unknown)
+        } else if let Some(x) = s.strip_prefix("!?") {
+            Ok(TheoreticalWrapper::Theory(parse(x)?))
+        } else if let Some(x) = s.strip_prefix("!!").or_else(|| s.strip_prefix('!')) {
+            Ok(TheoreticalWrapper::Known(parse(x)?))
+        } else {
+            Ok(TheoreticalWrapper::Known(parse(s)?))
+        }
+    }
+}
+
+impl Display for TheoreticalWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TheoreticalWrapper::Known(x) => write!(f, "{}", x),
+            TheoreticalWrapper::Theory(x) => write!(f, "!? {}", x),
+            TheoreticalWrapper::Unknown => write!(f, "??"),
+        }
+    }
+}
+
 impl Serialize for TheoreticalWrapper {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -78,82 +112,141 @@ impl<'de> Deserialize<'de> for TheoreticalWrapper {
     where
         D: Deserializer<'de>,
     {
-        struct TheoreticalWrapperVisitor;
+        deserializer.deserialize_any(TheoreticalWrapperVisitor { bools: false })
+    }
+}
 
-        impl<'de> Visitor<'de> for TheoreticalWrapperVisitor {
-            type Value = TheoreticalWrapper;
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a float value, ?? for default, !! <value> for known value or !? <value> for unknown value")
-            }
+/// Reads `bools` as known 1 and 0 when set
+struct TheoreticalWrapperVisitor {
+    bools: bool,
+}
 
-            fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
-                Ok(TheoreticalWrapper::Known(value))
-            }
+impl<'de> Visitor<'de> for TheoreticalWrapperVisitor {
+    type Value = TheoreticalWrapper;
 
-            fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
-                where
-                    E: de::Error, {
-                Ok(TheoreticalWrapper::Known(v as f64))
-            }
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a float value, ?? for default, !! <value> for known value or !? <value> for unknown value")
+    }
 
-            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
-                where
-                    E: de::Error, {
-                Ok(TheoreticalWrapper::Known(v as f64))
-            }
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+        where
+            E: de::Error, {
+        match self.bools {
+            true => Ok(TheoreticalWrapper::Known(if v { 1. } else { 0. })),
+            false => Err(de::Error::invalid_type(de::Unexpected::Bool(v), &self)),
+        }
+    }
 
-            fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E>
-                where
-                    E: de::Error, {
-                Ok(TheoreticalWrapper::Known(v as f64))
-            }
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(TheoreticalWrapper::Known(value))
+    }
 
+    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+        where
+            E: de::Error, {
+        Ok(TheoreticalWrapper::Known(v as f64))
+    }
 
-            fn visit_i128<E>(self,v:i128) -> Result<Self::Value,E>where E:de::Error, {
-                Ok(TheoreticalWrapper::Known(v as f64))
-            }
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error, {
+        Ok(TheoreticalWrapper::Known(v as f64))
+    }
 
-            fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
-                where
-                    E: de::Error, {
-                Ok(TheoreticalWrapper::Known(v as f64))
-            }
+    fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E>
+        where
+            E: de::Error, {
+        Ok(TheoreticalWrapper::Known(v as f64))
+    }
 
-            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
-                where
-                    E: de::Error, {
-                Ok(TheoreticalWrapper::Known(v as f64))
-            }
 
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-                where
-                    E: de::Error, {
-                match v {
-                    "??" => Ok(TheoreticalWrapper::Unknown),
-                    _ => Err(de::Error::custom(format!("Unknown value: {}", v)))
-                }
-            }
+    fn visit_i128<E>(self,v:i128) -> Result<Self::Value,E>where E:de::Error, {
+        Ok(TheoreticalWrapper::Known(v as f64))
+    }
 
-            fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
-                where
-                    A: de::EnumAccess<'de>, {
+    fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
+        where
+            E: de::Error, {
+        Ok(TheoreticalWrapper::Known(v as f64))
+    }
 
-                let (variant, values): (String, _) = data.variant()?;
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error, {
+        Ok(TheoreticalWrapper::Known(v as f64))
+    }
 
-                match variant.as_str() {
-                    "?" => Ok(
-                        values.newtype_variant().map(TheoreticalWrapper::Theory)
-                    )?,      
-                    "!" => Ok(values.newtype_variant().map(TheoreticalWrapper::Known))?,
-                    _ => Err(de::Error::unknown_variant(&variant, &["!", "?"]))
-                }
-                
-            }
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error, {
+        match v {
+            "??" => Ok(TheoreticalWrapper::Unknown),
+            _ => Err(de::Error::custom(format!("Unknown value: {}", v)))
+        }
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::EnumAccess<'de>, {
 
+        let (variant, values): (String, _) = data.variant()?;
+
+        match variant.as_str() {
+            "?" => Ok(
+                values.newtype_variant().map(TheoreticalWrapper::Theory)
+            )?,      
+            "!" => Ok(values.newtype_variant().map(TheoreticalWrapper::Known))?,
+            _ => Err(de::Error::unknown_variant(&variant, &["!", "?"]))
         }
+        
+    }
+
+    /// Formats without enums, such as JSON, write theories as `{"?": <value>}`
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::MapAccess<'de>, {
+
+        let (variant, value): (String, f64) = map.next_entry()?
+            .ok_or(de::Error::invalid_length(0, &self))?;
+
+        if map.next_key::<String>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+
+        match variant.as_str() {
+            "?" => Ok(TheoreticalWrapper::Theory(value)),
+            "!" => Ok(TheoreticalWrapper::Known(value)),
+            _ => Err(de::Error::unknown_variant(&variant, &["!", "?"]))
+        }
+    }
+}
+
+
+/// Alchemical weight of an ingredient. Weights were `true` or `false` before they were
+/// numeric, which still read as known 1 and 0
+#[derive(Debug, Clone, PartialEq, Copy, Default, Serialize)]
+#[serde(transparent)]
+pub struct WeightWrapper(pub TheoreticalWrapper);
+
+impl<'de> Deserialize<'de> for WeightWrapper {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TheoreticalWrapperVisitor { bools: true }).map(WeightWrapper)
+    }
+}
+
+impl From<Theoretical<f64>> for WeightWrapper {
+    fn from(value: Theoretical<f64>) -> Self {
+        Self(value.into())
+    }
+}
 
-        deserializer.deserialize_any(TheoreticalWrapperVisitor)
+impl From<WeightWrapper> for Theoretical<f64> {
+    fn from(value: WeightWrapper) -> Self {
+        value.0.into()
     }
 }
 
@@ -163,7 +256,7 @@ mod tests {
     use grimoire2::theoretical::Theoretical;
     use serde_yaml::{to_string, from_str};
 
-    use super::TheoreticalWrapper;
+    use super::{TheoreticalWrapper, WeightWrapper};
 
     #[test]
     fn test_serialize_known_f64() {
@@ -205,6 +298,23 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_from_str_and_display() {
+        for (input, expected) in [
+            ("2.4", TheoreticalWrapper::Known(2.4)),
+            ("! 2.4", TheoreticalWrapper::Known(2.4)),
+            (" !? 0.5 ", TheoreticalWrapper::Theory(0.5)),
+            ("??", TheoreticalWrapper::Unknown),
+        ] {
+            let actual: TheoreticalWrapper = input.parse().unwrap();
+            assert_eq!(actual, expected);
+            assert_eq!(actual.to_string().parse::<TheoreticalWrapper>().unwrap(), expected);
+        }
+
+        assert!("abc".parse::<TheoreticalWrapper>().is_err());
+        assert!("!?".parse::<TheoreticalWrapper>().is_err());
+    }
+
     #[test]
     fn test_deserialize_default() {
         let input = "??\n";
@@ -212,4 +322,23 @@ mod tests {
         let actual = from_str::<TheoreticalWrapper>(input).unwrap().to_theoretical(0.);
         assert_eq!(expected, actual);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_deserialize_map() {
+        let actual = from_str::<TheoreticalWrapper>("'?': 0.5\n").unwrap();
+        assert_eq!(actual, TheoreticalWrapper::Theory(0.5));
+
+        assert!(from_str::<TheoreticalWrapper>("'?': 0.5\n'!': 0.5\n").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_weight() {
+        let read = |x: &str| Theoretical::from(from_str::<WeightWrapper>(x).unwrap());
+
+        assert_eq!(read("true\n"), Theoretical::Known(1.));
+        assert_eq!(read("false\n"), Theoretical::Known(0.));
+        assert_eq!(read("2.5\n"), Theoretical::Known(2.5));
+        assert_eq!(read("!? 1.5\n"), Theoretical::Theory(1.5));
+        assert!(from_str::<TheoreticalWrapper>("true\n").is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use thiserror;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Could not open the database: {0}")]
+    Connection(String),
+    #[error("Could not upgrade the database schema: {0}")]
+    Migration(String),
+    #[error("Database query failed: {0}")]
+    Query(#[from] diesel::result::Error),
+    #[error("Unexpected value in the database: {0}")]
+    BadValue(String),
+    #[error("Ingredient {0} has values for effect number {1}, which is not in the effect catalogue")]
+    UnknownEffect(String, usize),
+    #[error("Could not store the update: {0}")]
+    History(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
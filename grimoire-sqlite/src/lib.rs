@@ -0,0 +1,6 @@
+pub mod error;
+pub mod models;
+pub mod schema;
+pub mod store;
+
+pub use store::GrimoireStore;
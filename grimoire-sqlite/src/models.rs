@@ -0,0 +1,216 @@
+use diesel::prelude::*;
+
+use crate::schema::*;
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = skills)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SkillRow {
+    pub name: String,
+    pub position: i32,
+    pub effectiveness: Option<f64>,
+    pub effectiveness_theory: bool,
+    pub parent: Option<String>,
+    pub parent_2: Option<String>,
+    pub category: String,
+    pub cost: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = ingredients)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct IngredientRow {
+    pub name: String,
+    pub position: i32,
+    pub skill: Option<String>,
+    pub weight: Option<f64>,
+    pub weight_theory: bool,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = ingredient_modifiers)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ModifierRow {
+    pub ingredient: String,
+    pub effect: String,
+    pub term: Option<f64>,
+    pub term_theory: bool,
+    pub multiplier: Option<f64>,
+    pub multiplier_theory: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = ingredient_tags)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct TagRow {
+    pub ingredient: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = ingredient_aliases)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AliasRow {
+    pub ingredient: String,
+    pub alias: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = characters)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct CharacterRow {
+    pub name: String,
+    pub position: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = character_clades)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct CharacterCladeRow {
+    pub character: String,
+    pub clade: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = character_skills)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct CharacterSkillRow {
+    pub character: String,
+    pub skill: String,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = clades)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct CladeRow {
+    pub name: String,
+    pub position: i32,
+    pub volume_multiplier: f64,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = effects)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EffectRow {
+    pub id: String,
+    pub position: i32,
+    pub code: String,
+    pub name: String,
+    pub category: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = rules)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RulesRow {
+    pub id: i32,
+    pub primary_points: i64,
+    pub secondary_points: i64,
+    pub tertiary_points: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = rulesets)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RulesetRow {
+    pub name: String,
+    pub position: i32,
+    pub in_effect: bool,
+    pub default_lore_effectiveness: f64,
+    pub advanced_potion_making: String,
+    pub advanced_potion_making_bonus: f64,
+    pub advanced_potion_making_steps: bool,
+    pub weight_offset: i64,
+    pub weight_per_volume: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = ruleset_clades)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RulesetCladeRow {
+    pub ruleset: String,
+    pub clade: String,
+    pub position: i32,
+    pub volume_multiplier: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = productions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ProductionRow {
+    pub ingredient: String,
+    pub position: i32,
+    pub yields: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = production_inputs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ProductionInputRow {
+    pub production: String,
+    pub input: String,
+    pub position: i32,
+    pub amount: i64,
+}
+
+/// The last evaluation of the recipe is kept as JSON
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = recipes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RecipeRow {
+    pub name: String,
+    pub position: i32,
+    pub character: Option<String>,
+    pub notes: String,
+    pub evaluation: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = recipe_ingredients)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RecipeIngredientRow {
+    pub recipe: String,
+    pub ingredient: String,
+    pub position: i32,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = recipe_tags)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RecipeTagRow {
+    pub recipe: String,
+    pub tag: String,
+}
+
+/// Provenance of a single value
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = observations)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ObservationRow {
+    /// `skill` or `ingredient`
+    pub subject: String,
+    pub name: String,
+    pub field: String,
+    pub source: Option<String>,
+    pub date: Option<String>,
+    pub confidence: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable)]
+#[diesel(table_name = update_history)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct HistoryRow {
+    pub id: i32,
+    pub applied_at: String,
+    pub update_json: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Insertable)]
+#[diesel(table_name = update_history)]
+pub struct NewHistoryRow {
+    pub update_json: String,
+}
@@ -0,0 +1,643 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use grimoire2::effect::EffectDefinition;
+use grimoire2::grimoire::{Character, Clade, Clades, Grimoire, Ingredient, Production, Recipe, Ruleset, Skill, SkillCategory, SkillPointRules};
+use grimoire2::modifier::Modifier;
+use grimoire2::provenance::Provenance;
+use grimoire2::theoretical::Theoretical;
+use grimoire_serde::modify::GrimoireUpdateSerializable;
+
+use crate::error::{Error, Result};
+use crate::models::*;
+use crate::schema::*;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../migrations");
+
+/// Prefix of the grimoire paths that point to a database
+pub const URL_PREFIX: &str = "sqlite://";
+
+const SKILL: &str = "skill";
+const INGREDIENT: &str = "ingredient";
+const EFFECTIVENESS: &str = "effectiveness";
+
+/// Grimoire kept in a SQLite database. The whole grimoire is read and written at once, the
+/// database only adds history and room for a grimoire shared by many people
+pub struct GrimoireStore {
+    connection: SqliteConnection,
+}
+
+impl GrimoireStore {
+    /// Opens the database, creating it if needed, and brings its schema up to date
+    pub fn open(path: &str) -> Result<Self> {
+        let mut connection = SqliteConnection::establish(path)
+            .map_err(|x| Error::Connection(x.to_string()))?;
+
+        diesel::sql_query("PRAGMA foreign_keys = ON").execute(&mut connection)?;
+
+        connection.run_pending_migrations(MIGRATIONS)
+            .map_err(|x| Error::Migration(x.to_string()))?;
+
+        Ok(Self { connection })
+    }
+
+    /// `sqlite://path` to `path`, `None` for anything else
+    pub fn path_from_url(url: &str) -> Option<&str> {
+        url.strip_prefix(URL_PREFIX)
+    }
+
+    pub fn load(&mut self) -> Result<Grimoire> {
+        let connection = &mut self.connection;
+        // Only the clades stored, not the default catalogue of a new grimoire
+        let mut grimoire = Grimoire { clades: Clades::default(), ..Grimoire::default() };
+
+        let mut provenance: HashMap<(String, String, String), Provenance> = observations::table
+            .select(ObservationRow::as_select())
+            .load(connection)?
+            .into_iter()
+            .map(|x| {
+                let date = x.date
+                    .map(|date| date.parse().map_err(|_| Error::BadValue(format!("date {date}"))))
+                    .transpose()?;
+                Ok(((x.subject, x.name, x.field), Provenance { source: x.source, date, confidence: x.confidence }))
+            })
+            .collect::<Result<_>>()?;
+        let mut take_provenance = |subject: &str, name: &str, field: &str| {
+            provenance.remove(&(subject.to_string(), name.to_string(), field.to_string()))
+        };
+
+        grimoire.effects = effects::table
+            .order(effects::position)
+            .select(EffectRow::as_select())
+            .load(connection)?
+            .into_iter()
+            .map(|x| (x.id, EffectDefinition { code: x.code, name: x.name, category: x.category }))
+            .collect();
+
+        for row in skills::table.order(skills::position).select(SkillRow::as_select()).load(connection)? {
+            let skill = Skill {
+                effectiveness: theoretical(row.effectiveness, row.effectiveness_theory),
+                parent: row.parent,
+                parent_2: row.parent_2,
+                category: category(&row.category)?,
+                cost: number(row.cost)?,
+                effectiveness_provenance: take_provenance(SKILL, &row.name, EFFECTIVENESS),
+            };
+            grimoire.skills.insert(row.name, skill);
+        }
+
+        for row in ingredients::table.order(ingredients::position).select(IngredientRow::as_select()).load(connection)? {
+            let ingredient = Ingredient {
+                skill: row.skill,
+                weight: theoretical(row.weight, row.weight_theory),
+                source: row.source,
+                ..Default::default()
+            };
+            grimoire.ingredients.insert(row.name, ingredient);
+        }
+
+        for row in ingredient_modifiers::table.select(ModifierRow::as_select()).load(connection)? {
+            let effects = &grimoire.effects;
+            let effect = effects.by_id(&row.effect)
+                .ok_or(Error::BadValue(format!("effect {}", row.effect)))?;
+            let code = |multiplier| effects.modifier_code(effect, multiplier).unwrap_or_default();
+
+            let modifier = Modifier {
+                term: theoretical(row.term, row.term_theory),
+                multiplier: theoretical(row.multiplier, row.multiplier_theory),
+                term_provenance: take_provenance(INGREDIENT, &row.ingredient, &code(false)),
+                multiplier_provenance: take_provenance(INGREDIENT, &row.ingredient, &code(true)),
+            };
+            ingredient(&mut grimoire, &row.ingredient)?.modifiers[effect] = modifier;
+        }
+
+        for row in ingredient_tags::table.select(TagRow::as_select()).load(connection)? {
+            ingredient(&mut grimoire, &row.ingredient)?.tags.insert(row.tag);
+        }
+
+        for row in ingredient_aliases::table.select(AliasRow::as_select()).load(connection)? {
+            ingredient(&mut grimoire, &row.ingredient)?.aliases.insert(row.alias);
+        }
+
+        for row in characters::table.order(characters::position).select(CharacterRow::as_select()).load(connection)? {
+            grimoire.characters.insert(row.name, Character::default());
+        }
+
+        for row in character_clades::table.select(CharacterCladeRow::as_select()).load(connection)? {
+            character(&mut grimoire, &row.character)?.clades.insert(row.clade);
+        }
+
+        for row in character_skills::table.select(CharacterSkillRow::as_select()).load(connection)? {
+            let value = number(row.value)?;
+            character(&mut grimoire, &row.character)?.skills.insert(row.skill, value);
+        }
+
+        for row in clades::table.order(clades::position).select(CladeRow::as_select()).load(connection)? {
+            grimoire.clades.insert(row.name, Clade::new(row.volume_multiplier, row.description));
+        }
+
+        grimoire.rules = rules::table
+            .select(RulesRow::as_select())
+            .first(connection)
+            .optional()?
+            .map(|x| Ok::<_, Error>(SkillPointRules::new(
+                number(x.primary_points)?,
+                number(x.secondary_points)?,
+                number(x.tertiary_points)?,
+            )))
+            .transpose()?;
+
+        for row in rulesets::table.order(rulesets::position).select(RulesetRow::as_select()).load(connection)? {
+            let ruleset = Ruleset {
+                default_lore_effectiveness: row.default_lore_effectiveness,
+                advanced_potion_making: row.advanced_potion_making,
+                advanced_potion_making_bonus: row.advanced_potion_making_bonus,
+                advanced_potion_making_steps: row.advanced_potion_making_steps,
+                clades: Default::default(),
+                weight_offset: number(row.weight_offset)?,
+                weight_per_volume: row.weight_per_volume,
+            };
+            if row.in_effect {
+                grimoire.selected_ruleset = Some(row.name.clone());
+            }
+            grimoire.rulesets.insert(row.name, ruleset);
+        }
+
+        for row in ruleset_clades::table.order(ruleset_clades::position).select(RulesetCladeRow::as_select()).load(connection)? {
+            grimoire.rulesets.get_mut(&row.ruleset)
+                .ok_or(Error::BadValue(format!("ruleset {}", row.ruleset)))?
+                .clades
+                .insert(row.clade, row.volume_multiplier);
+        }
+
+        for row in productions::table.order(productions::position).select(ProductionRow::as_select()).load(connection)? {
+            grimoire.productions.insert(row.ingredient, Production::new(Default::default(), number(row.yields)?));
+        }
+
+        for row in production_inputs::table.order(production_inputs::position).select(ProductionInputRow::as_select()).load(connection)? {
+            let amount = number(row.amount)?;
+            grimoire.productions.get_mut(&row.production)
+                .ok_or(Error::BadValue(format!("production {}", row.production)))?
+                .inputs
+                .insert(row.input, amount);
+        }
+
+        for row in recipes::table.order(recipes::position).select(RecipeRow::as_select()).load(connection)? {
+            let evaluation = row.evaluation
+                .map(|x| serde_json::from_str(&x))
+                .transpose()
+                .map_err(|_| Error::BadValue(format!("evaluation of recipe {}", row.name)))?;
+
+            grimoire.recipes.insert(row.name, Recipe {
+                character: row.character,
+                notes: row.notes,
+                evaluation,
+                ..Recipe::default()
+            });
+        }
+
+        for row in recipe_ingredients::table.order(recipe_ingredients::position).select(RecipeIngredientRow::as_select()).load(connection)? {
+            let amount = number(row.amount)?;
+            recipe(&mut grimoire, &row.recipe)?.ingredients.insert(row.ingredient, amount);
+        }
+
+        for row in recipe_tags::table.select(RecipeTagRow::as_select()).load(connection)? {
+            recipe(&mut grimoire, &row.recipe)?.tags.insert(row.tag);
+        }
+
+        Ok(grimoire)
+    }
+
+    /// Replaces the stored grimoire, recording the changes in the update history
+    pub fn save(&mut self, grimoire: &Grimoire) -> Result<()> {
+        let stored = self.load()?;
+
+        if stored == *grimoire {
+            return Ok(());
+        }
+
+        let update_json = serde_json::to_string(&GrimoireUpdateSerializable::diff(&stored, grimoire))?;
+
+        self.connection.transaction(|connection| {
+            clear(connection)?;
+            insert(connection, grimoire)?;
+
+            diesel::insert_into(update_history::table)
+                .values(NewHistoryRow { update_json })
+                .execute(connection)?;
+
+            Ok(())
+        })
+    }
+
+    /// Every save that changed the grimoire, oldest first
+    pub fn history(&mut self) -> Result<Vec<HistoryRow>> {
+        Ok(update_history::table
+            .order(update_history::id)
+            .select(HistoryRow::as_select())
+            .load(&mut self.connection)?)
+    }
+}
+
+fn clear(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::delete(observations::table).execute(connection)?;
+    diesel::delete(rules::table).execute(connection)?;
+    diesel::delete(recipe_tags::table).execute(connection)?;
+    diesel::delete(recipe_ingredients::table).execute(connection)?;
+    diesel::delete(recipes::table).execute(connection)?;
+    diesel::delete(production_inputs::table).execute(connection)?;
+    diesel::delete(productions::table).execute(connection)?;
+    diesel::delete(ruleset_clades::table).execute(connection)?;
+    diesel::delete(rulesets::table).execute(connection)?;
+    diesel::delete(clades::table).execute(connection)?;
+    diesel::delete(character_skills::table).execute(connection)?;
+    diesel::delete(character_clades::table).execute(connection)?;
+    diesel::delete(characters::table).execute(connection)?;
+    diesel::delete(ingredient_aliases::table).execute(connection)?;
+    diesel::delete(ingredient_tags::table).execute(connection)?;
+    diesel::delete(ingredient_modifiers::table).execute(connection)?;
+    diesel::delete(ingredients::table).execute(connection)?;
+    diesel::delete(skills::table).execute(connection)?;
+    diesel::delete(effects::table).execute(connection)?;
+    Ok(())
+}
+
+fn insert(connection: &mut SqliteConnection, grimoire: &Grimoire) -> Result<()> {
+    let mut observations: Vec<ObservationRow> = Vec::default();
+    let mut observe = |subject: &str, name: &str, field: &str, provenance: &Option<Provenance>| {
+        if let Some(x) = provenance {
+            observations.push(ObservationRow {
+                subject: subject.to_string(),
+                name: name.to_string(),
+                field: field.to_string(),
+                source: x.source.clone(),
+                date: x.date.map(|x| x.to_string()),
+                confidence: x.confidence.clone(),
+            });
+        }
+    };
+
+    let skills: Vec<SkillRow> = grimoire.skills.iter().enumerate().map(|(i, (name, skill))| {
+        observe(SKILL, name, EFFECTIVENESS, &skill.effectiveness_provenance);
+        let (effectiveness, effectiveness_theory) = split(skill.effectiveness);
+
+        SkillRow {
+            name: name.clone(),
+            position: i as i32,
+            effectiveness,
+            effectiveness_theory,
+            parent: skill.parent.clone(),
+            parent_2: skill.parent_2.clone(),
+            category: format!("{:?}", skill.category),
+            cost: skill.cost as i32,
+        }
+    }).collect();
+
+    let mut ingredients: Vec<IngredientRow> = Vec::default();
+    let mut modifiers: Vec<ModifierRow> = Vec::default();
+    let mut tags: Vec<TagRow> = Vec::default();
+    let mut aliases: Vec<AliasRow> = Vec::default();
+
+    for (i, (name, ingredient)) in grimoire.ingredients.iter().enumerate() {
+        let (weight, weight_theory) = split(ingredient.weight);
+        ingredients.push(IngredientRow {
+            name: name.clone(),
+            position: i as i32,
+            skill: ingredient.skill.clone(),
+            weight,
+            weight_theory,
+            source: ingredient.source.clone(),
+        });
+
+        for (effect, modifier) in ingredient.modifiers.iter() {
+            // Effects missing from the catalogue have nowhere to go, the grimoire is refused
+            // rather than losing values of them
+            let (Some(id), Some(definition)) = (grimoire.effects.id(effect), grimoire.effects.get(effect)) else {
+                if *modifier != Modifier::default() {
+                    return Err(Error::UnknownEffect(name.clone(), effect.0));
+                }
+                continue;
+            };
+            observe(INGREDIENT, name, &definition.code, &modifier.term_provenance);
+            observe(INGREDIENT, name, &definition.multiplier_code(), &modifier.multiplier_provenance);
+
+            let (term, term_theory) = split(modifier.term);
+            let (multiplier, multiplier_theory) = split(modifier.multiplier);
+
+            modifiers.push(ModifierRow {
+                ingredient: name.clone(),
+                effect: id.to_string(),
+                term,
+                term_theory,
+                multiplier,
+                multiplier_theory,
+            });
+        }
+
+        tags.extend(ingredient.tags.iter().map(|x| TagRow { ingredient: name.clone(), tag: x.clone() }));
+        aliases.extend(ingredient.aliases.iter().map(|x| AliasRow { ingredient: name.clone(), alias: x.clone() }));
+    }
+
+    let mut characters: Vec<CharacterRow> = Vec::default();
+    let mut character_clades: Vec<CharacterCladeRow> = Vec::default();
+    let mut character_skills: Vec<CharacterSkillRow> = Vec::default();
+
+    for (i, (name, character)) in grimoire.characters.iter().enumerate() {
+        characters.push(CharacterRow { name: name.clone(), position: i as i32 });
+        character_clades.extend(character.clades.iter().map(|x| CharacterCladeRow {
+            character: name.clone(),
+            clade: x.clone(),
+        }));
+        character_skills.extend(character.skills.iter().map(|(skill, value)| CharacterSkillRow {
+            character: name.clone(),
+            skill: skill.clone(),
+            value: *value as i32,
+        }));
+    }
+
+    let clades: Vec<CladeRow> = grimoire.clades.iter().enumerate().map(|(i, (name, clade))| CladeRow {
+        name: name.clone(),
+        position: i as i32,
+        volume_multiplier: clade.volume_multiplier,
+        description: clade.description.clone(),
+    }).collect();
+
+    let effects: Vec<EffectRow> = grimoire.effects.iter().map(|(effect, id, x)| EffectRow {
+        id: id.to_string(),
+        position: effect.0 as i32,
+        code: x.code.clone(),
+        name: x.name.clone(),
+        category: x.category.clone(),
+    }).collect();
+
+    let mut rulesets: Vec<RulesetRow> = Vec::default();
+    let mut ruleset_clades: Vec<RulesetCladeRow> = Vec::default();
+
+    for (i, (name, ruleset)) in grimoire.rulesets.iter().enumerate() {
+        rulesets.push(RulesetRow {
+            name: name.clone(),
+            position: i as i32,
+            in_effect: grimoire.selected_ruleset.as_ref() == Some(name),
+            default_lore_effectiveness: ruleset.default_lore_effectiveness,
+            advanced_potion_making: ruleset.advanced_potion_making.clone(),
+            advanced_potion_making_bonus: ruleset.advanced_potion_making_bonus,
+            advanced_potion_making_steps: ruleset.advanced_potion_making_steps,
+            weight_offset: ruleset.weight_offset as i64,
+            weight_per_volume: ruleset.weight_per_volume,
+        });
+        ruleset_clades.extend(ruleset.clades.iter().enumerate().map(|(i, (clade, x))| RulesetCladeRow {
+            ruleset: name.clone(),
+            clade: clade.clone(),
+            position: i as i32,
+            volume_multiplier: *x,
+        }));
+    }
+
+    let mut productions: Vec<ProductionRow> = Vec::default();
+    let mut production_inputs: Vec<ProductionInputRow> = Vec::default();
+
+    for (i, (name, production)) in grimoire.productions.iter().enumerate() {
+        productions.push(ProductionRow {
+            ingredient: name.clone(),
+            position: i as i32,
+            yields: production.yields as i64,
+        });
+        production_inputs.extend(production.inputs.iter().enumerate().map(|(i, (input, x))| ProductionInputRow {
+            production: name.clone(),
+            input: input.clone(),
+            position: i as i32,
+            amount: *x as i64,
+        }));
+    }
+
+    let mut recipes: Vec<RecipeRow> = Vec::default();
+    let mut recipe_ingredients: Vec<RecipeIngredientRow> = Vec::default();
+    let mut recipe_tags: Vec<RecipeTagRow> = Vec::default();
+
+    for (i, (name, recipe)) in grimoire.recipes.iter().enumerate() {
+        recipes.push(RecipeRow {
+            name: name.clone(),
+            position: i as i32,
+            character: recipe.character.clone(),
+            notes: recipe.notes.clone(),
+            evaluation: recipe.evaluation
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|_| Error::BadValue(format!("evaluation of recipe {name}")))?,
+        });
+        recipe_ingredients.extend(recipe.ingredients.iter().enumerate().map(|(i, (ingredient, x))| RecipeIngredientRow {
+            recipe: name.clone(),
+            ingredient: ingredient.clone(),
+            position: i as i32,
+            amount: *x as i64,
+        }));
+        recipe_tags.extend(recipe.tags.iter().map(|x| RecipeTagRow { recipe: name.clone(), tag: x.clone() }));
+    }
+
+    diesel::insert_into(effects::table).values(&effects).execute(connection)?;
+    diesel::insert_into(skills::table).values(&skills).execute(connection)?;
+    diesel::insert_into(ingredients::table).values(&ingredients).execute(connection)?;
+    diesel::insert_into(ingredient_modifiers::table).values(&modifiers).execute(connection)?;
+    diesel::insert_into(ingredient_tags::table).values(&tags).execute(connection)?;
+    diesel::insert_into(ingredient_aliases::table).values(&aliases).execute(connection)?;
+    diesel::insert_into(characters::table).values(&characters).execute(connection)?;
+    diesel::insert_into(character_clades::table).values(&character_clades).execute(connection)?;
+    diesel::insert_into(character_skills::table).values(&character_skills).execute(connection)?;
+    diesel::insert_into(clades::table).values(&clades).execute(connection)?;
+    diesel::insert_into(rulesets::table).values(&rulesets).execute(connection)?;
+    diesel::insert_into(ruleset_clades::table).values(&ruleset_clades).execute(connection)?;
+    diesel::insert_into(productions::table).values(&productions).execute(connection)?;
+    diesel::insert_into(production_inputs::table).values(&production_inputs).execute(connection)?;
+    diesel::insert_into(recipes::table).values(&recipes).execute(connection)?;
+    diesel::insert_into(recipe_ingredients::table).values(&recipe_ingredients).execute(connection)?;
+    diesel::insert_into(recipe_tags::table).values(&recipe_tags).execute(connection)?;
+    diesel::insert_into(observations::table).values(&observations).execute(connection)?;
+
+    if let Some(x) = grimoire.rules {
+        diesel::insert_into(rules::table)
+            .values(RulesRow {
+                id: 1,
+                primary_points: x.primary as i64,
+                secondary_points: x.secondary as i64,
+                tertiary_points: x.tertiary as i64,
+            })
+            .execute(connection)?;
+    }
+
+    Ok(())
+}
+
+/// Value and whether it is a theory, the value is `None` when unknown
+fn split(value: Theoretical<f64>) -> (Option<f64>, bool) {
+    match value {
+        Theoretical::Known(x) => (Some(x), false),
+        Theoretical::Theory(x) => (Some(x), true),
+        Theoretical::Unknown => (None, false),
+    }
+}
+
+fn theoretical(value: Option<f64>, theory: bool) -> Theoretical<f64> {
+    match (value, theory) {
+        (Some(x), false) => Theoretical::Known(x),
+        (Some(x), true) => Theoretical::Theory(x),
+        (None, _) => Theoretical::Unknown,
+    }
+}
+
+fn category(name: &str) -> Result<SkillCategory> {
+    match name {
+        "Primary" => Ok(SkillCategory::Primary),
+        "Secondary" => Ok(SkillCategory::Secondary),
+        "Tertiary" => Ok(SkillCategory::Tertiary),
+        other => Err(Error::BadValue(format!("skill category {other}"))),
+    }
+}
+
+fn number<T: TryFrom<i64>>(value: impl Into<i64>) -> Result<T> {
+    let value = value.into();
+    T::try_from(value).map_err(|_| Error::BadValue(format!("number {value}")))
+}
+
+fn recipe<'a>(grimoire: &'a mut Grimoire, name: &str) -> Result<&'a mut Recipe> {
+    grimoire.recipes.get_mut(name).ok_or(Error::BadValue(format!("recipe {name}")))
+}
+
+fn ingredient<'a>(grimoire: &'a mut Grimoire, name: &str) -> Result<&'a mut Ingredient> {
+    grimoire.ingredients.get_mut(name).ok_or(Error::BadValue(format!("ingredient {name}")))
+}
+
+fn character<'a>(grimoire: &'a mut Grimoire, name: &str) -> Result<&'a mut Character> {
+    grimoire.characters.get_mut(name).ok_or(Error::BadValue(format!("character {name}")))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::grimoire::Evaluation;
+    use grimoire2::modify::GrimoireUpdate;
+    use grimoire2::modify::command::Commands;
+    use grimoire2::modify::character::CharacterUpdate;
+    use grimoire2::modify::ingredient::IngredientUpdate;
+    use grimoire2::modify::skill::SkillUpdate;
+
+    use super::*;
+
+    fn grimoire() -> Grimoire {
+        let mut grimoire = Grimoire::default();
+
+        GrimoireUpdate::default()
+            .skill(
+                "Herbology", SkillUpdate::default()
+                    .set_effectiveness(Theoretical::Theory(0.5))
+                    .set_effectiveness_provenance(Some(Provenance::new("wiki")))
+                    .clone()
+            )
+            .skill("Botany", SkillUpdate::default().set_parent("Herbology").clone())
+            .ingredient(
+                "Leaf", IngredientUpdate::default()
+                    .set_skill("Botany")
+                    .set_term(Effect::DirectHealing, Theoretical::Known(1.5))
+                    .set_multiplier(Effect::DirectHealing, Theoretical::Theory(-0.2))
+                    .set_term_provenance(Effect::DirectHealing, Some(Provenance {
+                        source: Some("Tashka".to_string()),
+                        date: "2026-10-19".parse().ok(),
+                        confidence: None,
+                    }))
+                    .add_tag("herb")
+                    .add_alias("Leef")
+                    .clone()
+            )
+            .effect("Stamina", EffectDefinition::new("st", "Stamina", "buff"))
+            .ingredient(
+                "Water", IngredientUpdate::default()
+                    .set_term(Effect(7), Theoretical::Known(2.))
+                    .set_multiplier_provenance(Effect(7), Some(Provenance::new("Well")))
+                    .clone()
+            )
+            .character(
+                "Tashka", CharacterUpdate::default()
+                    .add_clade("Alchemist")
+                    .set_skill("Botany", 50)
+                    .clone()
+            )
+            .update(&mut grimoire);
+
+        grimoire.rulesets.insert("patched".to_string(), Ruleset {
+            clades: [("Alchemist".to_string(), 1.15)].into_iter().collect(),
+            ..Ruleset::default()
+        });
+        grimoire.rulesets.insert("default".to_string(), Ruleset::default());
+        grimoire.selected_ruleset = Some("patched".to_string());
+        grimoire.recipes.insert("Salve".to_string(), Recipe {
+            character: Some("Tashka".to_string()),
+            notes: "Brew at dawn".to_string(),
+            tags: ["cheap".to_string(), "healing".to_string()].into_iter().collect(),
+            evaluation: Some(Evaluation {
+                character: "Tashka".to_string(),
+                volume: Theoretical::Known(2.),
+                effects: [("dh".to_string(), Theoretical::Theory(1.5))].into_iter().collect(),
+            }),
+            ..Recipe::new([("Water".to_string(), 2), ("Leaf".to_string(), 1)].into_iter().collect())
+        });
+        grimoire.productions.insert("Leaf".to_string(), Production::new(
+            [("Seed".to_string(), 2), ("Water".to_string(), 1)].into_iter().collect(), 3,
+        ));
+        grimoire.rules = Some(SkillPointRules::new(1200, 600, 400));
+        grimoire.skills["Botany"].category = SkillCategory::Tertiary;
+        grimoire.ingredients["Water"].source = Some("Well".to_string());
+
+        grimoire
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut store = GrimoireStore::open(":memory:").unwrap();
+
+        assert_eq!(store.load().unwrap(), Grimoire { clades: Clades::default(), ..Grimoire::default() });
+
+        store.save(&grimoire()).unwrap();
+
+        assert_eq!(store.load().unwrap(), grimoire());
+        assert_eq!(store.load().unwrap().ruleset(), grimoire().ruleset());
+    }
+
+    #[test]
+    fn test_round_trip_effects_missing_from_catalogue() {
+        let mut store = GrimoireStore::open(":memory:").unwrap();
+        let missing = Effect(grimoire().effects.len() + 1);
+
+        let mut empty = grimoire();
+        empty.ingredients["Water"].modifiers[missing] = Modifier::default();
+
+        store.save(&empty).unwrap();
+        assert_eq!(store.load().unwrap(), empty);
+
+        let mut valued = grimoire();
+        valued.ingredients["Water"].modifiers[missing].term = Theoretical::Known(1.);
+
+        let result = store.save(&valued);
+        assert!(matches!(result, Err(Error::UnknownEffect(name, x)) if name == "Water" && x == missing.0));
+        assert_eq!(store.load().unwrap(), grimoire());
+        assert_eq!(store.history().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut store = GrimoireStore::open(":memory:").unwrap();
+        let mut changed = grimoire();
+        changed.ingredients["Leaf"].weight = Theoretical::Theory(1.5);
+
+        store.save(&grimoire()).unwrap();
+        store.save(&grimoire()).unwrap();
+        store.save(&changed).unwrap();
+
+        let history = store.history().unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert!(history[1].update_json.contains("Leaf"), "{}", history[1].update_json);
+        assert_eq!(store.load().unwrap(), changed);
+    }
+}
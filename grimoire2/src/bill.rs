@@ -0,0 +1,253 @@
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
+
+use crate::error::{Error, Result};
+use crate::grimoire::{Production, Productions};
+
+/// Prices and stock of ingredients, which decide whether an ingredient is taken from stock,
+/// bought or made from others
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Supplies {
+    /// Price of a unit by ingredient, ingredients without one are left out of the cost
+    pub prices: IndexMap<String, f64>,
+    /// Units at hand by ingredient, used before anything is bought or made
+    pub stock: IndexMap<String, u64>,
+}
+
+/// Everything that goes into brewing a mix
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Bill {
+    /// Units taken from stock
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub stock: IndexMap<String, u64>,
+    /// Units made from other ingredients, whole batches
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub made: IndexMap<String, u64>,
+    /// Units bought or gathered
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub bought: IndexMap<String, u64>,
+    /// Units made beyond what the mix needs, since batches can't be split
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub surplus: IndexMap<String, u64>,
+    /// Price of everything bought
+    pub cost: f64,
+    /// Ingredients bought without a price, which `cost` leaves out
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unpriced: Vec<String>,
+}
+
+impl Bill {
+    fn buy(&mut self, name: &str, amount: u64, price: Option<f64>) {
+        *self.bought.entry(name.to_string()).or_default() += amount;
+
+        match price {
+            Some(x) => self.cost += x * amount as f64,
+            None if !self.unpriced.iter().any(|x| x == name) => self.unpriced.push(name.to_string()),
+            None => {},
+        }
+    }
+}
+
+/// Works out the bill of a mix, making an ingredient whenever its inputs cost less than
+/// buying it
+#[derive(Debug, Clone, Default)]
+pub struct Planner {
+    productions: Productions,
+    supplies: Supplies,
+}
+
+#[derive(Clone)]
+struct State {
+    bill: Bill,
+    stock: IndexMap<String, u64>,
+}
+
+impl Planner {
+    pub fn new(productions: Productions, supplies: Supplies) -> Self {
+        Self {
+            productions,
+            supplies,
+        }
+    }
+
+    /// Bill of the `ingredients` of a mix with their amounts. Fails if the productions form a
+    /// cycle
+    pub fn plan<'a>(&self, ingredients: impl IntoIterator<Item = (&'a str, u64)>) -> Result<Bill> {
+        let mut state = State {
+            bill: Bill::default(),
+            stock: self.supplies.stock.clone(),
+        };
+
+        for (name, amount) in ingredients {
+            self.acquire(name, amount, &mut state, &mut Vec::default())?;
+        }
+
+        state.bill.surplus.retain(|_, x| *x > 0);
+        Ok(state.bill)
+    }
+
+    fn acquire(&self, name: &str, amount: u64, state: &mut State, path: &mut Vec<String>) -> Result<()> {
+        if let Some(start) = path.iter().position(|x| x == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(Error::ProductionCycle(cycle));
+        }
+
+        let mut amount = amount;
+
+        // What's left of earlier batches comes first, it's made already
+        if let Some(surplus) = state.bill.surplus.get_mut(name) {
+            let taken = amount.min(*surplus);
+            *surplus -= taken;
+            amount -= taken;
+        }
+
+        if let Some(stock) = state.stock.get_mut(name) {
+            let taken = amount.min(*stock);
+            if taken > 0 {
+                *stock -= taken;
+                *state.bill.stock.entry(name.to_string()).or_default() += taken;
+                amount -= taken;
+            }
+        }
+
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let price = self.supplies.prices.get(name).copied();
+
+        let Some(production) = self.productions.get(name) else {
+            state.bill.buy(name, amount, price);
+            return Ok(());
+        };
+
+        path.push(name.to_string());
+        let mut made = state.clone();
+        let can_make = self.make(name, production, amount, &mut made, path)?;
+        path.pop();
+
+        // Made ingredients are only as cheap as they seem if all their inputs have prices
+        let cheaper = match price {
+            None => true,
+            Some(x) => made.bill.unpriced.len() == state.bill.unpriced.len()
+                && made.bill.cost - state.bill.cost < x * amount as f64,
+        };
+
+        match can_make && cheaper {
+            true => *state = made,
+            false => state.bill.buy(name, amount, price),
+        }
+
+        Ok(())
+    }
+
+    /// Makes `amount` units in whole batches, false if a batch yields nothing
+    fn make(
+        &self,
+        name: &str,
+        production: &Production,
+        amount: u64,
+        state: &mut State,
+        path: &mut Vec<String>,
+    ) -> Result<bool> {
+        let Some(batches) = production.batches(amount) else {
+            return Ok(false);
+        };
+
+        let units = batches * production.yields;
+        *state.bill.made.entry(name.to_string()).or_default() += units;
+        *state.bill.surplus.entry(name.to_string()).or_default() += units - amount;
+
+        for (input, count) in &production.inputs {
+            self.acquire(input, count * batches, state, path)?;
+        }
+
+        Ok(true)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+
+    fn productions() -> Productions {
+        [
+            ("Salvia Oil".to_string(), Production::new(
+                [("Salvia".to_string(), 3), ("Water".to_string(), 1)].into_iter().collect(), 2,
+            )),
+            ("Salvia".to_string(), Production::new(
+                [("Salvia Seeds".to_string(), 1)].into_iter().collect(), 1,
+            )),
+        ].into_iter().collect()
+    }
+
+    fn units(x: &[(&str, u64)]) -> IndexMap<String, u64> {
+        x.iter().map(|(n, a)| (n.to_string(), *a)).collect()
+    }
+
+    fn supplies(oil: f64) -> Supplies {
+        Supplies {
+            prices: [
+                ("Salvia Oil".to_string(), oil),
+                ("Salvia".to_string(), 2.),
+                ("Salvia Seeds".to_string(), 1.),
+                ("Water".to_string(), 0.5),
+            ].into_iter().collect(),
+            stock: [("Water".to_string(), 2)].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn test_expand_without_supplies() {
+        let planner = Planner::new(productions(), Supplies::default());
+
+        let bill = planner.plan([("Salvia Oil", 3), ("Water", 1)]).unwrap();
+
+        assert_eq!(bill.made, units(&[("Salvia Oil", 4), ("Salvia", 6)]));
+        assert_eq!(bill.bought, units(&[("Salvia Seeds", 6), ("Water", 3)]));
+        assert_eq!(bill.surplus, units(&[("Salvia Oil", 1)]));
+        assert_eq!(bill.unpriced, vec!["Salvia Seeds", "Water"]);
+    }
+
+    #[test]
+    fn test_make_when_cheaper() {
+        let planner = Planner::new(productions(), supplies(10.));
+
+        let bill = planner.plan([("Salvia Oil", 2)]).unwrap();
+
+        // Three salvia from seeds at 1 each, the water from stock
+        assert_eq!(bill.made["Salvia Oil"], 2);
+        assert_eq!(bill.stock, units(&[("Water", 1)]));
+        assert!(approx_eq!(f64, bill.cost, 3., epsilon = 0.0001), "{}", bill.cost);
+        assert!(bill.unpriced.is_empty());
+    }
+
+    #[test]
+    fn test_buy_when_cheaper() {
+        let planner = Planner::new(productions(), supplies(1.));
+
+        let bill = planner.plan([("Salvia Oil", 2)]).unwrap();
+
+        assert!(bill.made.is_empty());
+        assert_eq!(bill.bought, units(&[("Salvia Oil", 2)]));
+        assert!(approx_eq!(f64, bill.cost, 2., epsilon = 0.0001), "{}", bill.cost);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut productions = productions();
+        productions.insert("Salvia Seeds".to_string(), Production::new(
+            [("Salvia".to_string(), 1)].into_iter().collect(), 1,
+        ));
+        let planner = Planner::new(productions, Supplies::default());
+
+        let result = planner.plan([("Salvia Oil", 1)]);
+
+        assert!(matches!(result, Err(Error::ProductionCycle(x)) if x == vec!["Salvia", "Salvia Seeds", "Salvia"]));
+    }
+}
@@ -1,13 +1,236 @@
-use serde::{Serialize, Deserialize};
-use strum::{EnumCount, EnumIter};
-
-#[derive(EnumCount, EnumIter, Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
-pub enum Effect {
-    DirectHealing,
-    DirectPoison,
-    HealingOverTime,
-    PoisonOverTime,
-    HealingLength,
-    PoisonLength,
-    Alcohol,
+use std::fmt::Debug;
+
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+/// Names of the effects every grimoire starts with, in the order of their indices
+const BUILTIN: [(&str, &str, &str, &str); 7] = [
+    ("DirectHealing", "dh", "Direct healing", "healing"),
+    ("DirectPoison", "dp", "Direct poison", "poison"),
+    ("HealingOverTime", "hot", "Healing over time", "healing"),
+    ("PoisonOverTime", "pot", "Poison over time", "poison"),
+    ("HealingLength", "hl", "Healing length", "healing"),
+    ("PoisonLength", "pl", "Poison length", "poison"),
+    ("Alcohol", "a", "Alcohol", "alcohol"),
+];
+
+/// Index of an effect in the catalogue of a grimoire. Indices never change once assigned, so
+/// that modifiers stored by index keep their meaning
+#[derive(Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Effect(pub usize);
+
+#[allow(non_upper_case_globals)]
+impl Effect {
+    pub const DirectHealing: Effect = Effect(0);
+    pub const DirectPoison: Effect = Effect(1);
+    pub const HealingOverTime: Effect = Effect(2);
+    pub const PoisonOverTime: Effect = Effect(3);
+    pub const HealingLength: Effect = Effect(4);
+    pub const PoisonLength: Effect = Effect(5);
+    pub const Alcohol: Effect = Effect(6);
+
+    fn builtin_id(&self) -> Option<&'static str> {
+        BUILTIN.get(self.0).map(|x| x.0)
+    }
+}
+
+impl Debug for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.builtin_id() {
+            Some(x) => write!(f, "{x}"),
+            None => write!(f, "Effect({})", self.0),
+        }
+    }
+}
+
+/// Built-in effects are written by name, as they were when they were the only ones, and the
+/// rest by index
+impl Serialize for Effect {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.builtin_id() {
+            Some(x) => serializer.serialize_str(x),
+            None => serializer.serialize_u64(self.0 as u64),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Effect {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Index(usize),
+            Name(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Index(x) => Ok(Effect(x)),
+            Repr::Name(x) => BUILTIN.iter()
+                .position(|(id, ..)| *id == x)
+                .map(Effect)
+                .ok_or_else(|| serde::de::Error::custom(format!("Unknown effect: {x}"))),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectDefinition {
+    /// Short name of the term of the effect, such as `dh`. The multiplier is named the same
+    /// with an `m` in front
+    pub code: String,
+    pub name: String,
+    /// Free-form group of the effect, such as `healing` or `buff`
+    pub category: String,
+}
+
+impl EffectDefinition {
+    pub fn new(code: &str, name: &str, category: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            name: name.to_string(),
+            category: category.to_string(),
+        }
+    }
+
+    pub fn multiplier_code(&self) -> String {
+        format!("m{}", self.code)
+    }
+}
+
+
+/// Catalogue of effects keyed by id, in the order of their indices. Effects are only ever
+/// added or redefined, never removed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Effects(IndexMap<String, EffectDefinition>);
+
+impl Effects {
+    /// The effects known before the grimoire had a catalogue of its own
+    pub fn builtin() -> Self {
+        Self(BUILTIN.iter()
+            .map(|(id, code, name, category)| (id.to_string(), EffectDefinition::new(code, name, category)))
+            .collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Effect, &str, &EffectDefinition)> {
+        self.0.iter().enumerate().map(|(i, (id, x))| (Effect(i), id.as_str(), x))
+    }
+
+    pub fn get(&self, effect: Effect) -> Option<&EffectDefinition> {
+        self.0.get_index(effect.0).map(|x| x.1)
+    }
+
+    pub fn id(&self, effect: Effect) -> Option<&str> {
+        self.0.get_index(effect.0).map(|x| x.0.as_str())
+    }
+
+    pub fn by_id(&self, id: &str) -> Option<Effect> {
+        self.0.get_index_of(id).map(Effect)
+    }
+
+    pub fn by_code(&self, code: &str) -> Option<Effect> {
+        self.0.values().position(|x| x.code == code).map(Effect)
+    }
+
+    /// Adds the effect, or redefines it in place if the id is known
+    pub fn insert(&mut self, id: &str, definition: EffectDefinition) -> Effect {
+        Effect(self.0.insert_full(id.to_string(), definition).0)
+    }
+
+    /// Code of every modifier value with its effect and whether it's the multiplier, terms
+    /// first
+    pub fn modifier_codes(&self) -> Vec<(String, Effect, bool)> {
+        self.iter()
+            .flat_map(|(effect, _, x)| [
+                (x.code.clone(), effect, false),
+                (x.multiplier_code(), effect, true),
+            ])
+            .collect()
+    }
+
+    /// Effect and whether it's the multiplier for a code such as `dh` or `mdh`
+    pub fn modifier(&self, code: &str) -> Option<(Effect, bool)> {
+        self.by_code(code).map(|x| (x, false))
+            .or_else(|| code.strip_prefix('m').and_then(|x| self.by_code(x)).map(|x| (x, true)))
+    }
+
+    pub fn modifier_code(&self, effect: Effect, multiplier: bool) -> Option<String> {
+        self.get(effect).map(|x| match multiplier {
+            true => x.multiplier_code(),
+            false => x.code.clone(),
+        })
+    }
+}
+
+impl FromIterator<(String, EffectDefinition)> for Effects {
+    fn from_iter<T: IntoIterator<Item = (String, EffectDefinition)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use proptest::strategy::Strategy;
+    use proptest::sample::select;
+    use super::*;
+
+    pub fn effects_strategy() -> impl Strategy<Value = Effects> {
+        select(vec![false, true]).prop_map(|custom| {
+            let mut effects = Effects::builtin();
+            if custom {
+                effects.insert("Stamina", EffectDefinition::new("st", "Stamina", "buff"));
+            }
+            effects
+        })
+    }
+
+    #[test]
+    fn test_builtin_codes() {
+        let effects = Effects::builtin();
+
+        assert_eq!(effects.modifier("dh"), Some((Effect::DirectHealing, false)));
+        assert_eq!(effects.modifier("mhot"), Some((Effect::HealingOverTime, true)));
+        assert_eq!(effects.modifier("ma"), Some((Effect::Alcohol, true)));
+        assert_eq!(effects.modifier("x"), None);
+        assert_eq!(effects.modifier_codes().len(), 14);
+        assert_eq!(effects.id(Effect::PoisonLength), Some("PoisonLength"));
+    }
+
+    #[test]
+    fn test_insert_keeps_indices() {
+        let mut effects = Effects::builtin();
+        let stamina = effects.insert("Stamina", EffectDefinition::new("st", "Stamina", "buff"));
+
+        assert_eq!(stamina, Effect(7));
+        assert_eq!(effects.modifier("mst"), Some((stamina, true)));
+
+        let renamed = effects.insert("DirectHealing", EffectDefinition::new("dh", "Healing", "healing"));
+        assert_eq!(renamed, Effect::DirectHealing);
+        assert_eq!(effects.len(), 8);
+    }
+
+    #[test]
+    fn test_serde() {
+        assert_eq!(serde_json::to_string(&Effect::Alcohol).unwrap(), "\"Alcohol\"");
+        assert_eq!(serde_json::to_string(&Effect(9)).unwrap(), "9");
+        assert_eq!(serde_json::from_str::<Effect>("\"HealingLength\"").unwrap(), Effect::HealingLength);
+        assert_eq!(serde_json::from_str::<Effect>("9").unwrap(), Effect(9));
+        assert!(serde_json::from_str::<Effect>("\"Mana\"").is_err());
+    }
 }
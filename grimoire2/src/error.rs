@@ -1,11 +1,29 @@
 use thiserror;
 
+use crate::grimoire::SkillCategory;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("The value of a skill must be between 0 and 100, but {0} is {1}")]
     SkillValueBound(String, u8),
     #[error("Ingredient not found: {0}")]
     IngredientNotFound(String),
+    #[error("Ingredient name {0} is ambiguous, it may be any of: {}", .1.join(", "))]
+    AmbiguousIngredient(String, Vec<String>),
+    #[error("Alias {0} is already a name or alias of another ingredient")]
+    AliasConflict(String),
+    #[error("Unknown ruleset: {0}")]
+    UnknownRuleset(String),
+    #[error("Productions form a cycle: {}", .0.join(" -> "))]
+    ProductionCycle(Vec<String>),
+    #[error("Unknown clade: {0}")]
+    UnknownClade(String),
+    #[error("{spent} skill points are spent on {category:?} skills, but only {cap} are available")]
+    SkillPointBudget { category: SkillCategory, spent: u32, cap: u32 },
+    #[error("Unknown grimoire schema: {0}")]
+    UnknownSchema(String),
+    #[error("Grimoire schema version {0} is newer than the supported version {1}, please update alrust")]
+    UnsupportedSchemaVersion(u32, u32),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
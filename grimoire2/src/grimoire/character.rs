@@ -4,7 +4,8 @@ use std::{
 };
 use serde::{Serialize, Deserialize};
 
-use super::Skills;
+use super::{Grimoire, Ruleset, SkillCategory, Skills};
+use crate::error::{Error, Result};
 use crate::theoretical::Theoretical;
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -18,11 +19,11 @@ impl Character {
         Self { clades, skills }
     }
 
-    pub fn lore_multiplier(&self, skills: &Skills, skill: &str) -> Theoretical<f64> {
+    pub fn lore_multiplier(&self, skills: &Skills, skill: &str, ruleset: &Ruleset) -> Theoretical<f64> {
         let effectiveness = skills.get(skill).cloned().unwrap_or_default().effectiveness;
         let value = self.skill(skills, skill);
 
-        Theoretical::from(1.) + effectiveness.default_theory(0.66666) * Theoretical::from(value as f64 / 100.)
+        Theoretical::from(1.) + effectiveness.default_theory(ruleset.default_lore_effectiveness) * Theoretical::from(value as f64 / 100.)
     }
 
     pub fn raw_skill(&self, skill: &str) -> u8 {
@@ -51,12 +52,113 @@ impl Character {
 
         min(this_skill_value, min_parent)
     }
+
+    /// Returns a copy of the character with the skill raised to `value`. Parent skills that
+    /// are lower than `value` are raised as well, since they would cap the skill otherwise.
+    pub fn with_skill_raised(&self, skills: &Skills, skill: &str, value: u8) -> Character {
+        let mut result = self.clone();
+        result.raise_skill(skills, skill, value, &mut HashSet::default());
+        result
+    }
+
+    /// Number of skill levels `other` has on top of this character
+    pub fn levels_to(&self, other: &Character) -> u32 {
+        other.skills
+            .iter()
+            .map(|(name, value)| value.saturating_sub(self.raw_skill(name)) as u32)
+            .sum()
+    }
+
+    /// Number of skill points `other` has spent on top of this character
+    pub fn points_to(&self, other: &Character, skills: &Skills) -> u32 {
+        other.skills
+            .iter()
+            .map(|(name, value)| {
+                let cost = skills.get(name).map(|x| x.cost).unwrap_or(1) as u32;
+                value.saturating_sub(self.raw_skill(name)) as u32 * cost
+            })
+            .sum()
+    }
+
+    /// Skill points spent on each category of skills
+    pub fn points_spent(&self, skills: &Skills) -> HashMap<SkillCategory, u32> {
+        let mut result = HashMap::default();
+
+        for (name, value) in &self.skills {
+            let skill = skills.get(name).cloned().unwrap_or_default();
+            *result.entry(skill.category).or_default() += *value as u32 * skill.cost as u32;
+        }
+
+        result
+    }
+
+    /// Everything that makes this character impossible to have in the game
+    pub fn violations(&self, grimoire: &Grimoire) -> Vec<Error> {
+        let mut result = Vec::default();
+
+        for (name, value) in &self.skills {
+            if *value > 100 {
+                result.push(Error::SkillValueBound(name.clone(), *value));
+            }
+        }
+
+        if !grimoire.clades.is_empty() {
+            let mut clades: Vec<&String> = self.clades.iter().collect();
+            clades.sort();
+
+            for clade in clades {
+                if !grimoire.clades.contains_key(clade) {
+                    result.push(Error::UnknownClade(clade.clone()));
+                }
+            }
+        }
+
+        if let Some(rules) = &grimoire.rules {
+            let spent = self.points_spent(&grimoire.skills);
+
+            for category in [SkillCategory::Primary, SkillCategory::Secondary, SkillCategory::Tertiary] {
+                let spent = spent.get(&category).cloned().unwrap_or_default();
+                let cap = rules.cap(category);
+
+                if spent > cap {
+                    result.push(Error::SkillPointBudget { category, spent, cap });
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn check(&self, grimoire: &Grimoire) -> Result<()> {
+        match self.violations(grimoire).into_iter().next() {
+            Some(x) => Err(x),
+            None => Ok(()),
+        }
+    }
+
+    /// Skills already in `visited` are skipped, so that parents forming a cycle are raised
+    /// only once
+    fn raise_skill(&mut self, skills: &Skills, skill: &str, value: u8, visited: &mut HashSet<String>) {
+        if !visited.insert(skill.to_string()) {
+            return;
+        }
+
+        if self.raw_skill(skill) < value {
+            self.skills.insert(skill.to_string(), value);
+        }
+
+        let this_skill = skills.get(skill).cloned().unwrap_or_default();
+
+        for parent in [this_skill.parent, this_skill.parent_2].into_iter().flatten() {
+            self.raise_skill(skills, &parent, value, visited);
+        }
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::grimoire::Skill;
+    use crate::grimoire::{Skill, SkillPointRules};
 
     use float_cmp::approx_eq;
 
@@ -134,6 +236,92 @@ pub mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_with_skill_raised() {
+        let skills = vec![
+            (
+                "Skill".to_string(),
+                Skill::new(
+                    Theoretical::Known(2.33333),
+                    Some("Parent Skill".to_string()),
+                    None,
+                ),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let character = Character::new(
+            vec![].into_iter().collect(),
+            vec![
+                ("Skill".to_string(), 100),
+                ("Parent Skill".to_string(), 20),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let raised = character.with_skill_raised(&skills, "Skill", 50);
+
+        assert_eq!(raised.raw_skill("Skill"), 100);
+        assert_eq!(raised.raw_skill("Parent Skill"), 50);
+        assert_eq!(raised.skill(&skills, "Skill"), 50);
+        assert_eq!(character.levels_to(&raised), 30);
+    }
+
+    #[test]
+    fn test_with_skill_raised_cycle() {
+        let skills = vec![
+            ("A".to_string(), Skill::new(Theoretical::Known(1.), Some("B".to_string()), None)),
+            ("B".to_string(), Skill::new(Theoretical::Known(1.), Some("A".to_string()), Some("B".to_string()))),
+        ]
+        .into_iter()
+        .collect();
+
+        let raised = Character::default().with_skill_raised(&skills, "A", 40);
+
+        assert_eq!(raised.raw_skill("A"), 40);
+        assert_eq!(raised.raw_skill("B"), 40);
+    }
+
+    #[test]
+    fn test_check_skill_points() {
+        let mut grimoire = Grimoire {
+            rules: Some(SkillPointRules::new(150, 0, 0)),
+            ..Grimoire::default()
+        };
+        grimoire.skills.insert("Expensive".to_string(), Skill { cost: 2, ..Skill::default() });
+
+        let valid = Character::new(
+            vec![].into_iter().collect(),
+            vec![("Cheap".to_string(), 100), ("Expensive".to_string(), 25)].into_iter().collect(),
+        );
+        let invalid = valid.with_skill_raised(&grimoire.skills, "Expensive", 26);
+
+        assert!(valid.check(&grimoire).is_ok());
+        assert!(matches!(
+            invalid.check(&grimoire),
+            Err(Error::SkillPointBudget { category: SkillCategory::Primary, spent: 152, cap: 150 })
+        ));
+        assert_eq!(valid.points_to(&invalid, &grimoire.skills), 2);
+    }
+
+    #[test]
+    fn test_check_clades_and_bounds() {
+        let grimoire = Grimoire::default();
+
+        let character = Character::new(
+            vec!["Alchemist".to_string(), "Wizard".to_string()].into_iter().collect(),
+            vec![("Skill".to_string(), 101)].into_iter().collect(),
+        );
+
+        let violations = character.violations(&grimoire);
+
+        assert_eq!(violations.len(), 2);
+        assert!(matches!(&violations[0], Error::SkillValueBound(name, 101) if name == "Skill"));
+        assert!(matches!(&violations[1], Error::UnknownClade(name) if name == "Wizard"));
+    }
+
     #[test]
     fn test_lore_multiplier() {
         let skills = vec![
@@ -164,7 +352,7 @@ pub mod tests {
         );
 
         let expected = 3.33333;
-        let actual = character.lore_multiplier(&skills, "Skill");
+        let actual = character.lore_multiplier(&skills, "Skill", &Ruleset::default());
 
         assert!(actual.is_known(), "{:?}", actual);
         assert!(
@@ -204,7 +392,7 @@ pub mod tests {
         );
 
         let expected = 3.33333;
-        let actual = character.lore_multiplier(&skills, "Skill");
+        let actual = character.lore_multiplier(&skills, "Skill", &Ruleset::default());
 
         assert!(!actual.is_known(), "{:?}", actual);
         assert!(
@@ -0,0 +1,69 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Clade {
+    /// Multiplier applied to the volume of the potions brewed by a character of this clade
+    pub volume_multiplier: f64,
+    pub description: Option<String>,
+}
+
+impl Clade {
+    pub fn new(volume_multiplier: f64, description: Option<String>) -> Self {
+        Self {
+            volume_multiplier,
+            description,
+        }
+    }
+}
+
+impl Default for Clade {
+    fn default() -> Self {
+        Self {
+            volume_multiplier: 1.,
+            description: None,
+        }
+    }
+}
+
+
+pub mod versioned {
+    use serde::{Serialize, Deserialize};
+
+    use super::Clade;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub enum CladeVersioned {
+        #[serde(rename="0")]
+        V0(Clade)
+    }
+
+    impl From<Clade> for CladeVersioned {
+        fn from(value: Clade) -> Self {
+            Self::V0(value)
+        }
+    }
+
+    impl From<CladeVersioned> for Clade {
+        fn from(value: CladeVersioned) -> Self {
+            match value {
+                CladeVersioned::V0(x) => x
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use proptest::strategy::Strategy;
+    use proptest::sample::select;
+    use super::*;
+
+    pub fn clade_strategy() -> impl Strategy<Value = Clade> {
+        let volume_multiplier = select(vec![1., 1.1]);
+        let description = select(vec![Some("a".to_string()), None]);
+
+        (volume_multiplier, description).prop_map(|(v, d)| Clade::new(v, d))
+    }
+}
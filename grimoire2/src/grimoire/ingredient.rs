@@ -1,42 +1,85 @@
+use std::collections::BTreeSet;
+
 use serde::{Serialize, Deserialize};
 
 use crate::modifiermap::ModifierMap;
+use crate::theoretical::Theoretical;
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Ingredient {
     pub skill: Option<String>,
-    pub weight: bool,
+    /// Alchemical weight, which sizes the potions it is brewed into
+    pub weight: Theoretical<f64>,
     pub modifiers: ModifierMap,
+    /// Free-form categories, e.g. herb, mineral, animal part, vendor-bought
+    pub tags: BTreeSet<String>,
+    /// Alternate names and common misspellings
+    pub aliases: BTreeSet<String>,
+    /// Where the ingredient can be found or bought
+    pub source: Option<String>,
+}
+
+impl Default for Ingredient {
+    fn default() -> Self {
+        Self {
+            skill: None,
+            weight: Theoretical::Known(0.),
+            modifiers: ModifierMap::default(),
+            tags: BTreeSet::default(),
+            aliases: BTreeSet::default(),
+            source: None,
+        }
+    }
 }
 
 impl Ingredient {
-    pub fn new(skill: &str, weight: bool, modifiers: ModifierMap) -> Self {
+    pub fn new(skill: &str, weight: Theoretical<f64>, modifiers: ModifierMap) -> Self {
         Self {
             skill: Some(skill.to_string()),
             weight,
             modifiers,
+            ..Default::default()
         }
     }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
 }
 
 
 
 pub mod versioned {
+    use std::collections::BTreeSet;
+
     use serde::{Serialize, Deserialize};
 
     use super::Ingredient;
     use crate::modifiermap::versioned::ModifierMapVersioned;
+    use crate::theoretical::Theoretical;
+    use crate::theoretical::versioned::TheoreticalVersioned;
+
+    /// Weights were only present or absent in version 0
+    fn flag(weight: Theoretical<f64>) -> bool {
+        weight.inner() > 0.
+    }
+
+    fn weight(flag: bool) -> Theoretical<f64> {
+        Theoretical::Known(if flag { 1. } else { 0. })
+    }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum IngredientVersioned {
         #[serde(rename="0")]
-        V0(IngredientV0)
+        V0(IngredientV0),
+        #[serde(rename="1")]
+        V1(IngredientV1),
     }
 
 
     impl From<Ingredient> for IngredientVersioned {
         fn from(value: Ingredient) -> Self {
-            Self::V0(value.into())
+            Self::V1(value.into())
         }
     }
 
@@ -44,7 +87,8 @@ pub mod versioned {
     impl From<IngredientVersioned> for Ingredient {
         fn from(value: IngredientVersioned) -> Self {
             match value {
-                IngredientVersioned::V0(x) => x.into()
+                IngredientVersioned::V0(x) => x.into(),
+                IngredientVersioned::V1(x) => x.into(),
             }
         }
     }
@@ -61,7 +105,7 @@ pub mod versioned {
         fn from(value: Ingredient) -> Self {
             Self {
                 skill: value.skill.clone(),
-                weight: value.weight,
+                weight: flag(value.weight),
                 modifiers: value.modifiers.into()
             }
         }
@@ -72,8 +116,47 @@ pub mod versioned {
         fn from(value: IngredientV0) -> Self {
             Self {
                 skill: value.skill.clone(),
-                weight: value.weight,
-                modifiers: value.modifiers.into()
+                weight: weight(value.weight),
+                modifiers: value.modifiers.into(),
+                ..Default::default()
+            }
+        }
+    }
+
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct IngredientV1 {
+        pub skill: Option<String>,
+        pub weight: TheoreticalVersioned<f64>,
+        pub modifiers: ModifierMapVersioned,
+        pub tags: BTreeSet<String>,
+        pub aliases: BTreeSet<String>,
+        pub source: Option<String>,
+    }
+
+    impl From<Ingredient> for IngredientV1 {
+        fn from(value: Ingredient) -> Self {
+            Self {
+                skill: value.skill,
+                weight: value.weight.into(),
+                modifiers: value.modifiers.into(),
+                tags: value.tags,
+                aliases: value.aliases,
+                source: value.source,
+            }
+        }
+    }
+
+
+    impl From<IngredientV1> for Ingredient {
+        fn from(value: IngredientV1) -> Self {
+            Self {
+                skill: value.skill,
+                weight: value.weight.into(),
+                modifiers: value.modifiers.into(),
+                tags: value.tags,
+                aliases: value.aliases,
+                source: value.source,
             }
         }
     }
@@ -83,9 +166,10 @@ pub mod versioned {
 #[cfg(test)]
 pub mod tests {
     use proptest::strategy::Strategy;
-    use proptest::sample::select;
+    use proptest::sample::{select, subsequence};
     use super::*;
     use crate::modifiermap::tests::modifier_map_strategy;
+    use crate::theoretical::tests::theoretical_f64_strategy;
     
     pub fn ingredient_strategy() -> impl Strategy<Value = Ingredient> {
         let skill = select(vec![
@@ -94,13 +178,19 @@ pub mod tests {
             Some("c".to_string()),
             None,
         ]);
-        let weight = select(vec![true, false]);
+        let weight = theoretical_f64_strategy();
         let modifiers = modifier_map_strategy();
+        let tags = subsequence(vec!["herb", "mineral", "vendor"], 0..=3);
+        let aliases = subsequence(vec!["x", "y"], 0..=2);
+        let source = select(vec![Some("a".to_string()), None]);
 
-        (skill, weight, modifiers).prop_map(|(s, w, m)| Ingredient {
+        (skill, weight, modifiers, tags, aliases, source).prop_map(|(s, w, m, t, a, so)| Ingredient {
             skill: s,
             weight: w,
-            modifiers: m
+            modifiers: m,
+            tags: t.into_iter().map(String::from).collect(),
+            aliases: a.into_iter().map(String::from).collect(),
+            source: so,
         })
     }
 }
\ No newline at end of file
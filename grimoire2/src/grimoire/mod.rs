@@ -1,23 +1,51 @@
 pub mod character;
+pub mod clade;
 pub mod ingredient;
+pub mod production;
+pub mod recipe;
+pub mod rules;
+pub mod ruleset;
 pub mod skill;
 
 pub use character::*;
+pub use clade::*;
 pub use ingredient::*;
+pub use production::*;
+pub use recipe::*;
+pub use rules::*;
+pub use ruleset::*;
 pub use skill::*;
 
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
+use crate::effect::Effects;
+use crate::error::{Error, Result};
+
 pub type Skills = IndexMap<String, Skill>;
 pub type Ingredients = IndexMap<String, Ingredient>;
 pub type Characters = IndexMap<String, Character>;
+pub type Clades = IndexMap<String, Clade>;
+pub type Rulesets = IndexMap<String, Ruleset>;
+pub type Productions = IndexMap<String, Production>;
+pub type Recipes = IndexMap<String, Recipe>;
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Grimoire {
     pub skills: Skills,
     pub ingredients: Ingredients,
     pub characters: Characters,
+    pub clades: Clades,
+    pub rules: Option<SkillPointRules>,
+    pub effects: Effects,
+    /// Game rules the potions may be brewed with
+    pub rulesets: Rulesets,
+    /// Name of the ruleset in effect, the current game rules if not set
+    pub selected_ruleset: Option<String>,
+    /// How ingredients are made from others, by the name of the ingredient made
+    pub productions: Productions,
+    /// Mixes kept by name
+    pub recipes: Recipes,
 }
 
 impl Grimoire {
@@ -26,8 +54,53 @@ impl Grimoire {
             skills,
             ingredients,
             characters,
+            clades: Self::default_clades(),
+            rules: None,
+            effects: Effects::builtin(),
+            rulesets: Rulesets::default(),
+            selected_ruleset: None,
+            productions: Productions::default(),
+            recipes: Recipes::default(),
         }
     }
+
+    /// Ruleset in effect, the current game rules if none is selected or the selected one is
+    /// gone
+    pub fn ruleset(&self) -> Ruleset {
+        self.selected_ruleset
+            .as_ref()
+            .and_then(|x| self.rulesets.get(x))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Puts the ruleset named `name` in effect. `default` is the current game rules, unless the
+    /// grimoire has a ruleset of that name
+    pub fn select_ruleset(&mut self, name: &str) -> Result<()> {
+        self.selected_ruleset = match self.rulesets.contains_key(name) {
+            true => Some(name.to_string()),
+            false if name == "default" => None,
+            false => return Err(Error::UnknownRuleset(name.to_string())),
+        };
+
+        Ok(())
+    }
+
+    /// Clades known before the grimoire had a catalogue of its own
+    pub fn default_clades() -> Clades {
+        let mut clades = Clades::default();
+        clades.insert(
+            "Alchemist".to_string(),
+            Clade::new(1.1, Some("Alvarin alchemist gift".to_string())),
+        );
+        clades
+    }
+}
+
+impl Default for Grimoire {
+    fn default() -> Self {
+        Self::new(Skills::default(), Ingredients::default(), Characters::default())
+    }
 }
 
 
@@ -36,31 +109,54 @@ pub mod versioned {
 
     use serde::{Serialize, Deserialize};
     
-    use super::Grimoire;
+    use indexmap::IndexMap;
+
+    use super::{Grimoire, SkillPointRules};
+    use crate::effect::Effects;
+    use super::ruleset::versioned::RulesetVersioned;
+    use super::production::versioned::ProductionVersioned;
+    use super::recipe::versioned::RecipeVersioned;
     use super::character::versioned::CharacterVersioned;
+    use super::clade::versioned::CladeVersioned;
     use super::skill::versioned::SkillVersioned;
     use super::ingredient::versioned::IngredientVersioned;
 
     type SkillsVersioned = HashMap<String, SkillVersioned>;
     type IngredientsVersioned = HashMap<String, IngredientVersioned>;
     type CharactersVersioned = HashMap<String, CharacterVersioned>;
+    type CladesVersioned = HashMap<String, CladeVersioned>;
+    type RulesetsVersioned = IndexMap<String, RulesetVersioned>;
+    type ProductionsVersioned = IndexMap<String, ProductionVersioned>;
+    type RecipesVersioned = IndexMap<String, RecipeVersioned>;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum GrimoireVersioned {
         #[serde(rename="0")]
-        V0(GrimoireV0)
+        V0(GrimoireV0),
+        #[serde(rename="1")]
+        V1(Box<GrimoireV1>),
+    }
+
+    impl GrimoireVersioned {
+        pub fn version(&self) -> u32 {
+            match self {
+                Self::V0(_) => 0,
+                Self::V1(_) => 1,
+            }
+        }
     }
 
     impl From<Grimoire> for GrimoireVersioned {
         fn from(value: Grimoire) -> Self {
-            Self::V0(value.into())
+            Self::V1(Box::new(value.into()))
         }
     }
 
     impl From<GrimoireVersioned> for Grimoire {
         fn from(value: GrimoireVersioned) -> Self {
             match value {
-                GrimoireVersioned::V0(x) => x.into()
+                GrimoireVersioned::V0(x) => GrimoireV1::from(x).into(),
+                GrimoireVersioned::V1(x) => (*x).into(),
             }
         }
     }
@@ -82,13 +178,73 @@ pub mod versioned {
         }
     }
 
-    impl From<GrimoireV0> for Grimoire {
+    /// Clades, skill point rules, the effect catalogue, rulesets, productions and recipes
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct GrimoireV1 {
+        pub skills: SkillsVersioned,
+        pub ingredients: IngredientsVersioned,
+        pub characters: CharactersVersioned,
+        pub clades: CladesVersioned,
+        pub rules: Option<SkillPointRules>,
+        pub effects: Effects,
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        pub rulesets: RulesetsVersioned,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub selected_ruleset: Option<String>,
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        pub productions: ProductionsVersioned,
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        pub recipes: RecipesVersioned,
+    }
+
+    impl From<GrimoireV0> for GrimoireV1 {
         fn from(value: GrimoireV0) -> Self {
+            Self {
+                skills: value.skills,
+                ingredients: value.ingredients,
+                characters: value.characters,
+                clades: Grimoire::default_clades().into_iter().map(|(n, x)| (n, x.into())).collect(),
+                rules: None,
+                effects: Effects::builtin(),
+                rulesets: Default::default(),
+                selected_ruleset: None,
+                productions: Default::default(),
+                recipes: Default::default(),
+            }
+        }
+    }
+
+    impl From<Grimoire> for GrimoireV1 {
+        fn from(value: Grimoire) -> Self {
             Self {
                 skills: value.skills.into_iter().map(|(n, x)| (n, x.into())).collect(),
                 ingredients: value.ingredients.into_iter().map(|(n, x)| (n, x.into())).collect(),
                 characters: value.characters.into_iter().map(|(n, x)| (n, x.into())).collect(),
-            }            
+                clades: value.clades.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                rules: value.rules,
+                effects: value.effects,
+                rulesets: value.rulesets.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                selected_ruleset: value.selected_ruleset,
+                productions: value.productions.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                recipes: value.recipes.into_iter().map(|(n, x)| (n, x.into())).collect(),
+            }
+        }
+    }
+
+    impl From<GrimoireV1> for Grimoire {
+        fn from(value: GrimoireV1) -> Self {
+            Self {
+                skills: value.skills.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                ingredients: value.ingredients.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                characters: value.characters.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                clades: value.clades.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                rules: value.rules,
+                effects: value.effects,
+                rulesets: value.rulesets.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                selected_ruleset: value.selected_ruleset,
+                productions: value.productions.into_iter().map(|(n, x)| (n, x.into())).collect(),
+                recipes: value.recipes.into_iter().map(|(n, x)| (n, x.into())).collect(),
+            }
         }
     }
 }
@@ -103,17 +259,69 @@ pub mod tests {
     use crate::grimoire::character::tests::character_strategy;
     use crate::grimoire::skill::tests::skill_strategy;
     use crate::grimoire::ingredient::tests::ingredient_strategy;
+    use crate::grimoire::clade::tests::clade_strategy;
+    use crate::grimoire::rules::tests::rules_strategy;
+    use crate::grimoire::ruleset::tests::ruleset_strategy;
+    use crate::grimoire::production::tests::production_strategy;
+    use crate::grimoire::recipe::tests::recipe_strategy;
+    use crate::effect::tests::effects_strategy;
     
     pub fn grimoire_strategy() -> impl Strategy<Value=Grimoire> {
         let name = select(vec!["a", "b", "c"]);
         let characters = hash_map(name.clone(), character_strategy(), 3);
         let skills = hash_map(name.clone(), skill_strategy(), 3);
-        let ingredients = hash_map(name, ingredient_strategy(), 3);
+        let ingredients = hash_map(name.clone(), ingredient_strategy(), 3);
+        let clades = hash_map(name, clade_strategy(), 2);
+        let rulesets = (
+            hash_map(select(vec!["current", "old"]), ruleset_strategy(), 0..2),
+            select(vec![None, Some("current"), Some("old")]),
+        );
+        let productions = hash_map(select(vec!["a", "b"]), production_strategy(), 0..2);
+        let recipes = hash_map(select(vec!["healing", "poison"]), recipe_strategy(), 0..2);
 
-        (characters, skills, ingredients).prop_map(|(c, s, i)| Grimoire {
+        (characters, skills, ingredients, clades, rules_strategy(), effects_strategy(), rulesets, productions, recipes).prop_map(|(c, s, i, cl, r, e, (rs, sr), p, rc)| Grimoire {
             characters: c.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
             skills: s.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
             ingredients: i.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
+            clades: cl.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
+            rules: r,
+            effects: e,
+            selected_ruleset: sr.filter(|x| rs.contains_key(x)).map(str::to_string),
+            rulesets: rs.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
+            productions: p.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
+            recipes: rc.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
         } )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_new_grimoire_clades() {
+        let grimoire = Grimoire::default();
+        let alchemist = Character::new(
+            ["Alchemist".to_string()].into_iter().collect(),
+            Default::default(),
+        );
+
+        assert_eq!(grimoire.clades, Grimoire::default_clades());
+        assert!(alchemist.check(&grimoire).is_ok());
+        assert_eq!(grimoire.ruleset().volume_multiplier(&alchemist, &grimoire.clades), 1.1);
+    }
+
+    #[test]
+    fn test_select_ruleset() {
+        let patched = Ruleset { advanced_potion_making_bonus: 0.25, ..Ruleset::default() };
+        let mut grimoire = Grimoire::default();
+        grimoire.rulesets.insert("patched".to_string(), patched.clone());
+
+        assert_eq!(grimoire.ruleset(), Ruleset::default());
+
+        grimoire.select_ruleset("patched").unwrap();
+        assert_eq!(grimoire.ruleset(), patched);
+
+        grimoire.select_ruleset("default").unwrap();
+        assert_eq!(grimoire.ruleset(), Ruleset::default());
+        assert_eq!(grimoire.selected_ruleset, None);
+        assert_eq!(grimoire.rulesets.len(), 1);
+
+        assert!(grimoire.select_ruleset("missing").is_err());
+    }
+}
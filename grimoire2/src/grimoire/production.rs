@@ -0,0 +1,93 @@
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
+
+/// How an ingredient is made from others, such as an oil pressed from leaves. One batch of
+/// the `inputs` yields `yields` units of the ingredient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Production {
+    /// Amount of every ingredient that goes into a batch
+    pub inputs: IndexMap<String, u64>,
+    pub yields: u64,
+}
+
+impl Production {
+    pub fn new(inputs: IndexMap<String, u64>, yields: u64) -> Self {
+        Self {
+            inputs,
+            yields,
+        }
+    }
+
+    /// Batches needed to make at least `amount` units, none if a batch yields nothing
+    pub fn batches(&self, amount: u64) -> Option<u64> {
+        match self.yields {
+            0 => None,
+            x => Some(amount.div_ceil(x)),
+        }
+    }
+}
+
+impl Default for Production {
+    fn default() -> Self {
+        Self {
+            inputs: IndexMap::default(),
+            yields: 1,
+        }
+    }
+}
+
+
+pub mod versioned {
+    use serde::{Serialize, Deserialize};
+
+    use super::Production;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub enum ProductionVersioned {
+        #[serde(rename="0")]
+        V0(Production)
+    }
+
+    impl From<Production> for ProductionVersioned {
+        fn from(value: Production) -> Self {
+            Self::V0(value)
+        }
+    }
+
+    impl From<ProductionVersioned> for Production {
+        fn from(value: ProductionVersioned) -> Self {
+            match value {
+                ProductionVersioned::V0(x) => x
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use proptest::strategy::Strategy;
+    use proptest::sample::select;
+    use super::*;
+
+    pub fn production_strategy() -> impl Strategy<Value = Production> {
+        let inputs = select(vec![vec![], vec![("a", 3)], vec![("b", 1), ("c", 2)]]);
+        let yields = select(vec![1, 2]);
+
+        (inputs, yields).prop_map(|(i, y)| Production::new(
+            i.into_iter().map(|(n, x)| (n.to_string(), x)).collect(),
+            y,
+        ))
+    }
+
+    #[test]
+    fn test_batches() {
+        let production = Production::new(IndexMap::default(), 3);
+
+        assert_eq!(production.batches(0), Some(0));
+        assert_eq!(production.batches(3), Some(1));
+        assert_eq!(production.batches(4), Some(2));
+        assert_eq!(Production::new(IndexMap::default(), 0).batches(4), None);
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeSet;
+
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
+
+use crate::standalone::Mix;
+use crate::theoretical::Theoretical;
+
+/// A mix worth keeping, with what it's for and how it last turned out
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Recipe {
+    /// Amount of every ingredient by name
+    pub ingredients: IndexMap<String, u64>,
+    /// Character the recipe is meant for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    /// The potion as it was brewed the last time the recipe was evaluated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evaluation: Option<Evaluation>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub character: String,
+    pub volume: Theoretical<f64>,
+    /// Value of every effect by code
+    pub effects: IndexMap<String, Theoretical<f64>>,
+}
+
+impl Recipe {
+    pub fn new(ingredients: IndexMap<String, u64>) -> Self {
+        Self {
+            ingredients,
+            ..Default::default()
+        }
+    }
+}
+
+impl Evaluation {
+    pub fn new(character: &str, mix: &Mix) -> Self {
+        Self {
+            character: character.to_string(),
+            volume: mix.volume(),
+            effects: mix.effects()
+                .iter()
+                .map(|(effect, _, x)| (x.code.clone(), mix.effect(effect)))
+                .collect(),
+        }
+    }
+}
+
+
+pub mod versioned {
+    use serde::{Serialize, Deserialize};
+
+    use super::Recipe;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub enum RecipeVersioned {
+        #[serde(rename="0")]
+        V0(Recipe)
+    }
+
+    impl From<Recipe> for RecipeVersioned {
+        fn from(value: Recipe) -> Self {
+            Self::V0(value)
+        }
+    }
+
+    impl From<RecipeVersioned> for Recipe {
+        fn from(value: RecipeVersioned) -> Self {
+            match value {
+                RecipeVersioned::V0(x) => x
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use proptest::strategy::Strategy;
+    use proptest::sample::select;
+    use super::*;
+
+    pub fn recipe_strategy() -> impl Strategy<Value = Recipe> {
+        let ingredients = select(vec![vec![("a", 3)], vec![("b", 1), ("c", 2)]]);
+        let character = select(vec![None, Some("Tashka")]);
+        let tags = select(vec![vec![], vec!["healing", "cheap"]]);
+
+        (ingredients, character, tags).prop_map(|(i, c, t)| Recipe {
+            ingredients: i.into_iter().map(|(n, x)| (n.to_string(), x)).collect(),
+            character: c.map(str::to_string),
+            tags: t.into_iter().map(str::to_string).collect(),
+            ..Recipe::default()
+        })
+    }
+
+    #[test]
+    fn test_evaluation() {
+        use crate::grimoire::{Character, Grimoire, Ingredient};
+        use crate::standalone::OptimizedGrimoire;
+        use crate::effect::Effect;
+
+        let mut ingredient = Ingredient {
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        };
+        ingredient.modifiers[Effect::DirectHealing].term = Theoretical::Theory(2.);
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Leaf".to_string(), ingredient);
+        let optimized = OptimizedGrimoire::from((&Character::default(), &grimoire));
+        let mix = Mix::new(&optimized, vec![(0, 2)]);
+
+        let evaluation = Evaluation::new("Tashka", &mix);
+
+        assert_eq!(evaluation.character, "Tashka");
+        assert_eq!(evaluation.volume, mix.volume());
+        assert_eq!(evaluation.effects["dh"], mix.effect(Effect::DirectHealing));
+        assert_eq!(evaluation.effects.len(), grimoire.effects.len());
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Serialize, Deserialize};
+
+use super::SkillCategory;
+
+/// Limits on how many skill points a character may spend in each category of skills
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkillPointRules {
+    pub primary: u32,
+    pub secondary: u32,
+    pub tertiary: u32,
+}
+
+impl SkillPointRules {
+    pub fn new(primary: u32, secondary: u32, tertiary: u32) -> Self {
+        Self {
+            primary,
+            secondary,
+            tertiary,
+        }
+    }
+
+    pub fn cap(&self, category: SkillCategory) -> u32 {
+        match category {
+            SkillCategory::Primary => self.primary,
+            SkillCategory::Secondary => self.secondary,
+            SkillCategory::Tertiary => self.tertiary,
+        }
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use proptest::strategy::Strategy;
+    use proptest::sample::select;
+    use super::*;
+
+    pub fn rules_strategy() -> impl Strategy<Value = Option<SkillPointRules>> {
+        select(vec![
+            None,
+            Some(SkillPointRules::new(1100, 400, 200)),
+            Some(SkillPointRules::new(900, 300, 100)),
+        ])
+    }
+}
@@ -0,0 +1,172 @@
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
+
+use super::{Character, Clades, Skills};
+
+/// Effectiveness of lores whose effectiveness isn't known, under the current rules
+pub const DEFAULT_LORE_EFFECTIVENESS: f64 = 0.66666;
+
+/// Constants of the brewing formula. The game changes them from patch to patch, so a grimoire
+/// may keep several rulesets to compare potions under old and new rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ruleset {
+    /// Effectiveness of lores whose effectiveness is unknown
+    pub default_lore_effectiveness: f64,
+    /// Skill that raises every effect of the potions brewed
+    pub advanced_potion_making: String,
+    /// Bonus to every effect at 100 points of `advanced_potion_making`
+    pub advanced_potion_making_bonus: f64,
+    /// Whether only whole hundreds of points count towards the bonus, as the game rounds the
+    /// skill down; the bonus grows linearly otherwise
+    pub advanced_potion_making_steps: bool,
+    /// Volume multipliers of clades under these rules, in place of those of the grimoire
+    pub clades: IndexMap<String, f64>,
+    /// Total weight of the ingredients that brews no volume at all
+    pub weight_offset: u64,
+    /// Weight of the ingredients per unit of volume past `weight_offset`
+    pub weight_per_volume: f64,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            default_lore_effectiveness: DEFAULT_LORE_EFFECTIVENESS,
+            advanced_potion_making: "Advanced Potion Making".to_string(),
+            advanced_potion_making_bonus: 0.2,
+            advanced_potion_making_steps: true,
+            clades: IndexMap::default(),
+            weight_offset: 1,
+            weight_per_volume: 10.,
+        }
+    }
+}
+
+impl Ruleset {
+    /// Product of the volume multipliers of the character's clades
+    pub fn volume_multiplier(&self, character: &Character, clades: &Clades) -> f64 {
+        character
+            .clades
+            .iter()
+            .filter_map(|x| self.clades.get(x).copied().or(clades.get(x).map(|x| x.volume_multiplier)))
+            .product()
+    }
+
+    pub fn advanced_potion_making_mod(&self, character: &Character, skills: &Skills) -> f64 {
+        let value = character.skill(skills, &self.advanced_potion_making);
+
+        let hundreds = match self.advanced_potion_making_steps {
+            true => (value / 100) as f64,
+            false => value as f64 / 100.,
+        };
+
+        1. + self.advanced_potion_making_bonus * hundreds
+    }
+
+    /// Volume of a potion before the clade multipliers
+    pub fn volume(&self, total_weight: f64) -> f64 {
+        if total_weight <= 0. {
+            return 0.
+        }
+
+        (total_weight - self.weight_offset as f64).max(0.) / self.weight_per_volume
+    }
+}
+
+
+pub mod versioned {
+    use serde::{Serialize, Deserialize};
+
+    use super::Ruleset;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub enum RulesetVersioned {
+        #[serde(rename="0")]
+        V0(Ruleset)
+    }
+
+    impl From<Ruleset> for RulesetVersioned {
+        fn from(value: Ruleset) -> Self {
+            Self::V0(value)
+        }
+    }
+
+    impl From<RulesetVersioned> for Ruleset {
+        fn from(value: RulesetVersioned) -> Self {
+            match value {
+                RulesetVersioned::V0(x) => x
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use float_cmp::approx_eq;
+    use proptest::strategy::Strategy;
+    use proptest::sample::select;
+    use super::*;
+    use crate::grimoire::{Clade, Skill};
+
+    pub fn ruleset_strategy() -> impl Strategy<Value = Ruleset> {
+        select(vec![false, true]).prop_map(|patched| match patched {
+            false => Ruleset::default(),
+            true => Ruleset {
+                advanced_potion_making_bonus: 0.25,
+                clades: [("Alchemist".to_string(), 1.15)].into_iter().collect(),
+                ..Ruleset::default()
+            },
+        })
+    }
+
+    #[test]
+    fn test_advanced_potion_making_mod() {
+        let skills: Skills = [("Advanced Potion Making".to_string(), Skill::default())].into_iter().collect();
+        let character = Character::new(
+            HashSet::default(),
+            [("Advanced Potion Making".to_string(), 50)].into_iter().collect(),
+        );
+
+        let master = Character::new(
+            HashSet::default(),
+            [("Advanced Potion Making".to_string(), 100)].into_iter().collect(),
+        );
+        let linear = Ruleset { advanced_potion_making_steps: false, ..Ruleset::default() };
+
+        assert_eq!(Ruleset::default().advanced_potion_making_mod(&character, &skills), 1.);
+        assert!(approx_eq!(f64, Ruleset::default().advanced_potion_making_mod(&master, &skills), 1.2, epsilon = 0.0001));
+        assert!(approx_eq!(f64, linear.advanced_potion_making_mod(&character, &skills), 1.1, epsilon = 0.0001));
+    }
+
+    #[test]
+    fn test_volume_multiplier() {
+        let clades: Clades = [
+            ("Alchemist".to_string(), Clade::new(1.1, None)),
+            ("Wizard".to_string(), Clade::new(1.2, None)),
+        ].into_iter().collect();
+        let character = Character::new(
+            ["Alchemist".to_string(), "Wizard".to_string()].into_iter().collect(),
+            HashMap::default(),
+        );
+        let patched = Ruleset {
+            clades: [("Alchemist".to_string(), 1.15)].into_iter().collect(),
+            ..Ruleset::default()
+        };
+
+        assert!(approx_eq!(f64, Ruleset::default().volume_multiplier(&character, &clades), 1.32, epsilon = 0.0001));
+        assert!(approx_eq!(f64, patched.volume_multiplier(&character, &clades), 1.38, epsilon = 0.0001));
+    }
+
+    #[test]
+    fn test_volume() {
+        let ruleset = Ruleset::default();
+
+        assert_eq!(ruleset.volume(0.), 0.);
+        assert_eq!(ruleset.volume(1.), 0.);
+        assert!(approx_eq!(f64, ruleset.volume(25.), 2.4, epsilon = 0.0001));
+        assert!(approx_eq!(f64, ruleset.volume(6.5), 0.55, epsilon = 0.0001));
+    }
+}
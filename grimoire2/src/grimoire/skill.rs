@@ -1,13 +1,26 @@
 use serde::{Deserialize, Serialize};
+use crate::provenance::Provenance;
 use crate::theoretical::Theoretical;
 
-use super::Grimoire;
+use super::{Grimoire, DEFAULT_LORE_EFFECTIVENESS};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SkillCategory {
+    #[default]
+    Primary,
+    Secondary,
+    Tertiary,
+}
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Skill {
     pub effectiveness: Theoretical<f64>,
     pub parent: Option<String>,
     pub parent_2: Option<String>,
+    pub category: SkillCategory,
+    /// Skill points spent per level of the skill
+    pub cost: u8,
+    pub effectiveness_provenance: Option<Provenance>,
 }
 
 impl Skill {
@@ -20,6 +33,9 @@ impl Skill {
             effectiveness,
             parent,
             parent_2,
+            category: SkillCategory::default(),
+            cost: 1,
+            effectiveness_provenance: None,
         }
     }
 
@@ -53,9 +69,12 @@ impl Skill {
 impl Default for Skill {
     fn default() -> Self {
         Self {
-            effectiveness: Theoretical::Theory(0.66666),
+            effectiveness: Theoretical::Theory(DEFAULT_LORE_EFFECTIVENESS),
             parent: None,
             parent_2: None,
+            category: SkillCategory::default(),
+            cost: 1,
+            effectiveness_provenance: None,
         }
     }
 }
@@ -64,26 +83,30 @@ impl Default for Skill {
 pub mod versioned {
     use serde::{Serialize, Deserialize};
 
+    use crate::provenance::Provenance;
     use crate::theoretical::versioned::TheoreticalVersioned;
 
-    use super::Skill;
+    use super::{Skill, SkillCategory};
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum SkillVersioned {
         #[serde(rename="0")]
-        V0(SkillV0)
+        V0(SkillV0),
+        #[serde(rename="1")]
+        V1(SkillV1),
     }
 
     impl From<Skill> for SkillVersioned {
         fn from(value: Skill) -> Self {
-            SkillVersioned::V0(value.into())
+            SkillVersioned::V1(value.into())
         }
     }
 
     impl From<SkillVersioned> for Skill {
         fn from(value: SkillVersioned) -> Self {
             match value {
-                SkillVersioned::V0(x) => x.into()
+                SkillVersioned::V0(x) => x.into(),
+                SkillVersioned::V1(x) => x.into(),
             }
         }
     }
@@ -107,11 +130,43 @@ pub mod versioned {
 
     impl From<SkillV0> for Skill {
         fn from(value: SkillV0) -> Self {
+            Self::new(value.effectiveness.into(), value.parent, value.parent_2)
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct SkillV1 {
+        pub effectiveness: TheoreticalVersioned<f64>,
+        pub parent: Option<String>,
+        pub parent_2: Option<String>,
+        pub category: SkillCategory,
+        pub cost: u8,
+        pub effectiveness_provenance: Option<Provenance>,
+    }
+
+    impl From<Skill> for SkillV1 {
+        fn from(value: Skill) -> Self {
             Self {
                 effectiveness: value.effectiveness.into(),
                 parent: value.parent,
                 parent_2: value.parent_2,
-            }            
+                category: value.category,
+                cost: value.cost,
+                effectiveness_provenance: value.effectiveness_provenance,
+            }
+        }
+    }
+
+    impl From<SkillV1> for Skill {
+        fn from(value: SkillV1) -> Self {
+            Self {
+                effectiveness: value.effectiveness.into(),
+                parent: value.parent,
+                parent_2: value.parent_2,
+                category: value.category,
+                cost: value.cost,
+                effectiveness_provenance: value.effectiveness_provenance,
+            }
         }
     }
 }
@@ -121,6 +176,7 @@ pub mod versioned {
 pub mod tests {
     use proptest::strategy::Strategy;
     use proptest::sample::select;
+    use crate::provenance::tests::provenance_strategy;
     use crate::theoretical::tests::theoretical_f64_strategy;
     use super::*;
 
@@ -136,12 +192,23 @@ pub mod tests {
             Some("b"),
             None
         ]);        
+        let category = select(vec![
+            SkillCategory::Primary,
+            SkillCategory::Secondary,
+            SkillCategory::Tertiary,
+        ]);
+        let cost = select(vec![1u8, 2]);
+
+        let provenance = provenance_strategy();
 
-        (effectiveness, parent, parent_2).prop_map(|(e, p, p2)| {
+        (effectiveness, parent, parent_2, category, cost, provenance).prop_map(|(e, p, p2, c, cost, pr)| {
             Skill { 
                 effectiveness: e, 
                 parent: p.map(|x| x.to_string()), 
                 parent_2: p2.map(|x| x.to_string()), 
+                category: c,
+                cost,
+                effectiveness_provenance: pr,
             }
         })
     }    
@@ -4,15 +4,21 @@ pub mod effect;
 pub mod modifier;
 pub mod modifiermap;
 pub mod theoretical;
+pub mod provenance;
 pub mod modify;
 
 pub mod grimoire;
 pub mod standalone;
+pub mod migrate;
+pub mod validate;
+pub mod bill;
+pub mod timeline;
+pub mod scenario;
 
 pub use indexmap;
 
 pub mod prelude {
-    pub use super::{effect::*, modifier::*, modifiermap::*, theoretical::*};
+    pub use super::{effect::*, modifier::*, modifiermap::*, provenance::*, theoretical::*};
 
     pub use super::{grimoire::*, standalone::*};
 }
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde::de::IgnoredAny;
+
+use crate::error::{Error, Result};
+use crate::grimoire::{Grimoire, Ingredient, Skill};
+use crate::grimoire::versioned::{GrimoireVersioned, GrimoireV1};
+use crate::grimoire::ingredient::versioned::IngredientVersioned;
+use crate::grimoire::skill::versioned::SkillVersioned;
+
+/// Schema version written by this version of the crate
+pub const GRIMOIRE_SCHEMA_VERSION: u32 = 1;
+
+/// What was changed while a grimoire was upgraded to the current schema
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Upgrade of a versioned value to the current model, noting every change in the report
+pub trait Migrate {
+    type Output;
+
+    fn migrate(self, path: &str, report: &mut MigrationReport) -> Self::Output;
+}
+
+impl Migrate for SkillVersioned {
+    type Output = Skill;
+
+    fn migrate(self, path: &str, report: &mut MigrationReport) -> Skill {
+        if let SkillVersioned::V0(_) = &self {
+            report.changes.push(format!("{path}: 0 -> 1, Primary skill costing 1 point per level"));
+        }
+
+        self.into()
+    }
+}
+
+impl Migrate for IngredientVersioned {
+    type Output = Ingredient;
+
+    fn migrate(self, path: &str, report: &mut MigrationReport) -> Ingredient {
+        if let IngredientVersioned::V1(_) = &self {
+            return self.into();
+        }
+
+        let result: Ingredient = self.into();
+        report.changes.push(format!(
+            "{path}: 0 -> 1, no tags, aliases or source, weight {}", result.weight.inner()
+        ));
+
+        result
+    }
+}
+
+impl Migrate for GrimoireVersioned {
+    type Output = Grimoire;
+
+    fn migrate(self, path: &str, report: &mut MigrationReport) -> Grimoire {
+        report.from = self.version();
+        report.to = GRIMOIRE_SCHEMA_VERSION;
+
+        let mut latest = match self {
+            GrimoireVersioned::V0(x) => {
+                report.changes.push(format!(
+                    "{path}: 0 -> 1, clade catalogue created with the Alchemist clade, no skill point \
+                     rules, built-in effects only, current game rules"
+                ));
+                GrimoireV1::from(x)
+            },
+            GrimoireVersioned::V1(x) => *x,
+        };
+
+        let skills = std::mem::take(&mut latest.skills);
+        let ingredients = std::mem::take(&mut latest.ingredients);
+        let mut result: Grimoire = latest.into();
+
+        let mut skills: Vec<_> = skills.into_iter().collect();
+        skills.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, skill) in skills {
+            let skill = skill.migrate(&format!("{path}.skills.{name}"), report);
+            result.skills.insert(name, skill);
+        }
+
+        let mut ingredients: Vec<_> = ingredients.into_iter().collect();
+        ingredients.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, ingredient) in ingredients {
+            let ingredient = ingredient.migrate(&format!("{path}.ingredients.{name}"), report);
+            result.ingredients.insert(name, ingredient);
+        }
+
+        result
+    }
+}
+
+/// Reads only the version tag of a stored grimoire, so that files written by a newer version
+/// of the crate are refused with a clear error instead of a deserialization failure
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct SchemaProbe(HashMap<String, IgnoredAny>);
+
+impl SchemaProbe {
+    pub fn version(&self) -> Result<u32> {
+        let mut keys = self.0.keys();
+
+        let version = match (keys.next(), keys.next()) {
+            (Some(x), None) => x.parse::<u32>().map_err(|_| Error::UnknownSchema(x.clone()))?,
+            _ => return Err(Error::UnknownSchema(format!("{} top-level keys", self.0.len()))),
+        };
+
+        if version > GRIMOIRE_SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchemaVersion(version, GRIMOIRE_SCHEMA_VERSION));
+        }
+
+        Ok(version)
+    }
+}
+
+/// Upgrades a stored grimoire to the current model
+pub fn migrate(grimoire: GrimoireVersioned) -> (Grimoire, MigrationReport) {
+    let mut report = MigrationReport::default();
+    let grimoire = grimoire.migrate("grimoire", &mut report);
+    (grimoire, report)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::effect::{Effect, Effects};
+    use crate::theoretical::Theoretical;
+
+    /// The same grimoire as written by every version of the schema, each with what it added
+    const GOLDEN: [&str; 2] = [
+        include_str!("../tests/data/grimoire_v0.json"),
+        include_str!("../tests/data/grimoire_v1.json"),
+    ];
+
+    fn load(data: &str) -> (Grimoire, MigrationReport) {
+        let probe: SchemaProbe = serde_json::from_str(data).unwrap();
+        probe.version().unwrap();
+
+        migrate(serde_json::from_str(data).unwrap())
+    }
+
+    #[test]
+    fn test_migrate_v0_golden() {
+        let (grimoire, report) = load(GOLDEN[0]);
+        let (expected, _) = load(GOLDEN[1]);
+
+        assert_eq!(grimoire.skills.keys().collect::<BTreeSet<_>>(), expected.skills.keys().collect());
+        assert_eq!(grimoire.ingredients.keys().collect::<BTreeSet<_>>(), expected.ingredients.keys().collect());
+        assert_eq!(grimoire.characters, expected.characters);
+        assert_eq!(grimoire.clades, expected.clades);
+
+        assert_eq!(report.from, 0);
+        assert_eq!(report.to, GRIMOIRE_SCHEMA_VERSION);
+        assert_eq!(report.changes.len(), 1 + grimoire.skills.len() + grimoire.ingredients.len());
+        assert!(report.changes[0].starts_with("grimoire: 0 -> 1, clade catalogue created"));
+        assert_eq!(
+            report.changes[1],
+            "grimoire.skills.Botanical Oils Lore: 0 -> 1, Primary skill costing 1 point per level"
+        );
+        assert_eq!(
+            report.changes.last().unwrap(),
+            "grimoire.ingredients.Sea Dew Leaves: 0 -> 1, no tags, aliases or source, weight 1"
+        );
+    }
+
+    #[test]
+    fn test_migrate_v0_keeps_values() {
+        let (grimoire, _) = load(GOLDEN[0]);
+
+        let salvia = &grimoire.ingredients["Salvia Oil"];
+        assert_eq!(salvia.skill.as_deref(), Some("Botanical Oils Lore"));
+        assert_eq!(salvia.modifiers[Effect::DirectHealing].term, Theoretical::Known(2.4));
+        assert_eq!(salvia.modifiers[Effect::DirectHealing].multiplier, Theoretical::Theory(1.2));
+        assert_eq!(salvia.weight, Theoretical::Known(1.));
+
+        let tashka = &grimoire.characters["Tashka"];
+        assert!(tashka.has_clade("Alchemist"));
+        assert_eq!(tashka.raw_skill("Botanical Oils Lore"), 80);
+
+        assert_eq!(grimoire.skills["Botanical Oils Lore"].effectiveness, Theoretical::Theory(0.5));
+        assert_eq!(grimoire.clades["Alchemist"].volume_multiplier, 1.1);
+    }
+
+    #[test]
+    fn test_migrate_golden_versions() {
+        for (version, data) in GOLDEN.iter().enumerate() {
+            let (grimoire, report) = load(data);
+            let version = version as u32;
+
+            assert_eq!(report.from, version);
+            assert_eq!(report.to, GRIMOIRE_SCHEMA_VERSION);
+            assert_eq!(report.is_empty(), version == GRIMOIRE_SCHEMA_VERSION);
+
+            assert_eq!(grimoire.characters["Tashka"].raw_skill("Botanical Oils Lore"), 80);
+            assert_eq!(grimoire.ingredients["Salvia Oil"].modifiers[Effect::DirectHealing].term, Theoretical::Known(2.4));
+            assert_eq!(grimoire.clades["Alchemist"].volume_multiplier, 1.1);
+        }
+    }
+
+    #[test]
+    fn test_migrate_golden_additions() {
+        let (v0, _) = load(GOLDEN[0]);
+        let (v1, _) = load(GOLDEN[1]);
+        let provenance = |x: &Grimoire| x.skills["Botanical Oils Lore"].effectiveness_provenance.clone();
+
+        assert_eq!(provenance(&v0), None);
+        assert_eq!(provenance(&v1).unwrap().source.as_deref(), Some("Tashka"));
+
+        assert_eq!(v0.effects, Effects::builtin());
+        assert!(v1.effects.by_id("Stamina").is_some());
+
+        assert!(v0.rulesets.is_empty());
+        assert_eq!(v0.selected_ruleset, None);
+        assert_eq!(v1.selected_ruleset.as_deref(), Some("current"));
+
+        assert_eq!(v0.ingredients["Sea Dew Leaves"].weight, Theoretical::Known(1.));
+        assert_eq!(v1.ingredients["Sea Dew Leaves"].weight, Theoretical::Theory(0.5));
+
+        assert!(v0.productions.is_empty());
+        assert_eq!(v1.productions["Purified Water"].yields, 2);
+
+        assert!(v0.recipes.is_empty());
+        assert_eq!(v1.recipes["healing"].ingredients["Purified Water"], 3);
+    }
+
+    #[test]
+    fn test_migrate_current_is_silent() {
+        let (grimoire, _) = load(GOLDEN[0]);
+        let saved = serde_json::to_string(&GrimoireVersioned::from(grimoire.clone())).unwrap();
+        let (reloaded, report) = load(&saved);
+
+        assert_eq!(reloaded, grimoire);
+        assert!(report.is_empty());
+        assert_eq!(report.from, GRIMOIRE_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_schema_probe() {
+        let probe = |x: &str| serde_json::from_str::<SchemaProbe>(x).unwrap().version();
+
+        assert_eq!(probe(r#"{"0": {}}"#).unwrap(), 0);
+        assert!(matches!(probe(r#"{"9": {}}"#), Err(Error::UnsupportedSchemaVersion(9, _))));
+        assert!(matches!(probe(r#"{"skills": {}}"#), Err(Error::UnknownSchema(_))));
+        assert!(matches!(probe(r#"{}"#), Err(Error::UnknownSchema(_))));
+    }
+}
@@ -1,16 +1,27 @@
 use serde::{Serialize, Deserialize};
 
+use crate::provenance::Provenance;
 use crate::theoretical::Theoretical;
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Modifier {
     pub term: Theoretical<f64>,
     pub multiplier: Theoretical<f64>,
+    pub term_provenance: Option<Provenance>,
+    pub multiplier_provenance: Option<Provenance>,
 }
 
 impl Modifier {
+    /// Same as `Modifier::default()`, usable in constants
+    pub const DEFAULT: Modifier = Modifier {
+        term: Theoretical::Unknown,
+        multiplier: Theoretical::Unknown,
+        term_provenance: None,
+        multiplier_provenance: None,
+    };
+
     pub fn new(term: Theoretical<f64>, multiplier: Theoretical<f64>) -> Self {
-        Self { term, multiplier }
+        Self { term, multiplier, term_provenance: None, multiplier_provenance: None }
     }
 
     pub fn new_known(term: f64, multiplier: f64) -> Self {
@@ -20,10 +31,7 @@ impl Modifier {
 
 impl From<(Option<f64>, Option<f64>)> for Modifier {
     fn from((term, multiplier): (Option<f64>, Option<f64>)) -> Self {
-        Self {
-            term: term.into(),
-            multiplier: multiplier.into(),
-        }
+        Self::new(term.into(), multiplier.into())
     }
 }
 
@@ -32,6 +40,7 @@ pub mod versioned {
     use serde::{Serialize, Deserialize};
 
     use super::Modifier;
+    use crate::provenance::Provenance;
     use crate::theoretical::versioned::TheoreticalVersioned;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,10 +49,20 @@ pub mod versioned {
         multiplier: TheoreticalVersioned<f64>
     }
 
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ModifierV1 {
+        term: TheoreticalVersioned<f64>,
+        multiplier: TheoreticalVersioned<f64>,
+        term_provenance: Option<Provenance>,
+        multiplier_provenance: Option<Provenance>,
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum ModifierVersioned {
         #[serde(rename="0")]
-        V0(ModifierV0)
+        V0(ModifierV0),
+        #[serde(rename="1")]
+        V1(ModifierV1),
     }
 
     impl From<Modifier> for ModifierV0 {
@@ -54,20 +73,43 @@ pub mod versioned {
 
     impl From<ModifierV0> for Modifier {
         fn from(value: ModifierV0) -> Self {
-            Self { term: value.term.into(), multiplier: value.multiplier.into() }
+            Self::new(value.term.into(), value.multiplier.into())
+        }
+    }
+
+    impl From<Modifier> for ModifierV1 {
+        fn from(value: Modifier) -> Self {
+            Self {
+                term: value.term.into(),
+                multiplier: value.multiplier.into(),
+                term_provenance: value.term_provenance,
+                multiplier_provenance: value.multiplier_provenance,
+            }
+        }
+    }
+
+    impl From<ModifierV1> for Modifier {
+        fn from(value: ModifierV1) -> Self {
+            Self {
+                term: value.term.into(),
+                multiplier: value.multiplier.into(),
+                term_provenance: value.term_provenance,
+                multiplier_provenance: value.multiplier_provenance,
+            }
         }
     }
 
     impl From<Modifier> for ModifierVersioned {
         fn from(value: Modifier) -> Self {
-            Self::V0(value.into())
+            Self::V1(value.into())
         }
     }
 
     impl From<ModifierVersioned> for Modifier {
         fn from(value: ModifierVersioned) -> Self {
             match value {
-                ModifierVersioned::V0(x) => x.into()
+                ModifierVersioned::V0(x) => x.into(),
+                ModifierVersioned::V1(x) => x.into(),
             }
         }
     }
@@ -77,6 +119,7 @@ pub mod versioned {
 #[cfg(test)]
 pub mod tests {
     use proptest::strategy::Strategy;
+    use crate::provenance::tests::provenance_strategy;
     use crate::theoretical::tests::theoretical_f64_strategy;
     use super::*;
     
@@ -84,6 +127,10 @@ pub mod tests {
         (
             theoretical_f64_strategy(),
             theoretical_f64_strategy(),
-        ).prop_map(|(term, multiplier)| Modifier { term, multiplier })
+            provenance_strategy(),
+            provenance_strategy(),
+        ).prop_map(|(term, multiplier, term_provenance, multiplier_provenance)| Modifier {
+            term, multiplier, term_provenance, multiplier_provenance
+        })
     }
 }
\ No newline at end of file
@@ -1,20 +1,30 @@
 use std::ops::{Index, IndexMut};
 
 use serde::{Serialize, Deserialize};
-use strum::{EnumCount, IntoEnumIterator};
 
 use crate::{effect::Effect, modifier::Modifier};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Modifiers of an ingredient by effect. Effects past the end of the map, such as ones added
+/// to the catalogue after the ingredient was written, have default modifiers
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ModifierMap(Vec<Modifier>);
 
+/// Reads default modifiers past the end of the map
+static DEFAULT_MODIFIER: Modifier = Modifier::DEFAULT;
+
 impl ModifierMap {
     pub fn size(&self) -> usize {
-        Effect::COUNT
+        self.0.len()
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (Effect, &Modifier)> {
-        Effect::iter().zip(self.0.iter())
+        self.0.iter().enumerate().map(|(i, x)| (Effect(i), x))
+    }
+}
+
+impl PartialEq for ModifierMap {
+    fn eq(&self, other: &Self) -> bool {
+        (0..self.size().max(other.size())).all(|i| self[Effect(i)] == other[Effect(i)])
     }
 }
 
@@ -43,23 +53,20 @@ impl From<Vec<(Effect, f64, f64)>> for ModifierMap {
     }
 }
 
-impl Default for ModifierMap {
-    fn default() -> Self {
-        Self(Effect::iter().map(|_| Modifier::default()).collect())
-    }
-}
-
 impl Index<Effect> for ModifierMap {
     type Output = Modifier;
 
     fn index(&self, index: Effect) -> &Self::Output {
-        &self.0[index as usize]
+        self.0.get(index.0).unwrap_or(&DEFAULT_MODIFIER)
     }
 }
 
 impl IndexMut<Effect> for ModifierMap {
     fn index_mut(&mut self, index: Effect) -> &mut Self::Output {
-        &mut self.0[index as usize]
+        if self.0.len() <= index.0 {
+            self.0.resize(index.0 + 1, Modifier::default());
+        }
+        &mut self.0[index.0]
     }
 }
 
@@ -122,6 +129,7 @@ pub mod tests {
             modifier_strategy(),
             modifier_strategy(),
             modifier_strategy(),
+            modifier_strategy(),
         ).prop_map(|mods| 
             vec![
                 (Effect::DirectHealing, mods.0),
@@ -131,6 +139,7 @@ pub mod tests {
                 (Effect::HealingLength, mods.4),
                 (Effect::PoisonLength, mods.5),
                 (Effect::Alcohol, mods.6),
+                (Effect(7), mods.7),
             ].into()
         )
     }
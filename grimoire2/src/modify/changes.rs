@@ -0,0 +1,459 @@
+use std::fmt::Display;
+
+use crate::effect::{Effect, EffectDefinition};
+use crate::grimoire::{Grimoire, Recipe};
+use crate::provenance::Provenance;
+use crate::theoretical::Theoretical;
+
+use super::{GrimoireUpdate, GrimoireUpdateCommand};
+use super::character::CharacterUpdateCommand;
+use super::command::Commands;
+use super::ingredient::IngredientUpdateCommand;
+use super::skill::SkillUpdateCommand;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Absent,
+    Value(Theoretical<f64>),
+    Text(String),
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Absent => write!(f, "-"),
+            FieldValue::Value(Theoretical::Known(x)) => write!(f, "{x} (known)"),
+            FieldValue::Value(Theoretical::Theory(x)) => write!(f, "{x} (theory)"),
+            FieldValue::Value(Theoretical::Unknown) => write!(f, "unknown"),
+            FieldValue::Text(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl From<Option<String>> for FieldValue {
+    fn from(value: Option<String>) -> Self {
+        value.map(FieldValue::Text).unwrap_or(FieldValue::Absent)
+    }
+}
+
+impl From<Option<Provenance>> for FieldValue {
+    fn from(value: Option<Provenance>) -> Self {
+        value.map(|x| FieldValue::Text(
+            [x.source, x.date.map(|x| x.to_string()), x.confidence].into_iter().flatten().collect::<Vec<_>>().join(", ")
+        )).unwrap_or(FieldValue::Absent)
+    }
+}
+
+/// One field of the grimoire changed by an update
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// Dotted path of the field, e.g. `ingredients.Salvia Oil.DirectHealing.term`
+    pub path: String,
+    pub old: FieldValue,
+    pub new: FieldValue,
+}
+
+impl Change {
+    fn new(path: String, old: impl Into<FieldValue>, new: impl Into<FieldValue>) -> Self {
+        Self { path, old: old.into(), new: new.into() }
+    }
+
+    fn value(path: String, old: Theoretical<f64>, new: Theoretical<f64>) -> Self {
+        Self { path, old: FieldValue::Value(old), new: FieldValue::Value(new) }
+    }
+
+    /// A known value replaced with a theory or an unknown value
+    pub fn is_downgrade(&self) -> bool {
+        match (&self.old, &self.new) {
+            (FieldValue::Value(old), FieldValue::Value(new)) => new.is_downgrade_of(old),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.path, self.old, self.new)
+    }
+}
+
+fn text(value: impl ToString) -> FieldValue {
+    FieldValue::Text(value.to_string())
+}
+
+/// Field by field description of what the update does to the grimoire. Commands are described
+/// one by one, so the update is expected to come from `GrimoireUpdate::diff`
+pub fn changes(grimoire: &Grimoire, update: &GrimoireUpdate) -> Vec<Change> {
+    let mut result = Vec::default();
+    let id = |effect: Effect| match grimoire.effects.id(effect) {
+        Some(x) => x.to_string(),
+        None => format!("{effect:?}"),
+    };
+
+    for i in 0..update.len() {
+        match &update[i] {
+            GrimoireUpdateCommand::Character(name, update) => {
+                let path = format!("characters.{name}");
+                let Some(old) = grimoire.characters.get(name) else {
+                    result.push(Change::new(path, FieldValue::Absent, text("added")));
+                    continue;
+                };
+
+                for i in 0..update.len() {
+                    result.push(match &update[i] {
+                        CharacterUpdateCommand::AddClade(x) => Change::new(
+                            format!("{path}.clades.{x}"), FieldValue::Absent, text("added")
+                        ),
+                        CharacterUpdateCommand::RemoveClade(x) => Change::new(
+                            format!("{path}.clades.{x}"), text("present"), FieldValue::Absent
+                        ),
+                        CharacterUpdateCommand::SetSkill(skill, x) => Change::new(
+                            format!("{path}.skills.{skill}"),
+                            old.skills.get(skill).map(|x| x.to_string()),
+                            text(x),
+                        ),
+                        CharacterUpdateCommand::RemoveSkill(skill) => Change::new(
+                            format!("{path}.skills.{skill}"),
+                            old.skills.get(skill).map(|x| x.to_string()),
+                            FieldValue::Absent,
+                        ),
+                    });
+                }
+            },
+            GrimoireUpdateCommand::Skill(name, update) => {
+                let path = format!("skills.{name}");
+                let Some(old) = grimoire.skills.get(name) else {
+                    result.push(Change::new(path, FieldValue::Absent, text("added")));
+                    continue;
+                };
+
+                for i in 0..update.len() {
+                    result.push(match &update[i] {
+                        SkillUpdateCommand::SetEffectiveness(x) => Change::value(
+                            format!("{path}.effectiveness"), old.effectiveness, *x
+                        ),
+                        SkillUpdateCommand::SetParent(x) => Change::new(
+                            format!("{path}.parent"), old.parent.clone(), x.clone()
+                        ),
+                        SkillUpdateCommand::SetParent2(x) => Change::new(
+                            format!("{path}.parent_2"), old.parent_2.clone(), x.clone()
+                        ),
+                        SkillUpdateCommand::SetCategory(x) => Change::new(
+                            format!("{path}.category"), text(format!("{:?}", old.category)), text(format!("{x:?}"))
+                        ),
+                        SkillUpdateCommand::SetCost(x) => Change::new(
+                            format!("{path}.cost"), text(old.cost), text(x)
+                        ),
+                        SkillUpdateCommand::SetEffectivenessProvenance(x) => Change::new(
+                            format!("{path}.effectiveness_provenance"),
+                            old.effectiveness_provenance.clone(),
+                            x.clone(),
+                        ),
+                    });
+                }
+            },
+            GrimoireUpdateCommand::Ingredient(name, update) => {
+                let path = format!("ingredients.{name}");
+                let Some(old) = grimoire.ingredients.get(name) else {
+                    result.push(Change::new(path, FieldValue::Absent, text("added")));
+                    continue;
+                };
+
+                for i in 0..update.len() {
+                    result.push(match &update[i] {
+                        IngredientUpdateCommand::ChangeTerm(effect, x) => Change::value(
+                            format!("{path}.{}.term", id(*effect)), old.modifiers[*effect].term, *x
+                        ),
+                        IngredientUpdateCommand::ChangeMultiplier(effect, x) => Change::value(
+                            format!("{path}.{}.multiplier", id(*effect)), old.modifiers[*effect].multiplier, *x
+                        ),
+                        IngredientUpdateCommand::SetSkill(x) => Change::new(
+                            format!("{path}.skill"), old.skill.clone(), x.clone()
+                        ),
+                        IngredientUpdateCommand::SetWeight(x) => Change::value(
+                            format!("{path}.weight"), old.weight, *x
+                        ),
+                        IngredientUpdateCommand::AddTag(x) => Change::new(
+                            format!("{path}.tags.{x}"), FieldValue::Absent, text("added")
+                        ),
+                        IngredientUpdateCommand::RemoveTag(x) => Change::new(
+                            format!("{path}.tags.{x}"), text("present"), FieldValue::Absent
+                        ),
+                        IngredientUpdateCommand::AddAlias(x) => Change::new(
+                            format!("{path}.aliases.{x}"), FieldValue::Absent, text("added")
+                        ),
+                        IngredientUpdateCommand::RemoveAlias(x) => Change::new(
+                            format!("{path}.aliases.{x}"), text("present"), FieldValue::Absent
+                        ),
+                        IngredientUpdateCommand::SetSource(x) => Change::new(
+                            format!("{path}.source"), old.source.clone(), x.clone()
+                        ),
+                        IngredientUpdateCommand::SetTermProvenance(effect, x) => Change::new(
+                            format!("{path}.{}.term_provenance", id(*effect)),
+                            old.modifiers[*effect].term_provenance.clone(),
+                            x.clone(),
+                        ),
+                        IngredientUpdateCommand::SetMultiplierProvenance(effect, x) => Change::new(
+                            format!("{path}.{}.multiplier_provenance", id(*effect)),
+                            old.modifiers[*effect].multiplier_provenance.clone(),
+                            x.clone(),
+                        ),
+                    });
+                }
+            },
+            GrimoireUpdateCommand::RemoveCharacter(name) => {
+                result.push(Change::new(format!("characters.{name}"), text("present"), FieldValue::Absent));
+            },
+            GrimoireUpdateCommand::RemoveSkill(name) => {
+                result.push(Change::new(format!("skills.{name}"), text("present"), FieldValue::Absent));
+            },
+            GrimoireUpdateCommand::RemoveIngredient(name) => {
+                result.push(Change::new(format!("ingredients.{name}"), text("present"), FieldValue::Absent));
+            },
+            GrimoireUpdateCommand::Clade(name, clade) => {
+                let old = grimoire.clades.get(name).map(|x| x.volume_multiplier.to_string());
+                result.push(Change::new(
+                    format!("clades.{name}.volume_multiplier"), old, text(clade.volume_multiplier)
+                ));
+            },
+            GrimoireUpdateCommand::RemoveClade(name) => {
+                result.push(Change::new(format!("clades.{name}"), text("present"), FieldValue::Absent));
+            },
+            GrimoireUpdateCommand::SetRules(rules) => {
+                let describe = |x: Option<_>| x.map(|x: crate::grimoire::SkillPointRules| format!(
+                    "{}/{}/{}", x.primary, x.secondary, x.tertiary
+                ));
+                result.push(Change::new("rules".to_string(), describe(grimoire.rules), describe(*rules)));
+            },
+            GrimoireUpdateCommand::Effect(id, definition) => {
+                let describe = |x: &EffectDefinition| format!("{} {} ({})", x.code, x.name, x.category);
+                let old = grimoire.effects.by_id(id).and_then(|x| grimoire.effects.get(x)).map(describe);
+                result.push(Change::new(format!("effects.{id}"), old, text(describe(definition))));
+            },
+            GrimoireUpdateCommand::Ruleset(name, ruleset) => {
+                let path = format!("rulesets.{name}");
+                let Some(old) = grimoire.rulesets.get(name) else {
+                    result.push(Change::new(path, FieldValue::Absent, text("added")));
+                    continue;
+                };
+
+                let fields = [
+                    ("default_lore_effectiveness", text(old.default_lore_effectiveness), text(ruleset.default_lore_effectiveness)),
+                    ("advanced_potion_making", text(&old.advanced_potion_making), text(&ruleset.advanced_potion_making)),
+                    ("advanced_potion_making_bonus", text(old.advanced_potion_making_bonus), text(ruleset.advanced_potion_making_bonus)),
+                    ("advanced_potion_making_steps", text(old.advanced_potion_making_steps), text(ruleset.advanced_potion_making_steps)),
+                    ("weight_offset", text(old.weight_offset), text(ruleset.weight_offset)),
+                    ("weight_per_volume", text(old.weight_per_volume), text(ruleset.weight_per_volume)),
+                ];
+
+                for (field, old, new) in fields {
+                    if old != new {
+                        result.push(Change::new(format!("{path}.{field}"), old, new));
+                    }
+                }
+
+                for clade in old.clades.keys().chain(ruleset.clades.keys().filter(|x| !old.clades.contains_key(*x))) {
+                    let old = old.clades.get(clade).map(|x| x.to_string());
+                    let new = ruleset.clades.get(clade).map(|x| x.to_string());
+                    if old != new {
+                        result.push(Change::new(format!("{path}.clades.{clade}"), old, new));
+                    }
+                }
+            },
+            GrimoireUpdateCommand::RemoveRuleset(name) => {
+                result.push(Change::new(format!("rulesets.{name}"), text("present"), FieldValue::Absent));
+            },
+            GrimoireUpdateCommand::SelectRuleset(name) => {
+                let old = grimoire.selected_ruleset.clone().unwrap_or("default".to_string());
+                result.push(Change::new("ruleset".to_string(), text(old), text(name)));
+            },
+            GrimoireUpdateCommand::Production(name, production) => {
+                let path = format!("productions.{name}");
+                let Some(old) = grimoire.productions.get(name) else {
+                    result.push(Change::new(path, FieldValue::Absent, text("added")));
+                    continue;
+                };
+
+                if old.yields != production.yields {
+                    result.push(Change::new(format!("{path}.yields"), text(old.yields), text(production.yields)));
+                }
+
+                for input in old.inputs.keys().chain(production.inputs.keys().filter(|x| !old.inputs.contains_key(*x))) {
+                    let old = old.inputs.get(input).map(|x| x.to_string());
+                    let new = production.inputs.get(input).map(|x| x.to_string());
+                    if old != new {
+                        result.push(Change::new(format!("{path}.inputs.{input}"), old, new));
+                    }
+                }
+            },
+            GrimoireUpdateCommand::RemoveProduction(name) => {
+                result.push(Change::new(format!("productions.{name}"), text("present"), FieldValue::Absent));
+            },
+            GrimoireUpdateCommand::Recipe(name, recipe) => {
+                let path = format!("recipes.{name}");
+                let Some(old) = grimoire.recipes.get(name) else {
+                    result.push(Change::new(path, FieldValue::Absent, text("added")));
+                    continue;
+                };
+
+                for ingredient in old.ingredients.keys().chain(recipe.ingredients.keys().filter(|x| !old.ingredients.contains_key(*x))) {
+                    let old = old.ingredients.get(ingredient).map(|x| x.to_string());
+                    let new = recipe.ingredients.get(ingredient).map(|x| x.to_string());
+                    if old != new {
+                        result.push(Change::new(format!("{path}.ingredients.{ingredient}"), old, new));
+                    }
+                }
+
+                if old.character != recipe.character {
+                    result.push(Change::new(format!("{path}.character"), old.character.clone(), recipe.character.clone()));
+                }
+
+                if old.notes != recipe.notes {
+                    result.push(Change::new(format!("{path}.notes"), text(&old.notes), text(&recipe.notes)));
+                }
+
+                if old.tags != recipe.tags {
+                    let tags = |x: &Recipe| x.tags.iter().cloned().collect::<Vec<_>>().join(", ");
+                    result.push(Change::new(format!("{path}.tags"), text(tags(old)), text(tags(recipe))));
+                }
+
+                // Evaluations are too long to show, only who they were for
+                if old.evaluation != recipe.evaluation {
+                    let character = |x: &Recipe| x.evaluation.as_ref().map(|x| x.character.clone());
+                    result.push(Change::new(format!("{path}.evaluation"), character(old), character(recipe)));
+                }
+            },
+            GrimoireUpdateCommand::RemoveRecipe(name) => {
+                result.push(Change::new(format!("recipes.{name}"), text("present"), FieldValue::Absent));
+            },
+        }
+    }
+
+    result
+}
+
+/// Changes from one grimoire to another
+pub fn diff_changes(old: &Grimoire, new: &Grimoire) -> Vec<Change> {
+    changes(old, &GrimoireUpdate::diff(old, new))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect::Effect;
+    use crate::grimoire::{Production, Ruleset};
+    use crate::modify::ingredient::IngredientUpdate;
+    use crate::modify::skill::SkillUpdate;
+
+    fn grimoire() -> Grimoire {
+        GrimoireUpdate::default()
+            .skill("Herbology", SkillUpdate::default().set_effectiveness(Theoretical::Known(0.5)).clone())
+            .ingredient(
+                "Leaf", IngredientUpdate::default()
+                    .set_skill("Herbology")
+                    .set_term(Effect::DirectHealing, Theoretical::Known(1.))
+                    .set_multiplier(Effect::DirectHealing, Theoretical::Theory(1.2))
+                    .clone()
+            )
+            .create()
+    }
+
+    #[test]
+    fn test_changes() {
+        let old = grimoire();
+        let mut new = old.clone();
+        GrimoireUpdate::default()
+            .ingredient(
+                "Leaf", IngredientUpdate::default()
+                    .set_term(Effect::DirectHealing, Theoretical::Theory(1.5))
+                    .set_multiplier(Effect::DirectHealing, Theoretical::Known(1.1))
+                    .clone()
+            )
+            .ingredient("Stone", IngredientUpdate::default())
+            .remove_skill("Herbology")
+            .update(&mut new);
+
+        let changes = diff_changes(&old, &new);
+
+        assert_eq!(changes.len(), 4, "{:?}", changes);
+        assert_eq!(changes[0].to_string(), "skills.Herbology: present -> -");
+        assert_eq!(changes[1].to_string(), "ingredients.Leaf.DirectHealing.term: 1 (known) -> 1.5 (theory)");
+        assert!(changes[1].is_downgrade());
+        assert_eq!(changes[2].to_string(), "ingredients.Leaf.DirectHealing.multiplier: 1.2 (theory) -> 1.1 (known)");
+        assert!(!changes[2].is_downgrade());
+        assert_eq!(changes[3].to_string(), "ingredients.Stone: - -> added");
+    }
+
+    #[test]
+    fn test_ruleset_changes() {
+        let mut old = grimoire();
+        old.rulesets.insert("current".to_string(), Ruleset::default());
+        old.selected_ruleset = Some("current".to_string());
+        let mut new = old.clone();
+        GrimoireUpdate::default()
+            .ruleset("current", Ruleset { advanced_potion_making_bonus: 0.25, ..Ruleset::default() })
+            .ruleset("old", Ruleset::default())
+            .select_ruleset("old")
+            .update(&mut new);
+
+        let changes: Vec<String> = diff_changes(&old, &new).iter().map(|x| x.to_string()).collect();
+
+        assert_eq!(changes, vec![
+            "rulesets.current.advanced_potion_making_bonus: 0.2 -> 0.25",
+            "rulesets.old: - -> added",
+            "ruleset: current -> old",
+        ]);
+    }
+
+    #[test]
+    fn test_production_changes() {
+        let mut old = grimoire();
+        old.productions.insert("Oil".to_string(), Production::new(
+            [("Leaf".to_string(), 3)].into_iter().collect(), 1,
+        ));
+        let mut new = old.clone();
+        GrimoireUpdate::default()
+            .production("Oil", Production::new([("Stone".to_string(), 1)].into_iter().collect(), 2))
+            .production("Leaf", Production::default())
+            .update(&mut new);
+
+        let changes: Vec<String> = diff_changes(&old, &new).iter().map(|x| x.to_string()).collect();
+
+        assert_eq!(changes, vec![
+            "productions.Oil.yields: 1 -> 2",
+            "productions.Oil.inputs.Leaf: 3 -> -",
+            "productions.Oil.inputs.Stone: - -> 1",
+            "productions.Leaf: - -> added",
+        ]);
+    }
+
+    #[test]
+    fn test_recipe_changes() {
+        let mut old = grimoire();
+        old.recipes.insert("Salve".to_string(), Recipe::new([("Leaf".to_string(), 3)].into_iter().collect()));
+        let mut new = old.clone();
+        GrimoireUpdate::default()
+            .recipe("Salve", Recipe {
+                character: Some("Tashka".to_string()),
+                tags: ["cheap".to_string()].into_iter().collect(),
+                ..Recipe::new([("Leaf".to_string(), 4)].into_iter().collect())
+            })
+            .recipe("Tonic", Recipe::default())
+            .update(&mut new);
+
+        let changes: Vec<String> = diff_changes(&old, &new).iter().map(|x| x.to_string()).collect();
+
+        assert_eq!(changes, vec![
+            "recipes.Salve.ingredients.Leaf: 3 -> 4",
+            "recipes.Salve.character: - -> Tashka",
+            "recipes.Salve.tags:  -> cheap",
+            "recipes.Tonic: - -> added",
+        ]);
+    }
+
+    #[test]
+    fn test_no_changes() {
+        assert!(diff_changes(&grimoire(), &grimoire()).is_empty());
+    }
+}
@@ -4,9 +4,9 @@ use serde::{Serialize, Deserialize};
 
 use super::command::Commands;
 use crate::grimoire::Ingredient;
+use crate::provenance::Provenance;
 use crate::theoretical::Theoretical;
 use crate::effect::Effect;
-use strum::IntoEnumIterator;
 
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -14,7 +14,14 @@ pub enum IngredientUpdateCommand {
     ChangeMultiplier(Effect, Theoretical<f64>),
     ChangeTerm(Effect, Theoretical<f64>),
     SetSkill(Option<String>),
-    SetWeight(bool)
+    SetWeight(Theoretical<f64>),
+    AddTag(String),
+    RemoveTag(String),
+    AddAlias(String),
+    RemoveAlias(String),
+    SetSource(Option<String>),
+    SetTermProvenance(Effect, Option<Provenance>),
+    SetMultiplierProvenance(Effect, Option<Provenance>),
 }
 
 
@@ -35,7 +42,7 @@ impl IngredientUpdate {
         self
     }
 
-    pub fn set_weight(&mut self, weight: bool) -> &mut Self {
+    pub fn set_weight(&mut self, weight: Theoretical<f64>) -> &mut Self {
         self.commands.push(IngredientUpdateCommand::SetWeight(weight));
         self
     }
@@ -61,6 +68,46 @@ impl IngredientUpdate {
         self
     }    
 
+    pub fn add_tag(&mut self, tag: &str) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::AddTag(tag.to_string()));
+        self
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::RemoveTag(tag.to_string()));
+        self
+    }
+
+    pub fn add_alias(&mut self, alias: &str) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::AddAlias(alias.to_string()));
+        self
+    }
+
+    pub fn remove_alias(&mut self, alias: &str) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::RemoveAlias(alias.to_string()));
+        self
+    }
+
+    pub fn set_source(&mut self, source: &str) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::SetSource(Some(source.to_string())));
+        self
+    }
+
+    pub fn remove_source(&mut self) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::SetSource(None));
+        self
+    }
+
+    pub fn set_term_provenance(&mut self, effect: Effect, value: Option<Provenance>) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::SetTermProvenance(effect, value));
+        self
+    }
+
+    pub fn set_multiplier_provenance(&mut self, effect: Effect, value: Option<Provenance>) -> &mut Self {
+        self.commands.push(IngredientUpdateCommand::SetMultiplierProvenance(effect, value));
+        self
+    }
+
 }
 
 
@@ -77,8 +124,17 @@ impl Commands<Ingredient, IngredientUpdateCommand> for IngredientUpdate {
     fn create_from(ingredient: &Ingredient) -> Self {
         let mut result = Self::default();
         ingredient.modifiers.iter().for_each(
-            |(effect, modifier)|
-            { result.set_modifier(effect, modifier.term, modifier.multiplier); }
+            |(effect, modifier)| {
+                result.set_modifier(effect, modifier.term, modifier.multiplier);
+
+                if modifier.term_provenance.is_some() {
+                    result.set_term_provenance(effect, modifier.term_provenance.clone());
+                }
+
+                if modifier.multiplier_provenance.is_some() {
+                    result.set_multiplier_provenance(effect, modifier.multiplier_provenance.clone());
+                }
+            }
         );
         match &ingredient.skill {
             Some(x) => result.set_skill(x),
@@ -86,13 +142,22 @@ impl Commands<Ingredient, IngredientUpdateCommand> for IngredientUpdate {
         };
 
         result.set_weight(ingredient.weight);
+        ingredient.tags.iter().for_each(|x| { result.add_tag(x); });
+        ingredient.aliases.iter().for_each(|x| { result.add_alias(x); });
+
+        if let Some(x) = &ingredient.source {
+            result.set_source(x);
+        }
+
         result
     }
 
     fn diff(c1: &Ingredient, c2: &Ingredient) -> Self {
         let mut result = Self::default();
 
-        for effect in Effect::iter() {
+        let size = c1.modifiers.size().max(c2.modifiers.size());
+
+        for effect in (0..size).map(Effect) {
             let c2_term = c2.modifiers[effect].term;
             let c2_multiplier = c2.modifiers[effect].multiplier;
             if c1.modifiers[effect].term != c2_term {
@@ -101,6 +166,15 @@ impl Commands<Ingredient, IngredientUpdateCommand> for IngredientUpdate {
             if c1.modifiers[effect].multiplier != c2_multiplier {
                 result.set_multiplier(effect, c2_multiplier);
             }
+
+            let c2_term_provenance = &c2.modifiers[effect].term_provenance;
+            let c2_multiplier_provenance = &c2.modifiers[effect].multiplier_provenance;
+            if &c1.modifiers[effect].term_provenance != c2_term_provenance {
+                result.set_term_provenance(effect, c2_term_provenance.clone());
+            }
+            if &c1.modifiers[effect].multiplier_provenance != c2_multiplier_provenance {
+                result.set_multiplier_provenance(effect, c2_multiplier_provenance.clone());
+            }
         }
 
         if c1.weight != c2.weight {
@@ -111,6 +185,15 @@ impl Commands<Ingredient, IngredientUpdateCommand> for IngredientUpdate {
             result.commands.push(IngredientUpdateCommand::SetSkill(c2.skill.clone()));
         }
 
+        c2.tags.difference(&c1.tags).for_each(|x| { result.add_tag(x); });
+        c1.tags.difference(&c2.tags).for_each(|x| { result.remove_tag(x); });
+        c2.aliases.difference(&c1.aliases).for_each(|x| { result.add_alias(x); });
+        c1.aliases.difference(&c2.aliases).for_each(|x| { result.remove_alias(x); });
+
+        if c1.source != c2.source {
+            result.commands.push(IngredientUpdateCommand::SetSource(c2.source.clone()));
+        }
+
         result
     }
 
@@ -134,6 +217,27 @@ impl Commands<Ingredient, IngredientUpdateCommand> for IngredientUpdate {
                 },
                 IngredientUpdateCommand::SetWeight(value) => {
                     ingredient.weight = *value
+                },
+                IngredientUpdateCommand::AddTag(value) => {
+                    ingredient.tags.insert(value.clone());
+                },
+                IngredientUpdateCommand::RemoveTag(value) => {
+                    ingredient.tags.remove(value);
+                },
+                IngredientUpdateCommand::AddAlias(value) => {
+                    ingredient.aliases.insert(value.clone());
+                },
+                IngredientUpdateCommand::RemoveAlias(value) => {
+                    ingredient.aliases.remove(value);
+                },
+                IngredientUpdateCommand::SetSource(value) => {
+                    ingredient.source = value.clone()
+                },
+                IngredientUpdateCommand::SetTermProvenance(effect, value) => {
+                    ingredient.modifiers[*effect].term_provenance = value.clone();
+                },
+                IngredientUpdateCommand::SetMultiplierProvenance(effect, value) => {
+                    ingredient.modifiers[*effect].multiplier_provenance = value.clone();
                 }
             }
         }
@@ -169,6 +273,17 @@ impl Commands<Ingredient, IngredientUpdateCommand> for IngredientUpdate {
             (SetSkill(_), SetSkill(_)) => {
                 self._replace_last_two_with(last.clone())
             },
+            (SetSource(_), SetSource(_)) => {
+                self._replace_last_two_with(last.clone())
+            },
+            (SetTermProvenance(a, _), SetTermProvenance(b, _))
+            | (SetMultiplierProvenance(a, _), SetMultiplierProvenance(b, _)) => if a == b {
+                self._replace_last_two_with(last.clone())
+            },
+            (AddTag(a), RemoveTag(b)) | (RemoveTag(a), AddTag(b))
+            | (AddAlias(a), RemoveAlias(b)) | (RemoveAlias(a), AddAlias(b)) => if a == b {
+                self._replace_last_two_with(last.clone())
+            },
             (_, _) => {},
         }
 
@@ -190,8 +305,17 @@ impl From<Ingredient> for IngredientUpdate {
     fn from(ingredient: Ingredient) -> Self {
         let mut result = Self::default();
         ingredient.modifiers.iter().for_each(
-            |(effect, modifier)|
-            { result.set_modifier(effect, modifier.term, modifier.multiplier); }
+            |(effect, modifier)| {
+                result.set_modifier(effect, modifier.term, modifier.multiplier);
+
+                if modifier.term_provenance.is_some() {
+                    result.set_term_provenance(effect, modifier.term_provenance.clone());
+                }
+
+                if modifier.multiplier_provenance.is_some() {
+                    result.set_multiplier_provenance(effect, modifier.multiplier_provenance.clone());
+                }
+            }
         );
         match &ingredient.skill {
             Some(x) => result.set_skill(x),
@@ -199,6 +323,13 @@ impl From<Ingredient> for IngredientUpdate {
         };
 
         result.set_weight(ingredient.weight);
+        ingredient.tags.iter().for_each(|x| { result.add_tag(x); });
+        ingredient.aliases.iter().for_each(|x| { result.add_alias(x); });
+
+        if let Some(x) = &ingredient.source {
+            result.set_source(x);
+        }
+
         result
     }
     
@@ -223,6 +354,7 @@ impl From<&Ingredient> for IngredientUpdate {
 mod tests {
     use crate::grimoire::Ingredient;
     use crate::effect::Effect;
+    use crate::provenance::Provenance;
     use crate::theoretical::Theoretical;
 
     use super::IngredientUpdate;
@@ -260,7 +392,7 @@ mod tests {
         assert_eq!( new_ingredient.modifiers[Effect::PoisonOverTime].multiplier, Theoretical::Theory(4.0) );
 
         assert_eq!( new_ingredient.skill, Some("skill".to_string()) );
-        assert!( new_ingredient.weight );
+        assert_eq!( new_ingredient.weight, Theoretical::Known(2.) );
 
     }  
 
@@ -277,7 +409,7 @@ mod tests {
             .set_multiplier(Effect::PoisonOverTime, Theoretical::Theory(4.0))
 
             .set_skill("skill")
-            .set_weight(true)
+            .set_weight(Theoretical::Known(2.))
             .clone()
     }
 
@@ -302,8 +434,8 @@ mod tests {
     #[test]
     fn test_set_weight() {
         let mut ingredient = Ingredient::default();
-        IngredientUpdate::default().set_weight(true).update(&mut ingredient);
-        assert!( ingredient.weight );
+        IngredientUpdate::default().set_weight(Theoretical::Theory(1.5)).update(&mut ingredient);
+        assert_eq!( ingredient.weight, Theoretical::Theory(1.5) );
     }
 
     #[test]
@@ -348,6 +480,71 @@ mod tests {
         assert_eq!(ingredient.skill, Some("a".to_string()));        
     }
 
+    #[test]
+    fn test_tags_aliases_and_source() {
+        let mut ingredient = IngredientUpdate::default()
+            .add_tag("herb")
+            .add_tag("vendor")
+            .add_alias("Salvia")
+            .set_source("Meduli")
+            .create();
+        IngredientUpdate::default()
+            .remove_tag("vendor")
+            .remove_source()
+            .update(&mut ingredient);
+
+        assert!(ingredient.has_tag("herb"));
+        assert!(!ingredient.has_tag("vendor"));
+        assert!(ingredient.aliases.contains("Salvia"));
+        assert!(ingredient.source.is_none());
+    }
+
+    #[test]
+    fn test_set_provenance() {
+        let ingredient = IngredientUpdate::default()
+            .set_term(Effect::DirectHealing, Theoretical::Known(2.4))
+            .set_term_provenance(Effect::DirectHealing, Some(Provenance::new("wiki")))
+            .create();
+
+        let update = IngredientUpdate::create_from(&ingredient);
+
+        assert_eq!(update.create(), ingredient);
+        assert_eq!(
+            ingredient.modifiers[Effect::DirectHealing].term_provenance,
+            Some(Provenance::new("wiki"))
+        );
+        assert!(ingredient.modifiers[Effect::DirectHealing].multiplier_provenance.is_none());
+    }
+
+    #[test]
+    fn test_diff_provenance() {
+        let old = IngredientUpdate::default()
+            .set_term(Effect::DirectHealing, Theoretical::Known(2.4))
+            .create();
+        let new = IngredientUpdate::default()
+            .set_term(Effect::DirectHealing, Theoretical::Known(2.4))
+            .set_multiplier_provenance(Effect::Alcohol, Some(Provenance::new("wiki")))
+            .create();
+        let mut updated = old.clone();
+
+        IngredientUpdate::diff(&old, &new).update(&mut updated);
+
+        assert_eq!(updated, new);
+    }
+
+    #[test]
+    fn test_combine_last_add_remove_tag() {
+        let update = IngredientUpdate::default()
+            .add_tag("herb")
+            .remove_tag("herb")
+            .combine_last()
+            .clone();
+        let ingredient = &mut IngredientUpdate::default().add_tag("herb").create();
+        update.update(ingredient);
+        assert_eq!(update.len(), 1);
+        assert!(ingredient.tags.is_empty());
+    }
+
     #[test]
     fn test_combine_last_set_term() {
         let update = IngredientUpdate::default()
@@ -412,19 +609,23 @@ pub mod versioned {
     #[derive(Clone, Debug, Serialize, Deserialize)]
     pub enum IngredientUpdateVersioned {
         #[serde(rename="0")]
-        V0(v0::IngredientUpdateV0)
+        V0(v0::IngredientUpdateV0),
+        /// Numeric weights, tags, aliases, sources and provenance
+        #[serde(rename="1")]
+        V1(v1::IngredientUpdateV1),
     }
 
     impl From<IngredientUpdate> for IngredientUpdateVersioned {
         fn from(value: IngredientUpdate) -> Self {
-            Self::V0(value.into())
+            Self::V1(value.into())
         }
     }
 
     impl From<IngredientUpdateVersioned> for IngredientUpdate {
         fn from(value: IngredientUpdateVersioned) -> Self {
             match value {
-                IngredientUpdateVersioned::V0(x) => x.into()
+                IngredientUpdateVersioned::V0(x) => x.into(),
+                IngredientUpdateVersioned::V1(x) => x.into(),
             }
         }
     }
@@ -432,6 +633,7 @@ pub mod versioned {
     pub mod v0 {
         use super::*;
 
+        use crate::theoretical::Theoretical;
         use crate::theoretical::versioned::TheoreticalVersioned;
         use crate::effect::Effect;
 
@@ -440,7 +642,51 @@ pub mod versioned {
             commands: Vec<IngredientUpdateCommandV0>
         }
 
-        impl From<IngredientUpdate> for IngredientUpdateV0 {
+        impl From<IngredientUpdateV0> for IngredientUpdate {
+            fn from(value: IngredientUpdateV0) -> Self {
+                Self {
+                    commands: value.commands.into_iter().map(|x| x.into()).collect(),
+                }                
+            }
+        }
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub enum IngredientUpdateCommandV0 {
+            ChangeMultiplier(Effect, TheoreticalVersioned<f64>),
+            ChangeTerm(Effect, TheoreticalVersioned<f64>),
+            SetSkill(Option<String>),
+            SetWeight(bool)
+        }
+
+        impl From<IngredientUpdateCommandV0> for IngredientUpdateCommand {
+            fn from(value: IngredientUpdateCommandV0) -> Self {
+                match value {
+                    IngredientUpdateCommandV0::ChangeMultiplier(n, v) => 
+                        IngredientUpdateCommand::ChangeMultiplier(n, v.into()),
+                    IngredientUpdateCommandV0::ChangeTerm(n, v) =>
+                        IngredientUpdateCommand::ChangeTerm(n, v.into()),
+                    IngredientUpdateCommandV0::SetSkill(n) => 
+                        IngredientUpdateCommand::SetSkill(n),
+                    IngredientUpdateCommandV0::SetWeight(n) =>
+                        IngredientUpdateCommand::SetWeight(Theoretical::Known(if n { 1. } else { 0. })),
+                }                
+            }
+        }
+    }
+
+    pub mod v1 {
+        use super::*;
+
+        use crate::theoretical::versioned::TheoreticalVersioned;
+        use crate::effect::Effect;
+        use crate::provenance::Provenance;
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct IngredientUpdateV1 {
+            commands: Vec<IngredientUpdateCommandV1>
+        }
+
+        impl From<IngredientUpdate> for IngredientUpdateV1 {
             fn from(value: IngredientUpdate) -> Self {
                 Self {
                     commands: value.commands.into_iter().map(|x| x.into()).collect(),
@@ -448,8 +694,8 @@ pub mod versioned {
             }
         }
 
-        impl From<IngredientUpdateV0> for IngredientUpdate {
-            fn from(value: IngredientUpdateV0) -> Self {
+        impl From<IngredientUpdateV1> for IngredientUpdate {
+            fn from(value: IngredientUpdateV1) -> Self {
                 Self {
                     commands: value.commands.into_iter().map(|x| x.into()).collect(),
                 }                
@@ -457,39 +703,74 @@ pub mod versioned {
         }
 
         #[derive(Clone, Debug, Serialize, Deserialize)]
-        pub enum IngredientUpdateCommandV0 {
+        pub enum IngredientUpdateCommandV1 {
             ChangeMultiplier(Effect, TheoreticalVersioned<f64>),
             ChangeTerm(Effect, TheoreticalVersioned<f64>),
             SetSkill(Option<String>),
-            SetWeight(bool)
+            SetWeight(TheoreticalVersioned<f64>),
+            AddTag(String),
+            RemoveTag(String),
+            AddAlias(String),
+            RemoveAlias(String),
+            SetSource(Option<String>),
+            SetTermProvenance(Effect, Option<Provenance>),
+            SetMultiplierProvenance(Effect, Option<Provenance>),
         }
 
-        impl From<IngredientUpdateCommand> for IngredientUpdateCommandV0 {
+        impl From<IngredientUpdateCommand> for IngredientUpdateCommandV1 {
             fn from(value: IngredientUpdateCommand) -> Self {
                 match value {
                     IngredientUpdateCommand::ChangeMultiplier(n, v) => 
-                        IngredientUpdateCommandV0::ChangeMultiplier(n, v.into()),
+                        IngredientUpdateCommandV1::ChangeMultiplier(n, v.into()),
                     IngredientUpdateCommand::ChangeTerm(n, v) =>
-                        IngredientUpdateCommandV0::ChangeTerm(n, v.into()),
+                        IngredientUpdateCommandV1::ChangeTerm(n, v.into()),
                     IngredientUpdateCommand::SetSkill(n) => 
-                        IngredientUpdateCommandV0::SetSkill(n),
+                        IngredientUpdateCommandV1::SetSkill(n),
                     IngredientUpdateCommand::SetWeight(n) =>
-                        IngredientUpdateCommandV0::SetWeight(n)
+                        IngredientUpdateCommandV1::SetWeight(n.into()),
+                    IngredientUpdateCommand::AddTag(n) =>
+                        IngredientUpdateCommandV1::AddTag(n),
+                    IngredientUpdateCommand::RemoveTag(n) =>
+                        IngredientUpdateCommandV1::RemoveTag(n),
+                    IngredientUpdateCommand::AddAlias(n) =>
+                        IngredientUpdateCommandV1::AddAlias(n),
+                    IngredientUpdateCommand::RemoveAlias(n) =>
+                        IngredientUpdateCommandV1::RemoveAlias(n),
+                    IngredientUpdateCommand::SetSource(n) =>
+                        IngredientUpdateCommandV1::SetSource(n),
+                    IngredientUpdateCommand::SetTermProvenance(n, v) =>
+                        IngredientUpdateCommandV1::SetTermProvenance(n, v),
+                    IngredientUpdateCommand::SetMultiplierProvenance(n, v) =>
+                        IngredientUpdateCommandV1::SetMultiplierProvenance(n, v),
                 }
             }
         }
 
-        impl From<IngredientUpdateCommandV0> for IngredientUpdateCommand {
-            fn from(value: IngredientUpdateCommandV0) -> Self {
+        impl From<IngredientUpdateCommandV1> for IngredientUpdateCommand {
+            fn from(value: IngredientUpdateCommandV1) -> Self {
                 match value {
-                    IngredientUpdateCommandV0::ChangeMultiplier(n, v) => 
+                    IngredientUpdateCommandV1::ChangeMultiplier(n, v) => 
                         IngredientUpdateCommand::ChangeMultiplier(n, v.into()),
-                    IngredientUpdateCommandV0::ChangeTerm(n, v) =>
+                    IngredientUpdateCommandV1::ChangeTerm(n, v) =>
                         IngredientUpdateCommand::ChangeTerm(n, v.into()),
-                    IngredientUpdateCommandV0::SetSkill(n) => 
+                    IngredientUpdateCommandV1::SetSkill(n) => 
                         IngredientUpdateCommand::SetSkill(n),
-                    IngredientUpdateCommandV0::SetWeight(n) =>
-                        IngredientUpdateCommand::SetWeight(n)
+                    IngredientUpdateCommandV1::SetWeight(n) =>
+                        IngredientUpdateCommand::SetWeight(n.into()),
+                    IngredientUpdateCommandV1::AddTag(n) =>
+                        IngredientUpdateCommand::AddTag(n),
+                    IngredientUpdateCommandV1::RemoveTag(n) =>
+                        IngredientUpdateCommand::RemoveTag(n),
+                    IngredientUpdateCommandV1::AddAlias(n) =>
+                        IngredientUpdateCommand::AddAlias(n),
+                    IngredientUpdateCommandV1::RemoveAlias(n) =>
+                        IngredientUpdateCommand::RemoveAlias(n),
+                    IngredientUpdateCommandV1::SetSource(n) =>
+                        IngredientUpdateCommand::SetSource(n),
+                    IngredientUpdateCommandV1::SetTermProvenance(n, v) =>
+                        IngredientUpdateCommand::SetTermProvenance(n, v),
+                    IngredientUpdateCommandV1::SetMultiplierProvenance(n, v) =>
+                        IngredientUpdateCommand::SetMultiplierProvenance(n, v),
                 }                
             }
         }
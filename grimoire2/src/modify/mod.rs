@@ -2,13 +2,15 @@ pub mod character;
 pub mod skill;
 pub mod ingredient;
 pub mod command;
+pub mod changes;
 
 use std::ops::Index;
 use command::Commands;
 
 use serde::{Serialize, Deserialize};
 
-use crate::{grimoire::{Grimoire, Ingredient}, prelude::{Character, Skill}};
+use crate::{grimoire::{Grimoire, Ingredient, Clade, Production, Recipe, Ruleset, SkillPointRules}, prelude::{Character, Skill}};
+use crate::effect::EffectDefinition;
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +21,22 @@ pub enum GrimoireUpdateCommand {
     RemoveCharacter(String),
     RemoveSkill(String),
     RemoveIngredient(String),
+    Clade(String, Clade),
+    RemoveClade(String),
+    SetRules(Option<SkillPointRules>),
+    /// Adds the effect to the catalogue, or redefines it
+    Effect(String, EffectDefinition),
+    /// Adds the ruleset, or replaces the one of the same name
+    Ruleset(String, Ruleset),
+    RemoveRuleset(String),
+    /// Puts the named ruleset in effect
+    SelectRuleset(String),
+    /// Sets how the named ingredient is made, replacing the production it had
+    Production(String, Production),
+    RemoveProduction(String),
+    /// Sets the named recipe, replacing the one it had
+    Recipe(String, Recipe),
+    RemoveRecipe(String),
 }
 
 
@@ -57,6 +75,61 @@ impl GrimoireUpdate {
         self.commands.push(GrimoireUpdateCommand::RemoveIngredient(name.to_string()));
         self    
     }
+
+    pub fn clade(&mut self, name: &str, clade: Clade) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::Clade(name.to_string(), clade));
+        self
+    }
+
+    pub fn remove_clade(&mut self, name: &str) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::RemoveClade(name.to_string()));
+        self
+    }
+
+    pub fn set_rules(&mut self, rules: Option<SkillPointRules>) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::SetRules(rules));
+        self
+    }
+
+    pub fn effect(&mut self, id: &str, definition: EffectDefinition) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::Effect(id.to_string(), definition));
+        self
+    }
+
+    pub fn ruleset(&mut self, name: &str, ruleset: Ruleset) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::Ruleset(name.to_string(), ruleset));
+        self
+    }
+
+    pub fn remove_ruleset(&mut self, name: &str) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::RemoveRuleset(name.to_string()));
+        self
+    }
+
+    pub fn select_ruleset(&mut self, name: &str) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::SelectRuleset(name.to_string()));
+        self
+    }
+
+    pub fn production(&mut self, name: &str, production: Production) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::Production(name.to_string(), production));
+        self
+    }
+
+    pub fn remove_production(&mut self, name: &str) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::RemoveProduction(name.to_string()));
+        self
+    }
+
+    pub fn recipe(&mut self, name: &str, recipe: Recipe) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::Recipe(name.to_string(), recipe));
+        self
+    }
+
+    pub fn remove_recipe(&mut self, name: &str) -> &mut Self {
+        self.commands.push(GrimoireUpdateCommand::RemoveRecipe(name.to_string()));
+        self
+    }
 }
 
 
@@ -87,6 +160,10 @@ impl Commands<Grimoire, GrimoireUpdateCommand> for GrimoireUpdate {
     fn create_from(value: &Grimoire) -> Self {
         let mut result = Self::default();
 
+        for (_, id, definition) in value.effects.iter() {
+            result.effect(id, definition.clone());
+        }
+
         for (name, character) in value.characters.iter() {
             result.character(name.as_str(), character.into());
         };
@@ -99,12 +176,39 @@ impl Commands<Grimoire, GrimoireUpdateCommand> for GrimoireUpdate {
             result.ingredient(name.as_str(), ingredient.into());
         };
 
+        for (name, clade) in value.clades.iter() {
+            result.clade(name.as_str(), clade.clone());
+        };
+
+        if value.rules.is_some() {
+            result.set_rules(value.rules);
+        }
+
+        for (name, ruleset) in value.rulesets.iter() {
+            result.ruleset(name.as_str(), ruleset.clone());
+        }
+
+        for (name, production) in value.productions.iter() {
+            result.production(name.as_str(), production.clone());
+        }
+
+        for (name, recipe) in value.recipes.iter() {
+            result.recipe(name.as_str(), recipe.clone());
+        }
+
         result
     }
 
     fn diff(c1: &Grimoire, c2: &Grimoire) -> Self {
         let mut result = Self::default();
 
+        for (_, id, definition) in c2.effects.iter() {
+            let old = c1.effects.by_id(id).and_then(|x| c1.effects.get(x));
+            if old != Some(definition) {
+                result.effect(id, definition.clone());
+            }
+        }
+
         for (name, c2_v) in &c2.characters {
             let maybe_c1_v = c1.characters.get(name);
 
@@ -154,6 +258,62 @@ impl Commands<Grimoire, GrimoireUpdateCommand> for GrimoireUpdate {
             }
         }
 
+        for (name, c2_v) in &c2.clades {
+            if c1.clades.get(name) != Some(c2_v) {
+                result.clade(name.as_str(), c2_v.clone());
+            }
+        }
+
+        for name in c1.clades.keys() {
+            if !c2.clades.contains_key(name) {
+                result.remove_clade(name.as_str());
+            }
+        }
+
+        if c1.rules != c2.rules {
+            result.set_rules(c2.rules);
+        }
+
+        for (name, c2_v) in &c2.rulesets {
+            if c1.rulesets.get(name) != Some(c2_v) {
+                result.ruleset(name.as_str(), c2_v.clone());
+            }
+        }
+
+        for name in c1.rulesets.keys() {
+            if !c2.rulesets.contains_key(name) {
+                result.remove_ruleset(name.as_str());
+            }
+        }
+
+        if c1.selected_ruleset != c2.selected_ruleset {
+            result.select_ruleset(c2.selected_ruleset.as_deref().unwrap_or("default"));
+        }
+
+        for (name, c2_v) in &c2.productions {
+            if c1.productions.get(name) != Some(c2_v) {
+                result.production(name.as_str(), c2_v.clone());
+            }
+        }
+
+        for name in c1.productions.keys() {
+            if !c2.productions.contains_key(name) {
+                result.remove_production(name.as_str());
+            }
+        }
+
+        for (name, c2_v) in &c2.recipes {
+            if c1.recipes.get(name) != Some(c2_v) {
+                result.recipe(name.as_str(), c2_v.clone());
+            }
+        }
+
+        for name in c1.recipes.keys() {
+            if !c2.recipes.contains_key(name) {
+                result.remove_recipe(name.as_str());
+            }
+        }
+
         result
     }
 
@@ -186,7 +346,41 @@ impl Commands<Grimoire, GrimoireUpdateCommand> for GrimoireUpdate {
                 },
                 GrimoireUpdateCommand::RemoveIngredient(name) => {
                     grimoire.ingredients.remove(name);
-                }
+                },
+                GrimoireUpdateCommand::Clade(name, clade) => {
+                    grimoire.clades.insert(name.clone(), clade.clone());
+                },
+                GrimoireUpdateCommand::RemoveClade(name) => {
+                    grimoire.clades.remove(name);
+                },
+                GrimoireUpdateCommand::SetRules(rules) => {
+                    grimoire.rules = *rules;
+                },
+                GrimoireUpdateCommand::Effect(id, definition) => {
+                    grimoire.effects.insert(id, definition.clone());
+                },
+                GrimoireUpdateCommand::Ruleset(name, ruleset) => {
+                    grimoire.rulesets.insert(name.clone(), ruleset.clone());
+                },
+                GrimoireUpdateCommand::RemoveRuleset(name) => {
+                    grimoire.rulesets.shift_remove(name);
+                },
+                GrimoireUpdateCommand::SelectRuleset(name) => {
+                    // Unknown rulesets leave the one in effect as it is
+                    grimoire.select_ruleset(name).ok();
+                },
+                GrimoireUpdateCommand::Production(name, production) => {
+                    grimoire.productions.insert(name.clone(), production.clone());
+                },
+                GrimoireUpdateCommand::RemoveProduction(name) => {
+                    grimoire.productions.shift_remove(name);
+                },
+                GrimoireUpdateCommand::Recipe(name, recipe) => {
+                    grimoire.recipes.insert(name.clone(), recipe.clone());
+                },
+                GrimoireUpdateCommand::RemoveRecipe(name) => {
+                    grimoire.recipes.shift_remove(name);
+                },
             }
         }
     }
@@ -231,7 +425,20 @@ impl Commands<Grimoire, GrimoireUpdateCommand> for GrimoireUpdate {
 
             (Character(a, _), RemoveCharacter(b))
             | (Skill(a, _), RemoveSkill(b))
-            | (Ingredient(a, _), RemoveIngredient(b)) if a == b => {
+            | (Ingredient(a, _), RemoveIngredient(b))
+            | (Clade(a, _), RemoveClade(b))
+            | (Clade(a, _), Clade(b, _))
+            | (Effect(a, _), Effect(b, _))
+            | (Ruleset(a, _), RemoveRuleset(b))
+            | (Ruleset(a, _), Ruleset(b, _))
+            | (Production(a, _), RemoveProduction(b))
+            | (Production(a, _), Production(b, _))
+            | (Recipe(a, _), RemoveRecipe(b))
+            | (Recipe(a, _), Recipe(b, _)) if a == b => {
+                *prev = last
+            },
+            (SetRules(_), SetRules(_))
+            | (SelectRuleset(_), SelectRuleset(_)) => {
                 *prev = last
             },
             _ => { self.commands.push(last) }
@@ -262,6 +469,7 @@ mod tests {
     use crate::prelude::Grimoire;
     use super::Commands;
     use super::GrimoireUpdateCommand;
+    use super::versioned::GrimoireUpdateVersioned;
 
 
     use proptest::prelude::*;
@@ -269,10 +477,13 @@ mod tests {
 
     proptest! {
         #[test]
-        fn test_diff(v1 in grimoire_strategy(), v2 in grimoire_strategy()) {            
+        fn test_diff(v1 in grimoire_strategy(), v2 in grimoire_strategy()) {
+            // Effects are never removed from the catalogue
+            prop_assume!(v1.effects.len() <= v2.effects.len());
             let mut v1_ = v1.clone();
             let diff = GrimoireUpdate::diff(&v1, &v2);
             diff.update(&mut v1_);
+            prop_assert_eq!(v1_.ruleset(), v2.ruleset());
             prop_assert_eq!(v1_, v2);
         }
     }    
@@ -300,6 +511,25 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_versioned() {
+        let json = serde_json::to_string(&GrimoireUpdateVersioned::from(grimoire_update())).unwrap();
+        let update: GrimoireUpdate = serde_json::from_str::<GrimoireUpdateVersioned>(&json).unwrap().into();
+
+        assert!(json.starts_with(r#"{"1":"#));
+        assert_eq!(update.create(), grimoire_update().create());
+
+        let v0 = r#"{"0": {"commands": [
+            {"Skill": ["a", {"0": {"commands": [{"SetEffectiveness": {"0": {"Known": 0.5}}}]}}]},
+            {"Ingredient": ["A", {"0": {"commands": [{"SetSkill": "a"}, {"SetWeight": true}]}}]}
+        ]}}"#;
+        let grimoire = GrimoireUpdate::from(serde_json::from_str::<GrimoireUpdateVersioned>(v0).unwrap()).create();
+
+        assert_eq!(grimoire.skills["a"].effectiveness, Theoretical::Known(0.5));
+        assert_eq!(grimoire.ingredients["A"].skill, Some("a".to_string()));
+        assert_eq!(grimoire.ingredients["A"].weight, Theoretical::Known(1.));
+    }
+
     #[test]
     fn test_update() {
         let mut grimoire = grimoire_update().create();
@@ -495,23 +725,33 @@ pub mod versioned {
     use super::character::versioned::CharacterUpdateVersioned;
     use super::skill::versioned::SkillUpdateVersioned;
     use super::ingredient::versioned::IngredientUpdateVersioned;
+    use crate::effect::EffectDefinition;
+    use crate::grimoire::SkillPointRules;
+    use crate::grimoire::clade::versioned::CladeVersioned;
+    use crate::grimoire::ruleset::versioned::RulesetVersioned;
+    use crate::grimoire::production::versioned::ProductionVersioned;
+    use crate::grimoire::recipe::versioned::RecipeVersioned;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum GrimoireUpdateVersioned {
         #[serde(rename="0")]
-        V0(v0::GrimoireUpdateV0)
+        V0(v0::GrimoireUpdateV0),
+        /// Adds clades, rules, effects, rulesets, productions and recipes
+        #[serde(rename="1")]
+        V1(v1::GrimoireUpdateV1),
     }
 
     impl From<GrimoireUpdate> for GrimoireUpdateVersioned {
         fn from(value: GrimoireUpdate) -> Self {
-            Self::V0(value.into())
+            Self::V1(value.into())
         }
     }
 
     impl From<GrimoireUpdateVersioned> for GrimoireUpdate {
         fn from(value: GrimoireUpdateVersioned) -> Self {
             match value {
-                GrimoireUpdateVersioned::V0(x) => x.into()
+                GrimoireUpdateVersioned::V0(x) => x.into(),
+                GrimoireUpdateVersioned::V1(x) => x.into(),
             }
         }
     }
@@ -524,7 +764,53 @@ pub mod versioned {
             commands: Vec<GrimoireUpdateCommandV0>
         }
 
-        impl From<GrimoireUpdate> for GrimoireUpdateV0 {
+        impl From<GrimoireUpdateV0> for GrimoireUpdate {
+            fn from(value: GrimoireUpdateV0) -> Self {
+                Self {
+                    commands: value.commands.into_iter().map(|x| x.into()).collect(),
+                }                
+            }
+        }
+
+        #[derive(Debug, Clone, Serialize, Deserialize)]
+        pub enum GrimoireUpdateCommandV0 {
+            Character(String, CharacterUpdateVersioned),
+            Skill(String, SkillUpdateVersioned),
+            Ingredient(String, IngredientUpdateVersioned),
+            RemoveCharacter(String),
+            RemoveSkill(String),
+            RemoveIngredient(String),                   
+        }
+
+        impl From<GrimoireUpdateCommandV0> for GrimoireUpdateCommand {
+            fn from(value: GrimoireUpdateCommandV0) -> Self {
+                match value {
+                    GrimoireUpdateCommandV0::Character(n, c) => 
+                        GrimoireUpdateCommand::Character(n, c.into()),
+                    GrimoireUpdateCommandV0::Skill(n, c) => 
+                        GrimoireUpdateCommand::Skill(n, c.into()),
+                    GrimoireUpdateCommandV0::Ingredient(n, c) => 
+                        GrimoireUpdateCommand::Ingredient(n, c.into()),
+                    GrimoireUpdateCommandV0::RemoveCharacter(n) =>
+                        GrimoireUpdateCommand::RemoveCharacter(n),
+                    GrimoireUpdateCommandV0::RemoveSkill(n) =>
+                        GrimoireUpdateCommand::RemoveSkill(n),
+                    GrimoireUpdateCommandV0::RemoveIngredient(n) =>
+                        GrimoireUpdateCommand::RemoveIngredient(n),
+                }               
+            }
+        }
+    }
+
+    pub mod v1 {
+        use super::*;
+
+        #[derive(Debug, Clone, Serialize, Deserialize)]
+        pub struct GrimoireUpdateV1 {
+            commands: Vec<GrimoireUpdateCommandV1>
+        }
+
+        impl From<GrimoireUpdate> for GrimoireUpdateV1 {
             fn from(value: GrimoireUpdate) -> Self {
                 Self {
                     commands: value.commands.into_iter().map(|x| x.into()).collect(),
@@ -532,8 +818,8 @@ pub mod versioned {
             }
         }
 
-        impl From<GrimoireUpdateV0> for GrimoireUpdate {
-            fn from(value: GrimoireUpdateV0) -> Self {
+        impl From<GrimoireUpdateV1> for GrimoireUpdate {
+            fn from(value: GrimoireUpdateV1) -> Self {
                 Self {
                     commands: value.commands.into_iter().map(|x| x.into()).collect(),
                 }                
@@ -541,49 +827,104 @@ pub mod versioned {
         }
 
         #[derive(Debug, Clone, Serialize, Deserialize)]
-        pub enum GrimoireUpdateCommandV0 {
+        pub enum GrimoireUpdateCommandV1 {
             Character(String, CharacterUpdateVersioned),
             Skill(String, SkillUpdateVersioned),
             Ingredient(String, IngredientUpdateVersioned),
             RemoveCharacter(String),
             RemoveSkill(String),
             RemoveIngredient(String),                   
+            Clade(String, CladeVersioned),
+            RemoveClade(String),
+            SetRules(Option<SkillPointRules>),
+            Effect(String, EffectDefinition),
+            Ruleset(String, RulesetVersioned),
+            RemoveRuleset(String),
+            SelectRuleset(String),
+            Production(String, ProductionVersioned),
+            RemoveProduction(String),
+            Recipe(String, RecipeVersioned),
+            RemoveRecipe(String),
         }
 
-        impl From<GrimoireUpdateCommand> for GrimoireUpdateCommandV0 {
+        impl From<GrimoireUpdateCommand> for GrimoireUpdateCommandV1 {
             fn from(value: GrimoireUpdateCommand) -> Self {
                 match value {
                     GrimoireUpdateCommand::Character(n, c) => 
-                        GrimoireUpdateCommandV0::Character(n, c.into()),
+                        GrimoireUpdateCommandV1::Character(n, c.into()),
                     GrimoireUpdateCommand::Skill(n, c) => 
-                        GrimoireUpdateCommandV0::Skill(n, c.into()),
+                        GrimoireUpdateCommandV1::Skill(n, c.into()),
                     GrimoireUpdateCommand::Ingredient(n, c) => 
-                        GrimoireUpdateCommandV0::Ingredient(n, c.into()),
+                        GrimoireUpdateCommandV1::Ingredient(n, c.into()),
                     GrimoireUpdateCommand::RemoveCharacter(n) =>
-                        GrimoireUpdateCommandV0::RemoveCharacter(n),
+                        GrimoireUpdateCommandV1::RemoveCharacter(n),
                     GrimoireUpdateCommand::RemoveSkill(n) =>
-                        GrimoireUpdateCommandV0::RemoveSkill(n),
+                        GrimoireUpdateCommandV1::RemoveSkill(n),
                     GrimoireUpdateCommand::RemoveIngredient(n) =>
-                        GrimoireUpdateCommandV0::RemoveIngredient(n),
+                        GrimoireUpdateCommandV1::RemoveIngredient(n),
+                    GrimoireUpdateCommand::Clade(n, c) =>
+                        GrimoireUpdateCommandV1::Clade(n, c.into()),
+                    GrimoireUpdateCommand::RemoveClade(n) =>
+                        GrimoireUpdateCommandV1::RemoveClade(n),
+                    GrimoireUpdateCommand::SetRules(r) =>
+                        GrimoireUpdateCommandV1::SetRules(r),
+                    GrimoireUpdateCommand::Effect(n, e) =>
+                        GrimoireUpdateCommandV1::Effect(n, e),
+                    GrimoireUpdateCommand::Ruleset(n, r) =>
+                        GrimoireUpdateCommandV1::Ruleset(n, r.into()),
+                    GrimoireUpdateCommand::RemoveRuleset(n) =>
+                        GrimoireUpdateCommandV1::RemoveRuleset(n),
+                    GrimoireUpdateCommand::SelectRuleset(n) =>
+                        GrimoireUpdateCommandV1::SelectRuleset(n),
+                    GrimoireUpdateCommand::Production(n, p) =>
+                        GrimoireUpdateCommandV1::Production(n, p.into()),
+                    GrimoireUpdateCommand::RemoveProduction(n) =>
+                        GrimoireUpdateCommandV1::RemoveProduction(n),
+                    GrimoireUpdateCommand::Recipe(n, r) =>
+                        GrimoireUpdateCommandV1::Recipe(n, r.into()),
+                    GrimoireUpdateCommand::RemoveRecipe(n) =>
+                        GrimoireUpdateCommandV1::RemoveRecipe(n),
                 }
             }
         }
 
-        impl From<GrimoireUpdateCommandV0> for GrimoireUpdateCommand {
-            fn from(value: GrimoireUpdateCommandV0) -> Self {
+        impl From<GrimoireUpdateCommandV1> for GrimoireUpdateCommand {
+            fn from(value: GrimoireUpdateCommandV1) -> Self {
                 match value {
-                    GrimoireUpdateCommandV0::Character(n, c) => 
+                    GrimoireUpdateCommandV1::Character(n, c) => 
                         GrimoireUpdateCommand::Character(n, c.into()),
-                    GrimoireUpdateCommandV0::Skill(n, c) => 
+                    GrimoireUpdateCommandV1::Skill(n, c) => 
                         GrimoireUpdateCommand::Skill(n, c.into()),
-                    GrimoireUpdateCommandV0::Ingredient(n, c) => 
+                    GrimoireUpdateCommandV1::Ingredient(n, c) => 
                         GrimoireUpdateCommand::Ingredient(n, c.into()),
-                    GrimoireUpdateCommandV0::RemoveCharacter(n) =>
+                    GrimoireUpdateCommandV1::RemoveCharacter(n) =>
                         GrimoireUpdateCommand::RemoveCharacter(n),
-                    GrimoireUpdateCommandV0::RemoveSkill(n) =>
+                    GrimoireUpdateCommandV1::RemoveSkill(n) =>
                         GrimoireUpdateCommand::RemoveSkill(n),
-                    GrimoireUpdateCommandV0::RemoveIngredient(n) =>
+                    GrimoireUpdateCommandV1::RemoveIngredient(n) =>
                         GrimoireUpdateCommand::RemoveIngredient(n),
+                    GrimoireUpdateCommandV1::Clade(n, c) =>
+                        GrimoireUpdateCommand::Clade(n, c.into()),
+                    GrimoireUpdateCommandV1::RemoveClade(n) =>
+                        GrimoireUpdateCommand::RemoveClade(n),
+                    GrimoireUpdateCommandV1::SetRules(r) =>
+                        GrimoireUpdateCommand::SetRules(r),
+                    GrimoireUpdateCommandV1::Effect(n, e) =>
+                        GrimoireUpdateCommand::Effect(n, e),
+                    GrimoireUpdateCommandV1::Ruleset(n, r) =>
+                        GrimoireUpdateCommand::Ruleset(n, r.into()),
+                    GrimoireUpdateCommandV1::RemoveRuleset(n) =>
+                        GrimoireUpdateCommand::RemoveRuleset(n),
+                    GrimoireUpdateCommandV1::SelectRuleset(n) =>
+                        GrimoireUpdateCommand::SelectRuleset(n),
+                    GrimoireUpdateCommandV1::Production(n, p) =>
+                        GrimoireUpdateCommand::Production(n, p.into()),
+                    GrimoireUpdateCommandV1::RemoveProduction(n) =>
+                        GrimoireUpdateCommand::RemoveProduction(n),
+                    GrimoireUpdateCommandV1::Recipe(n, r) =>
+                        GrimoireUpdateCommand::Recipe(n, r.into()),
+                    GrimoireUpdateCommandV1::RemoveRecipe(n) =>
+                        GrimoireUpdateCommand::RemoveRecipe(n),
                 }               
             }
         }
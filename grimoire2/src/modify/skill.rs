@@ -1,6 +1,7 @@
 use std::ops::Index;
 use serde::{Serialize, Deserialize};
-use crate::{theoretical::Theoretical, prelude::Skill};
+use crate::{theoretical::Theoretical, prelude::Skill, grimoire::SkillCategory};
+use crate::provenance::Provenance;
 
 
 use super::Commands;
@@ -10,7 +11,10 @@ use super::Commands;
 pub enum SkillUpdateCommand {
     SetEffectiveness(Theoretical<f64>),
     SetParent(Option<String>),
-    SetParent2(Option<String>)
+    SetParent2(Option<String>),
+    SetCategory(SkillCategory),
+    SetCost(u8),
+    SetEffectivenessProvenance(Option<Provenance>),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -51,6 +55,21 @@ impl SkillUpdate {
         self
     }
 
+    pub fn set_category(&mut self, value: SkillCategory) -> &mut Self {
+        self.commands.push(SkillUpdateCommand::SetCategory(value));
+        self
+    }
+
+    pub fn set_cost(&mut self, value: u8) -> &mut Self {
+        self.commands.push(SkillUpdateCommand::SetCost(value));
+        self
+    }
+
+    pub fn set_effectiveness_provenance(&mut self, value: Option<Provenance>) -> &mut Self {
+        self.commands.push(SkillUpdateCommand::SetEffectivenessProvenance(value));
+        self
+    }
+
 }
 
 
@@ -78,6 +97,13 @@ impl Commands<Skill, SkillUpdateCommand> for SkillUpdate {
             None => update.remove_parent_2()
         };
 
+        update.set_category(skill.category);
+        update.set_cost(skill.cost);
+
+        if skill.effectiveness_provenance.is_some() {
+            update.set_effectiveness_provenance(skill.effectiveness_provenance.clone());
+        }
+
         update
     }
 
@@ -92,6 +118,15 @@ impl Commands<Skill, SkillUpdateCommand> for SkillUpdate {
         if c2.parent != c1.parent {
             result.commands.push(SkillUpdateCommand::SetParent(c2.parent.clone()))
         }
+        if c2.category != c1.category {
+            result.set_category(c2.category);
+        }
+        if c2.cost != c1.cost {
+            result.set_cost(c2.cost);
+        }
+        if c2.effectiveness_provenance != c1.effectiveness_provenance {
+            result.set_effectiveness_provenance(c2.effectiveness_provenance.clone());
+        }
         result
     }
 
@@ -101,6 +136,10 @@ impl Commands<Skill, SkillUpdateCommand> for SkillUpdate {
                 SkillUpdateCommand::SetEffectiveness(x) => skill.effectiveness = *x,
                 SkillUpdateCommand::SetParent(x) => skill.parent = x.clone(),
                 SkillUpdateCommand::SetParent2(x) => skill.parent_2 = x.clone(),
+                SkillUpdateCommand::SetCategory(x) => skill.category = *x,
+                SkillUpdateCommand::SetCost(x) => skill.cost = *x,
+                SkillUpdateCommand::SetEffectivenessProvenance(x) =>
+                    skill.effectiveness_provenance = x.clone(),
             }
         };
     }
@@ -127,6 +166,10 @@ impl Commands<Skill, SkillUpdateCommand> for SkillUpdate {
                 self._replace_last_two_with(last.clone()),
             (SetParent(_), SetParent(_)) => self._replace_last_two_with(last.clone()),
             (SetParent2(_), SetParent2(_)) => self._replace_last_two_with(last.clone()),
+            (SetCategory(_), SetCategory(_)) => self._replace_last_two_with(last.clone()),
+            (SetCost(_), SetCost(_)) => self._replace_last_two_with(last.clone()),
+            (SetEffectivenessProvenance(_), SetEffectivenessProvenance(_)) =>
+                self._replace_last_two_with(last.clone()),
             (_, _) => {}
         }
 
@@ -173,19 +216,23 @@ pub mod versioned {
     #[derive(Clone, Debug, Serialize, Deserialize)]
     pub enum SkillUpdateVersioned {
         #[serde(rename="0")]
-        V0(v0::SkillUpdateV0)
+        V0(v0::SkillUpdateV0),
+        /// Adds categories, costs and provenance
+        #[serde(rename="1")]
+        V1(v1::SkillUpdateV1),
     }
 
     impl From<SkillUpdate> for SkillUpdateVersioned {
         fn from(value: SkillUpdate) -> Self {
-            Self::V0(value.into())
+            Self::V1(value.into())
         }
     }
 
     impl From<SkillUpdateVersioned> for SkillUpdate {
         fn from(value: SkillUpdateVersioned) -> Self {
             match value {
-                SkillUpdateVersioned::V0(x) => x.into()
+                SkillUpdateVersioned::V0(x) => x.into(),
+                SkillUpdateVersioned::V1(x) => x.into(),
             }
         }
     }
@@ -199,7 +246,45 @@ pub mod versioned {
             commands: Vec<SkillUpdateCommandV0>
         }
 
-        impl From<SkillUpdate> for SkillUpdateV0 {
+        impl From<SkillUpdateV0> for SkillUpdate {
+            fn from(value: SkillUpdateV0) -> Self {
+                Self {
+                    commands: value.commands.into_iter().map(|x| x.into()).collect(),
+                }                
+            }
+        }
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub enum SkillUpdateCommandV0 {
+            SetEffectiveness(TheoreticalVersioned<f64>),
+            SetParent(Option<String>),
+            SetParent2(Option<String>)
+        }
+
+        impl From<SkillUpdateCommandV0> for SkillUpdateCommand {
+            fn from(value: SkillUpdateCommandV0) -> Self {
+                match value {
+                    SkillUpdateCommandV0::SetEffectiveness(x) => 
+                        SkillUpdateCommand::SetEffectiveness(x.into()),
+                    SkillUpdateCommandV0::SetParent(x) => SkillUpdateCommand::SetParent(x),
+                    SkillUpdateCommandV0::SetParent2(x) => SkillUpdateCommand::SetParent2(x),
+                }                
+            }
+        }
+    }
+
+    pub mod v1 {
+        use super::*;
+        use crate::theoretical::versioned::TheoreticalVersioned;
+        use crate::grimoire::SkillCategory;
+        use crate::provenance::Provenance;
+        
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct SkillUpdateV1 {
+            commands: Vec<SkillUpdateCommandV1>
+        }
+
+        impl From<SkillUpdate> for SkillUpdateV1 {
             fn from(value: SkillUpdate) -> Self {
                 Self {
                     commands: value.commands.into_iter().map(|x| x.into()).collect(),
@@ -207,8 +292,8 @@ pub mod versioned {
             }
         }
 
-        impl From<SkillUpdateV0> for SkillUpdate {
-            fn from(value: SkillUpdateV0) -> Self {
+        impl From<SkillUpdateV1> for SkillUpdate {
+            fn from(value: SkillUpdateV1) -> Self {
                 Self {
                     commands: value.commands.into_iter().map(|x| x.into()).collect(),
                 }                
@@ -216,30 +301,41 @@ pub mod versioned {
         }
 
         #[derive(Clone, Debug, Serialize, Deserialize)]
-        pub enum SkillUpdateCommandV0 {
+        pub enum SkillUpdateCommandV1 {
             SetEffectiveness(TheoreticalVersioned<f64>),
             SetParent(Option<String>),
-            SetParent2(Option<String>)
+            SetParent2(Option<String>),
+            SetCategory(SkillCategory),
+            SetCost(u8),
+            SetEffectivenessProvenance(Option<Provenance>),
         }
 
-        impl From<SkillUpdateCommand> for SkillUpdateCommandV0 {
+        impl From<SkillUpdateCommand> for SkillUpdateCommandV1 {
             fn from(value: SkillUpdateCommand) -> Self {
                 match value {
                     SkillUpdateCommand::SetEffectiveness(x) => 
-                        SkillUpdateCommandV0::SetEffectiveness(x.into()),
-                    SkillUpdateCommand::SetParent(x) => SkillUpdateCommandV0::SetParent(x),
-                    SkillUpdateCommand::SetParent2(x) => SkillUpdateCommandV0::SetParent2(x),
+                        SkillUpdateCommandV1::SetEffectiveness(x.into()),
+                    SkillUpdateCommand::SetParent(x) => SkillUpdateCommandV1::SetParent(x),
+                    SkillUpdateCommand::SetParent2(x) => SkillUpdateCommandV1::SetParent2(x),
+                    SkillUpdateCommand::SetCategory(x) => SkillUpdateCommandV1::SetCategory(x),
+                    SkillUpdateCommand::SetCost(x) => SkillUpdateCommandV1::SetCost(x),
+                    SkillUpdateCommand::SetEffectivenessProvenance(x) =>
+                        SkillUpdateCommandV1::SetEffectivenessProvenance(x),
                 }
             }
         }
 
-        impl From<SkillUpdateCommandV0> for SkillUpdateCommand {
-            fn from(value: SkillUpdateCommandV0) -> Self {
+        impl From<SkillUpdateCommandV1> for SkillUpdateCommand {
+            fn from(value: SkillUpdateCommandV1) -> Self {
                 match value {
-                    SkillUpdateCommandV0::SetEffectiveness(x) => 
+                    SkillUpdateCommandV1::SetEffectiveness(x) => 
                         SkillUpdateCommand::SetEffectiveness(x.into()),
-                    SkillUpdateCommandV0::SetParent(x) => SkillUpdateCommand::SetParent(x),
-                    SkillUpdateCommandV0::SetParent2(x) => SkillUpdateCommand::SetParent2(x),
+                    SkillUpdateCommandV1::SetParent(x) => SkillUpdateCommand::SetParent(x),
+                    SkillUpdateCommandV1::SetParent2(x) => SkillUpdateCommand::SetParent2(x),
+                    SkillUpdateCommandV1::SetCategory(x) => SkillUpdateCommand::SetCategory(x),
+                    SkillUpdateCommandV1::SetCost(x) => SkillUpdateCommand::SetCost(x),
+                    SkillUpdateCommandV1::SetEffectivenessProvenance(x) =>
+                        SkillUpdateCommand::SetEffectivenessProvenance(x),
                 }                
             }
         }
@@ -249,7 +345,8 @@ pub mod versioned {
 
 #[cfg(test)]
 pub mod tests {
-    use crate::{grimoire::Skill, prelude::{Known, Theory}};
+    use crate::{grimoire::{Skill, SkillCategory}, prelude::{Known, Theory}};
+    use crate::provenance::Provenance;
 
     use super::SkillUpdate;
     use super::Commands;
@@ -266,6 +363,20 @@ pub mod tests {
         }
     }    
 
+    #[test]
+    fn test_diff_provenance() {
+        let old = Skill::new(Known(0.5), Some("parent".to_string()), None);
+        let new = Skill {
+            effectiveness_provenance: Some(Provenance::new("wiki")),
+            ..old.clone()
+        };
+        let mut updated = old.clone();
+
+        SkillUpdate::diff(&old, &new).update(&mut updated);
+
+        assert_eq!(updated, new);
+    }
+
     #[test]
     fn test_skill_update_set_effectiveness() {
         let mut skill = Skill::new(Known(0.9), None, None);
@@ -321,6 +432,20 @@ pub mod tests {
         assert!( skill.parent_2.is_none() );
     }
 
+    #[test]
+    fn test_skill_update_set_category_and_cost() {
+        let mut skill = Skill::new(Known(1.), None, None);
+        let update = SkillUpdate::default()
+            .set_category(SkillCategory::Tertiary)
+            .set_cost(3)
+            .clone();
+
+        update.update(&mut skill);
+
+        assert_eq!( skill.category, SkillCategory::Tertiary );
+        assert_eq!( skill.cost, 3 );
+    }
+
     #[test]
     fn test_update_last_set_effectiveness() {
         let update = SkillUpdate::default()
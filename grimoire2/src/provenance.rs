@@ -0,0 +1,59 @@
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+
+/// Where a value came from, so that disputed values can be traced back to their author
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Provenance {
+    /// Observation, wiki, name of a guildmate...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Day the value was found, written as `2026-10-12`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<String>,
+}
+
+impl Provenance {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: Some(source.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.source.is_none() && self.date.is_none() && self.confidence.is_none()
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use proptest::strategy::Strategy;
+    use proptest::sample::select;
+    use super::*;
+
+    pub fn provenance_strategy() -> impl Strategy<Value = Option<Provenance>> {
+        select(vec![
+            None,
+            Some(Provenance::new("wiki")),
+            Some(Provenance {
+                source: Some("Tashka".to_string()),
+                date: NaiveDate::from_ymd_opt(2026, 10, 1),
+                confidence: Some("measured".to_string()),
+            }),
+        ])
+    }
+
+    #[test]
+    fn test_date() {
+        let provenance: Provenance = serde_json::from_str(r#"{"source": "Tashka", "date": "2026-10-12"}"#).unwrap();
+
+        assert_eq!(provenance.date, NaiveDate::from_ymd_opt(2026, 10, 12));
+        assert_eq!(serde_json::to_string(&provenance).unwrap(), r#"{"source":"Tashka","date":"2026-10-12"}"#);
+        assert!(serde_json::from_str::<Provenance>(r#"{"date": "last week"}"#).is_err());
+        assert!(serde_json::from_str::<Provenance>(r#"{"date": "2026-13-01"}"#).is_err());
+    }
+}
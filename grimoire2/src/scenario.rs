@@ -0,0 +1,222 @@
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
+
+use crate::timeline::{Tick, Timeline};
+
+/// A fight to drink potions in: damage coming in second by second, and potions drunk whenever
+/// health runs low
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Health at the start, which healing can't go beyond
+    pub health: f64,
+    /// Seconds the fight lasts
+    pub length: u64,
+    /// Part of the health below which a potion is drunk
+    pub drink_below: f64,
+    pub damage: Vec<Damage>,
+    /// Potions at hand, the first one off cooldown is drunk
+    pub potions: IndexMap<String, ScenarioPotion>,
+}
+
+/// Damage taken at a second, or every second of a span
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct Damage {
+    pub from: u64,
+    /// Second the damage stops at, a single hit at `from` without it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<u64>,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioPotion {
+    /// Ingredients and their amounts, none for the potion being optimized
+    pub mix: IndexMap<String, u64>,
+    /// Volume the potion is brewed into, that of the mix without it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+    /// Seconds before the same potion can be drunk again
+    pub cooldown: u64,
+    /// Potions of the kind at hand, as many as needed without it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+}
+
+/// How a fight went
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Outcome {
+    pub survived: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub died_at: Option<u64>,
+    /// Health at the end, or when the character died
+    pub health: f64,
+    pub lowest_health: f64,
+    /// Health restored by potions, healing beyond full health left out
+    pub healing: f64,
+    /// Poison taken from potions
+    pub poison: f64,
+    /// Potions drunk by name
+    pub drunk: IndexMap<String, u64>,
+}
+
+impl Outcome {
+    pub fn potions_drunk(&self) -> u64 {
+        self.drunk.values().sum()
+    }
+}
+
+impl Damage {
+    fn at(&self, time: u64) -> f64 {
+        match self.to {
+            None if time == self.from => self.amount,
+            Some(to) if (self.from..to).contains(&time) => self.amount,
+            _ => 0.,
+        }
+    }
+}
+
+impl Scenario {
+    /// Plays the fight with the `timelines` of the potions, in the order of `potions`
+    pub fn simulate(&self, timelines: &[&Timeline]) -> Outcome {
+        let mut outcome = Outcome {
+            health: self.health,
+            lowest_health: self.health,
+            ..Outcome::default()
+        };
+        let mut left: Vec<Option<u64>> = self.potions.values().map(|x| x.count).collect();
+        let mut last: Vec<Option<u64>> = vec![None; self.potions.len()];
+        // Potions working at the moment, by index and second they were drunk at
+        let mut drunk: Vec<(usize, u64)> = Vec::default();
+
+        for time in 0..=self.length {
+            outcome.health -= self.damage.iter().map(|x| x.at(time)).sum::<f64>();
+
+            if outcome.health < self.health * self.drink_below {
+                let ready = self.potions.values().enumerate().position(|(i, potion)| {
+                    left[i] != Some(0)
+                        && i < timelines.len()
+                        && last[i].map(|x| time - x >= potion.cooldown).unwrap_or(true)
+                });
+
+                if let Some(i) = ready {
+                    left[i] = left[i].map(|x| x - 1);
+                    last[i] = Some(time);
+                    drunk.push((i, time));
+                    *outcome.drunk.entry(self.potions.get_index(i).unwrap().0.clone()).or_default() += 1;
+                }
+            }
+
+            for (i, at) in &drunk {
+                let Some(tick) = timelines[*i].ticks.get((time - at) as usize) else {
+                    continue;
+                };
+                self.drink(tick, &mut outcome);
+            }
+
+            outcome.lowest_health = outcome.lowest_health.min(outcome.health);
+
+            if outcome.health <= 0. {
+                outcome.died_at = Some(time);
+                return outcome;
+            }
+        }
+
+        outcome.survived = true;
+        outcome
+    }
+
+    fn drink(&self, tick: &Tick, outcome: &mut Outcome) {
+        outcome.health -= tick.poison;
+        outcome.poison += tick.poison;
+
+        let restored = tick.healing.min(self.health - outcome.health).max(0.);
+        outcome.health += restored;
+        outcome.healing += restored;
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            health: 100.,
+            length: 60,
+            drink_below: 0.5,
+            damage: Vec::default(),
+            potions: IndexMap::default(),
+        }
+    }
+}
+
+impl Default for ScenarioPotion {
+    fn default() -> Self {
+        Self {
+            mix: IndexMap::default(),
+            volume: None,
+            cooldown: 10,
+            count: None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A potion healing `burst` at once and `per_second` for `length` seconds
+    fn timeline(burst: f64, per_second: f64, length: u64) -> Timeline {
+        let mut ticks = vec![Tick { healing: burst, ..Tick::default() }];
+        ticks.extend((1..=length).map(|time| Tick { time, healing: per_second, ..Tick::default() }));
+        Timeline { ticks }
+    }
+
+    fn scenario(count: Option<u64>) -> Scenario {
+        Scenario {
+            length: 20,
+            damage: vec![Damage { from: 0, to: Some(20), amount: 10. }],
+            potions: [
+                ("Burst".to_string(), ScenarioPotion { cooldown: 5, count, ..ScenarioPotion::default() }),
+            ].into_iter().collect(),
+            ..Scenario::default()
+        }
+    }
+
+    #[test]
+    fn test_without_potions() {
+        let outcome = scenario(Some(0)).simulate(&[&timeline(50., 0., 0)]);
+
+        assert!(!outcome.survived);
+        assert_eq!(outcome.died_at, Some(9));
+        assert!(outcome.drunk.is_empty());
+    }
+
+    #[test]
+    fn test_survive() {
+        let outcome = scenario(None).simulate(&[&timeline(50., 0., 0)]);
+
+        // Drunk below 50 health every 5 seconds, from the sixth second on
+        assert!(outcome.survived, "{outcome:?}");
+        assert_eq!(outcome.drunk["Burst"], 3);
+        assert_eq!(outcome.healing, 150.);
+        assert_eq!(outcome.health, 100. - 200. + 150.);
+    }
+
+    #[test]
+    fn test_healing_is_capped() {
+        let mut scenario = scenario(Some(1));
+        scenario.damage = vec![Damage { from: 0, to: None, amount: 60. }];
+
+        let outcome = scenario.simulate(&[&timeline(10., 20., 3)]);
+
+        assert!(outcome.survived);
+        assert_eq!(outcome.healing, 60.);
+        assert_eq!(outcome.health, 100.);
+        assert_eq!(outcome.lowest_health, 50.);
+    }
+}
@@ -0,0 +1,114 @@
+use indexmap::IndexMap;
+
+use crate::error::Result;
+use crate::grimoire::Grimoire;
+use crate::theoretical::Theoretical;
+
+use super::{Mix, OptimizedGrimoire};
+
+/// A recipe prepared for one of the characters of a grimoire
+#[derive(Debug, Clone)]
+pub struct CharacterMix {
+    pub character: String,
+    pub grimoire: OptimizedGrimoire,
+    pub ingredients: Vec<(usize, u64)>,
+    pub lore_multipliers: IndexMap<String, Theoretical<f64>>,
+}
+
+impl CharacterMix {
+    pub fn mix(&self) -> Mix<'_> {
+        Mix::new(&self.grimoire, self.ingredients.clone())
+    }
+}
+
+/// Prepare the recipe for every character in the grimoire, keeping the lore multipliers of
+/// the ingredients it uses so that the results can be explained.
+pub fn mix_for_every_character(
+    grimoire: &Grimoire,
+    ingredients: &[(String, u64)],
+) -> Result<Vec<CharacterMix>> {
+    grimoire.characters.iter().map(|(name, character)| {
+        let optimized = OptimizedGrimoire::from((character, grimoire));
+
+        let indices = ingredients
+            .iter()
+            .map(|(name, amount)| Ok((optimized.ingredients.by_name(name)?, *amount)))
+            .collect::<Result<Vec<(usize, u64)>>>()?;
+
+        let lore_multipliers = ingredients
+            .iter()
+            .filter_map(|(name, _)| grimoire.ingredients.get(name)?.skill.clone())
+            .map(|skill| {
+                let multiplier = character.lore_multiplier(&grimoire.skills, &skill, &optimized.ruleset);
+                (skill, multiplier)
+            })
+            .collect();
+
+        Ok(CharacterMix {
+            character: name.clone(),
+            grimoire: optimized,
+            ingredients: indices,
+            lore_multipliers,
+        })
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use super::*;
+    use crate::effect::Effect;
+    use crate::modify::GrimoireUpdate;
+    use crate::modify::character::CharacterUpdate;
+    use crate::modify::ingredient::IngredientUpdate;
+    use crate::modify::command::Commands;
+
+    fn grimoire() -> Grimoire {
+        GrimoireUpdate::default()
+            .character(
+                "Master", CharacterUpdate::default()
+                    .set_skill("Herbology", 100)
+                    .clone()
+            )
+            .character("Novice", CharacterUpdate::default())
+            .ingredient(
+                "Leaf", IngredientUpdate::default()
+                    .set_skill("Herbology")
+                    .set_weight(Theoretical::Known(1.))
+                    .set_term(Effect::DirectHealing, Theoretical::Known(1.))
+                    .clone()
+            )
+            .create()
+    }
+
+    #[test]
+    fn test_mix_for_every_character() {
+        let grimoire = grimoire();
+        let mixes = mix_for_every_character(&grimoire, &[("Leaf".to_string(), 10)]).unwrap();
+
+        assert_eq!(mixes.len(), 2);
+
+        let master = mixes.iter().find(|x| x.character == "Master").unwrap();
+        let novice = mixes.iter().find(|x| x.character == "Novice").unwrap();
+
+        assert!(approx_eq!(
+            f64, master.mix().effect(Effect::DirectHealing).inner(), 1.66666, epsilon = 0.001
+        ));
+        assert!(approx_eq!(
+            f64, novice.mix().effect(Effect::DirectHealing).inner(), 1., epsilon = 0.001
+        ));
+        assert!(approx_eq!(
+            f64, master.lore_multipliers["Herbology"].inner(), 1.66666, epsilon = 0.001
+        ));
+    }
+
+    #[test]
+    fn test_mix_for_every_character_not_found() {
+        let grimoire = grimoire();
+        let result = mix_for_every_character(&grimoire, &[("Stone".to_string(), 10)]);
+
+        assert!(result.is_err());
+    }
+}
@@ -3,13 +3,13 @@ use serde::{Serialize, Deserialize};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct StandaloneIngredient {
-    pub weight: u8,
+    pub weight: Theoretical<f64>,
     pub lore_multiplier: Theoretical<f64>,
     pub modifiers: ModifierMap,
 }
 
 impl StandaloneIngredient {
-    pub fn new(weight: u8, lore_multiplier: Theoretical<f64>, modifiers: ModifierMap) -> Self {
+    pub fn new(weight: Theoretical<f64>, lore_multiplier: Theoretical<f64>, modifiers: ModifierMap) -> Self {
         Self {
             weight,
             lore_multiplier,
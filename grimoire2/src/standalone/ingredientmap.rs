@@ -9,6 +9,7 @@ pub struct IngredientMap {
     ingredients: Vec<StandaloneIngredient>,
     names: Vec<String>,
     names_map: HashMap<String, usize>,
+    aliases: HashMap<String, usize>,
 }
 
 impl IngredientMap {
@@ -28,12 +29,96 @@ impl IngredientMap {
         self.ingredients.is_empty()
     }
 
+    /// Makes `alias` resolve to the ingredient called `name`, unless it already resolves to
+    /// another one
+    pub fn add_alias(&mut self, alias: &str, name: &str) -> Result<()> {
+        let index = self.by_exact_name(name)?;
+
+        match self.names_map.get(alias).or(self.aliases.get(alias)) {
+            Some(x) if *x != index => Err(Error::AliasConflict(alias.to_string())),
+            _ => {
+                self.aliases.insert(alias.to_string(), index);
+                Ok(())
+            },
+        }
+    }
+
+    /// Looks the ingredient up by its name or one of its aliases
     pub fn by_name(&self, name: &str) -> Result<usize> {
+        self.by_exact_name(name)
+            .or_else(|_| self.aliases.get(name).cloned().ok_or(Error::IngredientNotFound(name.to_string())))
+    }
+
+    fn by_exact_name(&self, name: &str) -> Result<usize> {
         match self.names_map.get(name) {
             Some(x) => Ok(*x),
             None => Err(Error::IngredientNotFound(name.to_string()))            
         }
     }
+
+    /// The ingredient closest to `name`, as long as it's the only one within a couple of typos.
+    /// Meant for suggestions to a user, never for brewing
+    pub fn by_fuzzy_name(&self, name: &str) -> Result<usize> {
+        let needle = normalize(name);
+        let max_distance = needle.chars().count() / 4;
+
+        let candidates = self.names_map
+            .iter()
+            .chain(self.aliases.iter())
+            .map(|(x, i)| (distance(&needle, &normalize(x)), *i))
+            .filter(|(d, _)| *d <= max_distance);
+
+        let mut best: Option<usize> = None;
+        let mut found: Vec<usize> = Vec::default();
+
+        for (d, i) in candidates {
+            match best {
+                Some(b) if d > b => continue,
+                Some(b) if d == b => {},
+                _ => {
+                    best = Some(d);
+                    found.clear();
+                }
+            }
+
+            if !found.contains(&i) {
+                found.push(i);
+            }
+        }
+
+        match found.as_slice() {
+            [x] => Ok(*x),
+            [] => Err(Error::IngredientNotFound(name.to_string())),
+            xs => {
+                let mut names: Vec<String> = xs.iter().map(|x| self.names[*x].clone()).collect();
+                names.sort();
+                Err(Error::AmbiguousIngredient(name.to_string(), names))
+            },
+        }
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars().filter(|x| x.is_alphanumeric()).flat_map(|x| x.to_lowercase()).collect()
+}
+
+/// Levenshtein distance
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev + (ca != *cb) as usize;
+            prev = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(prev + 1);
+        }
+    }
+
+    row[b.len()]
 }
 
 impl Index<usize> for IngredientMap {
@@ -52,8 +137,65 @@ where
         let (names, ingredients): (Vec<String>, Vec<StandaloneIngredient>) = src.unzip();
         Self { 
             names_map: names.iter().enumerate().map(|(i, n)| (n.clone(), i)).collect(),
+            aliases: HashMap::default(),
             names, 
             ingredients             
         }
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ingredient_map() -> IngredientMap {
+        let mut result: IngredientMap = vec!["Salvia Oil", "Sea Dew Leaves", "Troll Sweat"]
+            .into_iter()
+            .map(|x| (x.to_string(), StandaloneIngredient::default()))
+            .into();
+        result.add_alias("Sage Oil", "Salvia Oil").unwrap();
+        result
+    }
+
+    #[test]
+    fn test_by_name() {
+        let mut map = ingredient_map();
+
+        assert_eq!(map.by_name("Sea Dew Leaves").unwrap(), 1);
+        assert_eq!(map.by_name("Sage Oil").unwrap(), 0);
+        assert!(matches!(map.by_name("sea dew leafs"), Err(Error::IngredientNotFound(_))));
+        assert!(matches!(map.by_name("Dragon Blood"), Err(Error::IngredientNotFound(_))));
+        assert!(map.add_alias("x", "Dragon Blood").is_err());
+    }
+
+    #[test]
+    fn test_by_fuzzy_name() {
+        let map = ingredient_map();
+
+        assert_eq!(map.by_fuzzy_name("sea dew leafs").unwrap(), 1);
+        assert_eq!(map.by_fuzzy_name("sage-oil").unwrap(), 0);
+        assert!(matches!(map.by_fuzzy_name("Dragon Blood"), Err(Error::IngredientNotFound(_))));
+    }
+
+    #[test]
+    fn test_alias_conflict() {
+        let mut map = ingredient_map();
+
+        assert!(matches!(map.add_alias("Troll Sweat", "Salvia Oil"), Err(Error::AliasConflict(_))));
+        assert!(matches!(map.add_alias("Sage Oil", "Troll Sweat"), Err(Error::AliasConflict(_))));
+        assert!(map.add_alias("Sage Oil", "Salvia Oil").is_ok());
+        assert_eq!(map.by_name("Sage Oil").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_by_name_ambiguous() {
+        let map: IngredientMap = vec!["Bone Dust 1", "Bone Dust 2"]
+            .into_iter()
+            .map(|x| (x.to_string(), StandaloneIngredient::default()))
+            .into();
+
+        assert!(matches!(map.by_fuzzy_name("Bone Dust"), Err(Error::AmbiguousIngredient(_, x)) if x.len() == 2));
+    }
+}
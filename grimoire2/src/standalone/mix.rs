@@ -1,4 +1,4 @@
-use crate::prelude::{Effect, Theoretical};
+use crate::prelude::{Effect, Effects, Theoretical};
 
 use super::{OptimizedGrimoire, StandaloneIngredient};
 
@@ -32,64 +32,74 @@ impl<'a> Mix<'a> {
             .map(|(i, a)| (&self.grimoire.ingredients[*i], *a))
     }
 
-    pub fn volume(&self) -> f64 {
-        let total_weight: u64 = self
-            .ingredients_iter()
-            .map(|(i, a)| i.weight as u64 * a)
-            .sum();
+    /// Catalogue of the effects the mix may have
+    pub fn effects(&self) -> &Effects {
+        &self.grimoire.effects
+    }
 
-        if total_weight == 0 {
-            return 0.
-        }
+    /// Volume of the potion, as certain as the least certain weight in it
+    pub fn volume(&self) -> Theoretical<f64> {
+        let total_weight = self
+            .ingredients_iter()
+            .fold(Theoretical::from(0.), |acc, (i, a)| acc + i.weight * Theoretical::from(a as f64));
 
-        let without_clade = (total_weight - 1) as f64 / 10.;
+        total_weight.map(|x| self.grimoire.ruleset.volume(x) * self.grimoire.volume_multiplier)
+    }
 
-        if !self.grimoire.alvarin_clade {
-            return without_clade;
-        };
+    pub fn effect(&self, effect: Effect) -> Theoretical<f64> {
+        let (sum, multiplier) = self.contributions(effect).into_iter().fold(
+            (Theoretical::from(0.), Theoretical::from(1.)),
+            |(sum, multiplier), x| (sum + x.term, multiplier * x.factor),
+        );
 
-        without_clade * 1.1
+        Theoretical::from(self.grimoire.advanced_potion_making_mod) * sum * multiplier
     }
 
-    pub fn effect(&self, effect: Effect) -> Theoretical<f64> {
+    /// Part every ingredient takes in the effect, in the order of the mix; the effect is the
+    /// sum of the terms times the product of the factors, times the advanced potion making
+    /// bonus
+    pub fn contributions(&self, effect: Effect) -> Vec<Contribution> {
         let total_count: u64 = self.ingredients.iter().map(|(_, c)| c).sum();
 
         if total_count == 0 {
-            return Theoretical::from(0.);
-        }
-
-        let mut multiplier = Theoretical::from(1.);
-
-        for (ingredient, count) in self.ingredients_iter() {
-            multiplier = multiplier
-                * (Theoretical::from(1.)
-                    + ingredient.modifiers[effect].multiplier
-                        * Theoretical::from((count as f64 / total_count as f64).sqrt()))
-        }
-
-        let mut sum = Theoretical::from(0.);
-
-        for (ingredient, count) in self.ingredients_iter() {
-            sum = sum
-                + ingredient.lore_multiplier
-                    * ingredient.modifiers[effect].term
-                    * Theoretical::from(count as f64 / total_count as f64)
+            return Vec::default();
         }
 
-        Theoretical::from(self.grimoire.advanced_potion_making_mod) * sum * multiplier
+        self.ingredients_iter()
+            .map(|(ingredient, count)| {
+                let share = count as f64 / total_count as f64;
+
+                Contribution {
+                    term: ingredient.lore_multiplier
+                        * ingredient.modifiers[effect].term
+                        * Theoretical::from(share),
+                    factor: Theoretical::from(1.)
+                        + ingredient.modifiers[effect].multiplier * Theoretical::from(share.sqrt()),
+                }
+            })
+            .collect()
     }
 }
 
+/// Part of a single ingredient in an effect of a mix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contribution {
+    /// Lore × term × share of the ingredient, added up with the other ingredients
+    pub term: Theoretical<f64>,
+    /// 1 + multiplier × √share, multiplied with the other ingredients
+    pub factor: Theoretical<f64>,
+}
+
 #[cfg(test)]
 mod tests {
     use float_cmp::approx_eq;
 
     use super::*;
-    use crate::prelude::{Effect, ModifierMap, StandaloneIngredient, Theoretical};
+    use crate::prelude::{Effect, Effects, ModifierMap, Ruleset, StandaloneIngredient, Theoretical};
 
     #[test]
     fn test_mix_dh_noapm() {
-        let grimoire = create_grimoire(true, 1.0);
+        let grimoire = create_grimoire(1.1, 1.0);
         let mix = Mix::new(&grimoire, vec![(0, 11), (1, 11), (2, 11)]);
         let expected = 2.704;
         let actual = mix.effect(Effect::DirectHealing);
@@ -104,7 +114,7 @@ mod tests {
 
     #[test]
     fn test_mix_dh_apm() {
-        let grimoire = create_grimoire(true, 1.2);
+        let grimoire = create_grimoire(1.1, 1.2);
         let mix = Mix::new(&grimoire, vec![(0, 11), (1, 11), (2, 11)]);
         let expected = 3.245;
         let actual = mix.effect(Effect::DirectHealing);
@@ -120,44 +130,63 @@ mod tests {
     fn create_ingredients() -> Vec<StandaloneIngredient> {
         vec![
             StandaloneIngredient::new(
-                1,
+                Theoretical::Known(1.),
                 Theoretical::Known(1.66666),
                 ModifierMap::from(vec![(Effect::DirectHealing, 2.4, 0.)]),
             ),
             StandaloneIngredient::new(
-                1,
+                Theoretical::Known(1.),
                 Theoretical::Known(1.66666),
                 ModifierMap::from(vec![(Effect::DirectHealing, 0., 0.64)]),
             ),
             StandaloneIngredient::new(
-                1,
+                Theoretical::Known(1.),
                 Theoretical::Known(1.99999),
                 ModifierMap::from(vec![(Effect::DirectHealing, 0.5, 0.32)]),
             ),
-            StandaloneIngredient::new(0, Theoretical::default(), ModifierMap::default()),
-            StandaloneIngredient::new(0, Theoretical::default(), ModifierMap::default()),
-            StandaloneIngredient::new(1, Theoretical::default(), ModifierMap::default()),
-            StandaloneIngredient::new(1, Theoretical::default(), ModifierMap::default()),
+            StandaloneIngredient::new(Theoretical::Known(0.), Theoretical::default(), ModifierMap::default()),
+            StandaloneIngredient::new(Theoretical::Known(0.), Theoretical::default(), ModifierMap::default()),
+            StandaloneIngredient::new(Theoretical::Known(1.), Theoretical::default(), ModifierMap::default()),
+            StandaloneIngredient::new(Theoretical::Known(1.), Theoretical::default(), ModifierMap::default()),
+            StandaloneIngredient::new(Theoretical::Theory(2.), Theoretical::default(), ModifierMap::default()),
         ]
     }
 
-    fn create_grimoire(alvarin_clade: bool, advanced_potion_making_mod: f64) -> OptimizedGrimoire {
+    fn create_grimoire(volume_multiplier: f64, advanced_potion_making_mod: f64) -> OptimizedGrimoire {
         let ingredients = create_ingredients();
         let ingredients_map = ingredients
             .into_iter()
             .map(|x| ("...".to_string(), x))
             .into();
-        OptimizedGrimoire::new(alvarin_clade, advanced_potion_making_mod, ingredients_map)
+        OptimizedGrimoire::new(volume_multiplier, advanced_potion_making_mod, ingredients_map, Effects::builtin(), Ruleset::default())
+    }
+
+    #[test]
+    fn test_contributions() {
+        let grimoire = create_grimoire(1.1, 1.2);
+        let mix = Mix::new(&grimoire, vec![(0, 11), (1, 11), (2, 11)]);
+        let contributions = mix.contributions(Effect::DirectHealing);
+
+        assert_eq!(contributions.len(), 3);
+        assert!(approx_eq!(f64, contributions[0].term.inner(), 1.66666 * 2.4 / 3., epsilon = 0.0001));
+        assert_eq!(contributions[0].factor.inner(), 1.);
+        assert_eq!(contributions[1].term.inner(), 0.);
+        assert!(approx_eq!(f64, contributions[1].factor.inner(), 1. + 0.64 * (1. / 3f64).sqrt(), epsilon = 0.0001));
+
+        let sum: f64 = contributions.iter().map(|x| x.term.inner()).sum();
+        let product: f64 = contributions.iter().map(|x| x.factor.inner()).product();
+        assert!(approx_eq!(f64, 1.2 * sum * product, mix.effect(Effect::DirectHealing).inner(), epsilon = 0.0001));
+        assert!(Mix::new(&grimoire, vec![]).contributions(Effect::DirectHealing).is_empty());
     }
 
     #[test]
     fn test_mix_volume_wo_clade() {
         let expected = 24. / 10.;
 
-        let grimoire = create_grimoire(false, 1.0);
+        let grimoire = create_grimoire(1.0, 1.0);
         let mix = Mix::new(&grimoire, vec![(3, 10), (4, 15), (5, 10), (6, 15)]);
 
-        let actual = mix.volume();
+        let actual = mix.volume().inner();
 
         assert!(
             approx_eq!(f64, actual, expected, epsilon = 0.01),
@@ -171,10 +200,10 @@ mod tests {
     fn test_mix_volume_w_clade() {
         let expected = (24. / 10.) * 1.1;
 
-        let grimoire = create_grimoire(true, 1.0);
+        let grimoire = create_grimoire(1.1, 1.0);
         let mix = Mix::new(&grimoire, vec![(3, 10), (4, 15), (5, 10), (6, 15)]);
 
-        let actual = mix.volume();
+        let actual = mix.volume().inner();
 
         assert!(
             approx_eq!(f64, actual, expected, epsilon = 0.01),
@@ -188,10 +217,10 @@ mod tests {
     fn test_mix_volume_w_clade_zero() {
         let expected = 0.;
 
-        let grimoire = create_grimoire(true, 1.0);
+        let grimoire = create_grimoire(1.1, 1.0);
         let mix = Mix::new(&grimoire, vec![(3, 11), (4, 11)]);
 
-        let actual = mix.volume();
+        let actual = mix.volume().inner();
 
         assert!(
             approx_eq!(f64, actual, expected, epsilon = 0.01),
@@ -199,5 +228,23 @@ mod tests {
             expected,
             actual
         )
-    }    
+    }
+
+    #[test]
+    fn test_mix_volume_heavy() {
+        let expected = (30. - 1.) / 10.;
+
+        let grimoire = create_grimoire(1.0, 1.0);
+        let mix = Mix::new(&grimoire, vec![(5, 10), (7, 10)]);
+
+        let actual = mix.volume();
+
+        assert!(actual.is_theory());
+        assert!(
+            approx_eq!(f64, actual.inner(), expected, epsilon = 0.01),
+            "Volume expected {}, but got {}",
+            expected,
+            actual.inner()
+        )
+    }
 }
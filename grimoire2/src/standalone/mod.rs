@@ -1,51 +1,63 @@
+pub mod characters;
 pub mod ingredient;
 pub mod ingredientmap;
 pub mod mix;
 
+pub use characters::*;
 pub use ingredient::*;
 pub use ingredientmap::*;
 pub use mix::*;
 use serde::{Serialize, Deserialize};
 
-use crate::grimoire::{Character, Grimoire};
+use crate::effect::Effects;
+use crate::grimoire::{Character, Grimoire, Ruleset};
+use crate::validate::alias_conflicts;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimizedGrimoire {
-    pub alvarin_clade: bool,
+    /// Product of the volume multipliers of the character's clades
+    pub volume_multiplier: f64,
     pub advanced_potion_making_mod: f64,
     pub ingredients: IngredientMap,
+    pub effects: Effects,
+    pub ruleset: Ruleset,
 }
 
 impl OptimizedGrimoire {
     pub fn new(
-        alvarin_clade: bool,
+        volume_multiplier: f64,
         advanced_potion_making_mod: f64,
         ingredients: IngredientMap,
+        effects: Effects,
+        ruleset: Ruleset,
     ) -> Self {
         Self {
-            alvarin_clade,
+            volume_multiplier,
             advanced_potion_making_mod,
             ingredients,
+            effects,
+            ruleset,
         }
     }
 }
 
 impl From<(&Character, &Grimoire)> for OptimizedGrimoire {
     fn from((character, grimoire): (&Character, &Grimoire)) -> Self {
-        let alvarin_clade = character.clades.contains("Alchemist");
-        let advanced_potion_making_mod =
-            1. + 0.2 * (character.skill(&grimoire.skills, "Advanced Potion Making") / 100) as f64;
-        let ingredients = grimoire
+        let ruleset = grimoire.ruleset();
+        let volume_multiplier = ruleset.volume_multiplier(character, &grimoire.clades);
+        let advanced_potion_making_mod = ruleset.advanced_potion_making_mod(character, &grimoire.skills);
+        let mut ingredients: IngredientMap = grimoire
             .ingredients
             .iter()
             .map(|(name, ingredient)| {
                 (
                     name.clone(),
                     StandaloneIngredient::new(
-                        ingredient.weight as u8,
+                        ingredient.weight,
                         character.lore_multiplier(
                             &grimoire.skills,
                             ingredient.skill.as_ref().unwrap_or(&"".to_string()),
+                            &ruleset,
                         ),
                         ingredient.modifiers.clone(),
                     ),
@@ -54,6 +66,17 @@ impl From<(&Character, &Grimoire)> for OptimizedGrimoire {
             .collect::<Vec<(String, StandaloneIngredient)>>()
             .into_iter()
             .into();
-        Self::new(alvarin_clade, advanced_potion_making_mod, ingredients)
+
+        // Conflicting aliases are reported by `validate`; none of the ingredients gets them,
+        // so that a lookup by such an alias fails instead of picking one
+        let conflicts = alias_conflicts(grimoire);
+
+        for (name, ingredient) in &grimoire.ingredients {
+            for alias in ingredient.aliases.iter().filter(|x| !conflicts.contains_key(*x)) {
+                ingredients.add_alias(alias, name).ok();
+            }
+        }
+
+        Self::new(volume_multiplier, advanced_potion_making_mod, ingredients, grimoire.effects.clone(), ruleset)
     }
 }
@@ -71,6 +71,20 @@ where
         }
     }
 
+    /// Applies `f` to the value, keeping how certain it is
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Theoretical<U> {
+        match self {
+            Self::Known(x) => Theoretical::Known(f(x)),
+            Self::Theory(x) => Theoretical::Theory(f(x)),
+            Self::Unknown => Theoretical::Unknown,
+        }
+    }
+
+    /// Whether this value is less certain than a known value it replaces
+    pub fn is_downgrade_of(&self, previous: &Self) -> bool {
+        previous.is_known() && !self.is_known()
+    }
+
     pub fn known_or(&self, or_: impl Fn(T) -> T) -> T 
         where T: Default
     {
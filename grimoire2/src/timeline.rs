@@ -0,0 +1,188 @@
+use serde::Serialize;
+
+use crate::effect::Effect;
+use crate::standalone::Mix;
+use crate::theoretical::Theoretical;
+
+/// Healing and poison of a single second of a potion, the second 0 being the direct burst
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Tick {
+    pub time: u64,
+    pub healing: f64,
+    pub poison: f64,
+    /// Healing minus poison of this second
+    pub net: f64,
+    /// Healing up to and including this second
+    pub total_healing: f64,
+    pub total_poison: f64,
+    pub total_net: f64,
+}
+
+/// Second by second course of a drunk potion: direct healing and poison at once, then healing
+/// and poison over time for as long as their lengths, overlapping where both run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timeline {
+    pub ticks: Vec<Tick>,
+}
+
+/// Rate and length of an effect over time, lengths count from the moment the potion is drunk
+struct OverTime {
+    rate: f64,
+    length: f64,
+}
+
+impl OverTime {
+    fn new(rate: f64, length: f64, volume: f64) -> Self {
+        Self {
+            rate: rate * volume.sqrt(),
+            length: (length * volume.sqrt()).max(0.),
+        }
+    }
+
+    /// Amount during the second ending at `time`, a part of it if the effect ends midway
+    fn during(&self, time: u64) -> f64 {
+        let start = (time - 1) as f64;
+        self.rate * (self.length.min(time as f64) - start).max(0.)
+    }
+}
+
+impl Timeline {
+    /// Timeline of the mix brewed into `volume`, theories count as known
+    pub fn simulate(mix: &Mix, volume: f64) -> Self {
+        Self::simulate_with(mix, volume, |x| x.inner())
+    }
+
+    /// Timeline of the mix brewed into `volume`, with `value` deciding what theories and
+    /// unknown values are worth
+    pub fn simulate_with(mix: &Mix, volume: f64, value: impl Fn(Theoretical<f64>) -> f64) -> Self {
+        let effect = |x| value(mix.effect(x));
+        let volume = volume.max(0.);
+
+        let healing = OverTime::new(effect(Effect::HealingOverTime), effect(Effect::HealingLength), volume);
+        let poison = OverTime::new(effect(Effect::PoisonOverTime), effect(Effect::PoisonLength), volume);
+        let length = healing.length.max(poison.length).ceil() as u64;
+
+        let mut ticks = vec![Tick::default()];
+        ticks[0].healing = effect(Effect::DirectHealing) * volume;
+        ticks[0].poison = effect(Effect::DirectPoison) * volume;
+
+        ticks.extend((1..=length).map(|time| Tick {
+            time,
+            healing: healing.during(time),
+            poison: poison.during(time),
+            ..Tick::default()
+        }));
+
+        let (mut total_healing, mut total_poison) = (0., 0.);
+
+        for tick in &mut ticks {
+            total_healing += tick.healing;
+            total_poison += tick.poison;
+
+            tick.net = tick.healing - tick.poison;
+            tick.total_healing = total_healing;
+            tick.total_poison = total_poison;
+            tick.total_net = total_healing - total_poison;
+        }
+
+        Self { ticks }
+    }
+
+    /// Last second of the potion
+    pub fn length(&self) -> u64 {
+        self.ticks.last().map(|x| x.time).unwrap_or_default()
+    }
+
+    /// State of the potion at `time`, the last second once it has run out
+    pub fn at(&self, time: u64) -> Tick {
+        let mut tick = self.ticks
+            .get(time as usize)
+            .or(self.ticks.last())
+            .cloned()
+            .unwrap_or_default();
+
+        if tick.time != time {
+            tick = Tick {
+                time,
+                total_healing: tick.total_healing,
+                total_poison: tick.total_poison,
+                total_net: tick.total_net,
+                ..Tick::default()
+            };
+        }
+
+        tick
+    }
+
+    /// First second at which healing minus poison reaches `amount`, if it ever does
+    pub fn time_to_net(&self, amount: f64) -> Option<u64> {
+        self.ticks.iter().find(|x| x.total_net >= amount).map(|x| x.time)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use float_cmp::approx_eq;
+
+    use crate::grimoire::{Character, Grimoire, Ingredient};
+    use crate::standalone::OptimizedGrimoire;
+
+    use super::*;
+
+    fn grimoire() -> Grimoire {
+        let mut ingredient = Ingredient {
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        };
+        ingredient.modifiers[Effect::DirectHealing].term = Theoretical::Known(2.);
+        ingredient.modifiers[Effect::DirectPoison].term = Theoretical::Known(1.);
+        ingredient.modifiers[Effect::HealingOverTime].term = Theoretical::Known(3.);
+        ingredient.modifiers[Effect::HealingLength].term = Theoretical::Known(2.5);
+        ingredient.modifiers[Effect::PoisonOverTime].term = Theoretical::Theory(1.);
+        ingredient.modifiers[Effect::PoisonLength].term = Theoretical::Known(1.);
+
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Leaf".to_string(), ingredient);
+        grimoire
+    }
+
+    #[test]
+    fn test_simulate() {
+        let grimoire = grimoire();
+        let optimized = OptimizedGrimoire::from((&Character::default(), &grimoire));
+        let mix = Mix::new(&optimized, vec![(0, 1)]);
+
+        let dh = mix.effect(Effect::DirectHealing).inner();
+        let hot = mix.effect(Effect::HealingOverTime).inner();
+        let hl = mix.effect(Effect::HealingLength).inner();
+        let pot = mix.effect(Effect::PoisonOverTime).inner();
+        let pl = mix.effect(Effect::PoisonLength).inner();
+        let timeline = Timeline::simulate(&mix, 4.);
+
+        assert_eq!(timeline.length(), (hl * 2.).max(pl * 2.).ceil() as u64);
+        assert!(approx_eq!(f64, timeline.ticks[0].healing, dh * 4., epsilon = 0.0001));
+
+        let last = timeline.ticks.last().unwrap();
+        let total = dh * 4. + hot * hl * 4. - mix.effect(Effect::DirectPoison).inner() * 4. - pot * pl * 4.;
+        assert!(approx_eq!(f64, last.total_net, total, epsilon = 0.0001), "{} {}", last.total_net, total);
+        assert!(approx_eq!(f64, timeline.at(100).total_net, total, epsilon = 0.0001));
+        assert_eq!(timeline.at(100).healing, 0.);
+    }
+
+    #[test]
+    fn test_unknown_poison() {
+        let grimoire = grimoire();
+        let optimized = OptimizedGrimoire::from((&Character::default(), &grimoire));
+        let mix = Mix::new(&optimized, vec![(0, 1)]);
+
+        let timeline = Timeline::simulate_with(&mix, 1., |x| match x {
+            Theoretical::Known(x) => x,
+            _ => 0.,
+        });
+
+        assert!(timeline.ticks.iter().skip(1).all(|x| x.poison == 0.));
+        assert_eq!(timeline.time_to_net(f64::MAX), None);
+        assert_eq!(timeline.time_to_net(0.), Some(0));
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use indexmap::IndexMap;
+
+use crate::effect::Effect;
+use crate::error::Error;
+use crate::grimoire::Grimoire;
+use crate::modifier::Modifier;
+use crate::theoretical::Theoretical;
+
+/// Multipliers and terms beyond this value are most likely typos
+const SUSPICIOUS_VALUE: f64 = 100.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Issue {
+    #[error("Skills form a cycle: {}", .0.join(" -> "))]
+    SkillCycle(Vec<String>),
+    #[error("Skill {skill} has an unknown parent: {parent}")]
+    DanglingParent { skill: String, parent: String },
+    #[error("Ingredient {ingredient} has an unknown lore: {skill}")]
+    UnknownLore { ingredient: String, skill: String },
+    #[error("Ingredient {0} has no lore")]
+    MissingLore(String),
+    #[error("Character {character}: {error}")]
+    Character { character: String, error: Error },
+    #[error("Ingredient {ingredient} has a suspicious {code}: {value}")]
+    SuspiciousModifier { ingredient: String, code: String, value: f64 },
+    #[error("Effects {} share the modifier code {code}", .effects.join(", "))]
+    DuplicateEffectCode { code: String, effects: Vec<String> },
+    #[error("Ingredient {ingredient} has values for effect {} which is not in the catalogue", .effect.0)]
+    UnknownEffect { ingredient: String, effect: Effect },
+    #[error("Ruleset {0} brews no volume, its weight per volume must be positive")]
+    NoVolume(String),
+    #[error("Productions form a cycle: {}", .0.join(" -> "))]
+    ProductionCycle(Vec<String>),
+    #[error("Production of {production} uses an unknown ingredient: {ingredient}")]
+    UnknownProductionIngredient { production: String, ingredient: String },
+    #[error("Production of {0} yields nothing")]
+    NoYield(String),
+    #[error("Recipe {recipe} uses an unknown ingredient: {ingredient}")]
+    UnknownRecipeIngredient { recipe: String, ingredient: String },
+    #[error("Recipe {recipe} is meant for an unknown character: {character}")]
+    UnknownRecipeCharacter { recipe: String, character: String },
+    #[error("Alias {alias} may stand for any of: {}", .ingredients.join(", "))]
+    AliasConflict { alias: String, ingredients: Vec<String> },
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::MissingLore(_)
+            | Issue::UnknownLore { .. }
+            | Issue::SuspiciousModifier { .. }
+            | Issue::UnknownEffect { .. }
+            | Issue::UnknownRecipeCharacter { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// Everything that looks wrong in the grimoire, in the order effects, rulesets, skills,
+/// ingredients, productions, recipes, characters
+pub fn validate(grimoire: &Grimoire) -> Vec<Issue> {
+    let mut result = Vec::default();
+
+    let mut codes: IndexMap<String, Vec<String>> = IndexMap::default();
+    for (code, effect, _) in grimoire.effects.modifier_codes() {
+        let id = grimoire.effects.id(effect).unwrap_or_default().to_string();
+        codes.entry(code).or_default().push(id);
+    }
+    result.extend(codes.into_iter().filter(|x| x.1.len() > 1).map(|(code, effects)| {
+        Issue::DuplicateEffectCode { code, effects }
+    }));
+
+    result.extend(grimoire.rulesets.iter()
+        .filter(|x| x.1.weight_per_volume <= 0.)
+        .map(|x| Issue::NoVolume(x.0.clone())));
+
+    result.extend(skill_cycles(grimoire).into_iter().map(Issue::SkillCycle));
+
+    for (name, skill) in &grimoire.skills {
+        for parent in skill.parent.iter().chain(skill.parent_2.iter()) {
+            if !grimoire.skills.contains_key(parent) {
+                result.push(Issue::DanglingParent { skill: name.clone(), parent: parent.clone() });
+            }
+        }
+    }
+
+    for (name, ingredient) in &grimoire.ingredients {
+        match &ingredient.skill {
+            Some(skill) if !grimoire.skills.contains_key(skill) => {
+                result.push(Issue::UnknownLore { ingredient: name.clone(), skill: skill.clone() });
+            },
+            Some(_) => {},
+            None => result.push(Issue::MissingLore(name.clone())),
+        }
+
+        for (effect, modifier) in ingredient.modifiers.iter() {
+            if grimoire.effects.get(effect).is_none() {
+                if modifier != &Modifier::default() {
+                    result.push(Issue::UnknownEffect { ingredient: name.clone(), effect });
+                }
+                continue;
+            }
+
+            for (value, multiplier) in [(modifier.term, false), (modifier.multiplier, true)] {
+                if let Some(value) = suspicious(value, multiplier) {
+                    result.push(Issue::SuspiciousModifier {
+                        ingredient: name.clone(),
+                        code: grimoire.effects.modifier_code(effect, multiplier).unwrap_or_default(),
+                        value,
+                    });
+                }
+            }
+        }
+    }
+
+    result.extend(alias_conflicts(grimoire).into_iter().map(|(alias, ingredients)| {
+        Issue::AliasConflict { alias, ingredients }
+    }));
+
+    result.extend(production_cycles(grimoire).into_iter().map(Issue::ProductionCycle));
+
+    for (name, production) in &grimoire.productions {
+        for ingredient in [name].into_iter().chain(production.inputs.keys()) {
+            if !grimoire.ingredients.contains_key(ingredient) {
+                result.push(Issue::UnknownProductionIngredient {
+                    production: name.clone(),
+                    ingredient: ingredient.clone(),
+                });
+            }
+        }
+
+        if production.yields == 0 {
+            result.push(Issue::NoYield(name.clone()));
+        }
+    }
+
+    let known = |x: &String| grimoire.ingredients.contains_key(x)
+        || grimoire.ingredients.values().any(|i| i.aliases.contains(x));
+
+    for (name, recipe) in &grimoire.recipes {
+        result.extend(recipe.ingredients.keys().filter(|x| !known(x)).map(|x| Issue::UnknownRecipeIngredient {
+            recipe: name.clone(),
+            ingredient: x.clone(),
+        }));
+
+        if let Some(character) = recipe.character.as_ref().filter(|x| !grimoire.characters.contains_key(*x)) {
+            result.push(Issue::UnknownRecipeCharacter { recipe: name.clone(), character: character.clone() });
+        }
+    }
+
+    for (name, character) in &grimoire.characters {
+        result.extend(character.violations(grimoire).into_iter().map(|error| Issue::Character {
+            character: name.clone(),
+            error,
+        }));
+    }
+
+    result
+}
+
+pub fn is_valid(issues: &[Issue]) -> bool {
+    issues.iter().all(|x| x.severity() < Severity::Error)
+}
+
+fn suspicious(value: Theoretical<f64>, multiplier: bool) -> Option<f64> {
+    let value = match value {
+        Theoretical::Known(x) | Theoretical::Theory(x) => x,
+        Theoretical::Unknown => return None,
+    };
+
+    if !value.is_finite() || value.abs() > SUSPICIOUS_VALUE || (multiplier && value < 0.) {
+        return Some(value);
+    }
+
+    None
+}
+
+/// Aliases that are the name of another ingredient or an alias of several, with the
+/// ingredients they may stand for
+pub fn alias_conflicts(grimoire: &Grimoire) -> IndexMap<String, Vec<String>> {
+    let mut claims: IndexMap<&String, Vec<String>> = IndexMap::default();
+
+    for (name, ingredient) in &grimoire.ingredients {
+        for alias in ingredient.aliases.iter().filter(|x| *x != name) {
+            let owners = claims.entry(alias).or_default();
+
+            if owners.is_empty() && grimoire.ingredients.contains_key(alias) {
+                owners.push(alias.clone());
+            }
+            owners.push(name.clone());
+        }
+    }
+
+    claims.into_iter()
+        .filter(|x| x.1.len() > 1)
+        .map(|(alias, mut owners)| {
+            owners.sort();
+            owners.dedup();
+            (alias.clone(), owners)
+        })
+        .filter(|x| x.1.len() > 1)
+        .collect()
+}
+
+/// Every cycle in the parent links of the skills, each one reported once
+pub fn skill_cycles(grimoire: &Grimoire) -> Vec<Vec<String>> {
+    cycles(grimoire.skills.keys(), |skill| match grimoire.skills.get(skill) {
+        Some(x) => x.parent.iter().chain(x.parent_2.iter()).collect(),
+        None => Vec::default(),
+    })
+}
+
+/// Every cycle of ingredients made from each other, each one reported once
+fn production_cycles(grimoire: &Grimoire) -> Vec<Vec<String>> {
+    cycles(grimoire.productions.keys(), |name| match grimoire.productions.get(name) {
+        Some(x) => x.inputs.keys().collect(),
+        None => Vec::default(),
+    })
+}
+
+/// Every cycle in the `links` between `names`, each one reported once
+fn cycles<'a>(
+    names: impl Iterator<Item = &'a String>,
+    links: impl Fn(&str) -> Vec<&'a String>,
+) -> Vec<Vec<String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        InProgress,
+        Done,
+    }
+
+    fn visit<'a>(
+        links: &impl Fn(&str) -> Vec<&'a String>,
+        name: &str,
+        visited: &mut HashMap<String, Visit>,
+        path: &mut Vec<String>,
+        result: &mut Vec<Vec<String>>,
+    ) {
+        match visited.get(name) {
+            Some(Visit::Done) => return,
+            Some(Visit::InProgress) => {
+                let start = path.iter().position(|x| x == name).unwrap_or_default();
+                let mut cycle = path[start..].to_vec();
+                cycle.push(name.to_string());
+                result.push(cycle);
+                return;
+            },
+            None => {},
+        }
+
+        visited.insert(name.to_string(), Visit::InProgress);
+        path.push(name.to_string());
+
+        for next in links(name) {
+            visit(links, next, visited, path, result);
+        }
+
+        path.pop();
+        visited.insert(name.to_string(), Visit::Done);
+    }
+
+    let mut visited = HashMap::default();
+    let mut result = Vec::default();
+
+    for name in names {
+        visit(&links, name, &mut visited, &mut Vec::default(), &mut result);
+    }
+
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect::EffectDefinition;
+    use crate::grimoire::{Production, Recipe, Ruleset};
+    use crate::modify::GrimoireUpdate;
+    use crate::modify::character::CharacterUpdate;
+    use crate::modify::command::Commands;
+    use crate::modify::ingredient::IngredientUpdate;
+    use crate::modify::skill::SkillUpdate;
+
+    fn grimoire() -> Grimoire {
+        GrimoireUpdate::default()
+            .skill("Botany", SkillUpdate::default())
+            .skill("Herbology", SkillUpdate::default().set_parent("Botany").clone())
+            .ingredient(
+                "Leaf", IngredientUpdate::default()
+                    .set_skill("Herbology")
+                    .set_weight(Theoretical::Known(1.))
+                    .set_term(Effect::DirectHealing, Theoretical::Known(1.))
+                    .clone()
+            )
+            .character(
+                "Tashka", CharacterUpdate::default()
+                    .set_skill("Herbology", 100)
+                    .clone()
+            )
+            .create()
+    }
+
+    #[test]
+    fn test_validate_clean() {
+        let issues = validate(&grimoire());
+
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_validate_rulesets() {
+        let mut grimoire = grimoire();
+        grimoire.rulesets.insert("current".to_string(), Ruleset::default());
+        grimoire.rulesets.insert("broken".to_string(), Ruleset { weight_per_volume: 0., ..Ruleset::default() });
+
+        let issues = validate(&grimoire);
+
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert!(matches!(&issues[0], Issue::NoVolume(x) if x == "broken"));
+        assert!(!is_valid(&issues));
+    }
+
+    #[test]
+    fn test_validate_productions() {
+        let mut grimoire = grimoire();
+        let production = |input: &str, yields| Production::new([(input.to_string(), 1)].into_iter().collect(), yields);
+        grimoire.ingredients.insert("Oil".to_string(), IngredientUpdate::default().set_skill("Herbology").create());
+        grimoire.productions.insert("Leaf".to_string(), production("Oil", 1));
+        grimoire.productions.insert("Oil".to_string(), production("Leaf", 0));
+        grimoire.productions.insert("Dust".to_string(), production("Leaf", 1));
+
+        let issues = validate(&grimoire);
+
+        assert_eq!(issues.len(), 3, "{:?}", issues);
+        assert!(matches!(&issues[0], Issue::ProductionCycle(x) if x == &["Leaf", "Oil", "Leaf"]));
+        assert!(matches!(&issues[1], Issue::NoYield(x) if x == "Oil"));
+        assert!(matches!(
+            &issues[2], Issue::UnknownProductionIngredient { production, ingredient } if production == "Dust" && ingredient == "Dust"
+        ));
+    }
+
+    #[test]
+    fn test_validate_recipes() {
+        let mut grimoire = grimoire();
+        grimoire.ingredients["Leaf"].aliases.insert("Leef".to_string());
+        grimoire.recipes.insert("Salve".to_string(), Recipe {
+            character: Some("Nobody".to_string()),
+            ..Recipe::new([("Leef".to_string(), 1), ("Stone".to_string(), 2)].into_iter().collect())
+        });
+
+        let issues = validate(&grimoire);
+
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(matches!(
+            &issues[0], Issue::UnknownRecipeIngredient { recipe, ingredient } if recipe == "Salve" && ingredient == "Stone"
+        ));
+        assert!(matches!(&issues[1], Issue::UnknownRecipeCharacter { character, .. } if character == "Nobody"));
+        assert_eq!(issues[1].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_alias_conflicts() {
+        let mut grimoire = grimoire();
+        let mut stone = grimoire.ingredients["Leaf"].clone();
+        stone.aliases.insert("Leaf".to_string());
+        stone.aliases.insert("Rock".to_string());
+        grimoire.ingredients.insert("Stone".to_string(), stone);
+        grimoire.ingredients["Leaf"].aliases.insert("Rock".to_string());
+        grimoire.ingredients["Leaf"].aliases.insert("Leaf".to_string());
+
+        let issues = validate(&grimoire);
+
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(matches!(&issues[0], Issue::AliasConflict { alias, .. } if alias == "Rock"));
+        assert!(matches!(
+            &issues[1], Issue::AliasConflict { alias, ingredients } if alias == "Leaf" && ingredients == &["Leaf", "Stone"]
+        ));
+        assert_eq!(issues[0].severity(), Severity::Error);
+
+        let optimized = crate::standalone::OptimizedGrimoire::from((&grimoire.characters["Tashka"], &grimoire));
+        assert!(optimized.ingredients.by_name("Rock").is_err());
+        assert_eq!(optimized.ingredients.name(optimized.ingredients.by_name("Leaf").unwrap()), "Leaf");
+    }
+
+    #[test]
+    fn test_validate_cycle_and_dangling_parent() {
+        let mut grimoire = grimoire();
+        SkillUpdate::default().set_parent("Herbology").update(grimoire.skills.get_mut("Botany").unwrap());
+        SkillUpdate::default().set_parent2("Zoology").update(grimoire.skills.get_mut("Herbology").unwrap());
+
+        let issues = validate(&grimoire);
+
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(matches!(
+            &issues[0], Issue::SkillCycle(x) if x == &["Botany", "Herbology", "Botany"]
+        ));
+        assert!(matches!(
+            &issues[1], Issue::DanglingParent { skill, parent } if skill == "Herbology" && parent == "Zoology"
+        ));
+        assert!(!is_valid(&issues));
+    }
+
+    #[test]
+    fn test_validate_ingredients_and_characters() {
+        let mut grimoire = grimoire();
+        grimoire.ingredients.insert("Stone".to_string(), IngredientUpdate::default()
+            .set_skill("Geology")
+            .set_multiplier(Effect::Alcohol, Theoretical::Theory(-1.))
+            .create());
+        grimoire.ingredients.insert("Water".to_string(), Default::default());
+        grimoire.characters.get_mut("Tashka").unwrap().skills.insert("Botany".to_string(), 120);
+
+        let issues = validate(&grimoire);
+
+        assert_eq!(issues.len(), 4, "{:?}", issues);
+        assert!(matches!(&issues[0], Issue::UnknownLore { skill, .. } if skill == "Geology"));
+        assert_eq!(issues[0].severity(), Severity::Warning);
+        assert!(matches!(
+            &issues[1], Issue::SuspiciousModifier { code, .. } if code == "ma"
+        ));
+        assert!(matches!(&issues[2], Issue::MissingLore(x) if x == "Water"));
+        assert!(matches!(
+            &issues[3], Issue::Character { error: Error::SkillValueBound(_, 120), .. }
+        ));
+        assert!(!is_valid(&issues));
+        assert!(is_valid(&issues[..3]));
+    }
+
+    #[test]
+    fn test_validate_effects() {
+        let mut grimoire = grimoire();
+        grimoire.effects.insert("Mana", EffectDefinition::new("dp", "Mana", "buff"));
+        grimoire.ingredients.get_mut("Leaf").unwrap().modifiers[Effect(9)].term = Theoretical::Known(1.);
+
+        let issues = validate(&grimoire);
+
+        assert_eq!(issues.len(), 3, "{:?}", issues);
+        assert!(matches!(
+            &issues[0], Issue::DuplicateEffectCode { code, effects } if code == "dp" && effects == &["DirectPoison", "Mana"]
+        ));
+        assert!(matches!(&issues[1], Issue::DuplicateEffectCode { code, .. } if code == "mdp"));
+        assert!(matches!(&issues[2], Issue::UnknownEffect { effect: Effect(9), .. }));
+    }
+}
@@ -0,0 +1,20 @@
+DROP TABLE recipe_tags;
+DROP TABLE recipe_ingredients;
+DROP TABLE recipes;
+DROP TABLE production_inputs;
+DROP TABLE productions;
+DROP TABLE ruleset_clades;
+DROP TABLE rulesets;
+DROP TABLE effects;
+DROP TABLE update_history;
+DROP TABLE observations;
+DROP TABLE rules;
+DROP TABLE clades;
+DROP TABLE character_skills;
+DROP TABLE character_clades;
+DROP TABLE characters;
+DROP TABLE ingredient_aliases;
+DROP TABLE ingredient_tags;
+DROP TABLE ingredient_modifiers;
+DROP TABLE ingredients;
+DROP TABLE skills;
@@ -0,0 +1,179 @@
+-- Entities keep the order they have in the grimoire through `position`
+
+CREATE TABLE skills (
+    name TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL,
+    -- NULL when unknown
+    effectiveness DOUBLE,
+    effectiveness_theory BOOLEAN NOT NULL DEFAULT 0,
+    parent TEXT,
+    parent_2 TEXT,
+    category TEXT NOT NULL DEFAULT 'Primary',
+    cost INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE TABLE ingredients (
+    name TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL,
+    skill TEXT,
+    -- NULL when unknown
+    weight DOUBLE,
+    weight_theory BOOLEAN NOT NULL DEFAULT 0,
+    source TEXT
+);
+
+CREATE TABLE ingredient_modifiers (
+    ingredient TEXT NOT NULL REFERENCES ingredients (name) ON DELETE CASCADE,
+    effect TEXT NOT NULL,
+    term DOUBLE,
+    term_theory BOOLEAN NOT NULL DEFAULT 0,
+    multiplier DOUBLE,
+    multiplier_theory BOOLEAN NOT NULL DEFAULT 0,
+    PRIMARY KEY (ingredient, effect)
+);
+
+CREATE TABLE ingredient_tags (
+    ingredient TEXT NOT NULL REFERENCES ingredients (name) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (ingredient, tag)
+);
+
+CREATE TABLE ingredient_aliases (
+    ingredient TEXT NOT NULL REFERENCES ingredients (name) ON DELETE CASCADE,
+    alias TEXT NOT NULL,
+    PRIMARY KEY (ingredient, alias)
+);
+
+CREATE TABLE characters (
+    name TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL
+);
+
+CREATE TABLE character_clades (
+    character TEXT NOT NULL REFERENCES characters (name) ON DELETE CASCADE,
+    clade TEXT NOT NULL,
+    PRIMARY KEY (character, clade)
+);
+
+CREATE TABLE character_skills (
+    character TEXT NOT NULL REFERENCES characters (name) ON DELETE CASCADE,
+    skill TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    PRIMARY KEY (character, skill)
+);
+
+CREATE TABLE clades (
+    name TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL,
+    volume_multiplier DOUBLE NOT NULL,
+    description TEXT
+);
+
+-- At most one row
+CREATE TABLE rules (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    primary_points BIGINT NOT NULL,
+    secondary_points BIGINT NOT NULL,
+    tertiary_points BIGINT NOT NULL
+);
+
+-- Provenance of skill effectiveness (`field` is `effectiveness`) and of ingredient
+-- modifiers (`field` is a modifier code such as `dh` or `mdh`)
+CREATE TABLE observations (
+    subject TEXT NOT NULL,
+    name TEXT NOT NULL,
+    field TEXT NOT NULL,
+    source TEXT,
+    date TEXT,
+    confidence TEXT,
+    PRIMARY KEY (subject, name, field)
+);
+
+-- Every save that changed the grimoire, as an update file in JSON
+CREATE TABLE update_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_json TEXT NOT NULL
+);
+
+-- Catalogue of effects, `ingredient_modifiers.effect` is the id of one of them. A new
+-- database starts with the built-in effects
+
+CREATE TABLE effects (
+    id TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL,
+    code TEXT NOT NULL,
+    name TEXT NOT NULL,
+    category TEXT NOT NULL
+);
+
+INSERT INTO effects (id, position, code, name, category) VALUES
+    ('DirectHealing', 0, 'dh', 'Direct healing', 'healing'),
+    ('DirectPoison', 1, 'dp', 'Direct poison', 'poison'),
+    ('HealingOverTime', 2, 'hot', 'Healing over time', 'healing'),
+    ('PoisonOverTime', 3, 'pot', 'Poison over time', 'poison'),
+    ('HealingLength', 4, 'hl', 'Healing length', 'healing'),
+    ('PoisonLength', 5, 'pl', 'Poison length', 'poison'),
+    ('Alcohol', 6, 'a', 'Alcohol', 'alcohol');
+
+-- Constants of the brewing formula, at most one ruleset is in effect
+
+CREATE TABLE rulesets (
+    name TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL,
+    in_effect BOOLEAN NOT NULL,
+    default_lore_effectiveness DOUBLE NOT NULL,
+    advanced_potion_making TEXT NOT NULL,
+    advanced_potion_making_bonus DOUBLE NOT NULL,
+    advanced_potion_making_steps BOOLEAN NOT NULL,
+    weight_offset BIGINT NOT NULL,
+    weight_per_volume DOUBLE NOT NULL
+);
+
+CREATE TABLE ruleset_clades (
+    ruleset TEXT NOT NULL REFERENCES rulesets (name) ON DELETE CASCADE,
+    clade TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    volume_multiplier DOUBLE NOT NULL,
+    PRIMARY KEY (ruleset, clade)
+);
+
+-- How ingredients are made from others
+
+CREATE TABLE productions (
+    ingredient TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL,
+    yields BIGINT NOT NULL
+);
+
+CREATE TABLE production_inputs (
+    production TEXT NOT NULL REFERENCES productions (ingredient) ON DELETE CASCADE,
+    input TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (production, input)
+);
+
+-- Mixes kept by name, the last evaluation is stored as JSON
+
+CREATE TABLE recipes (
+    name TEXT PRIMARY KEY NOT NULL,
+    position INTEGER NOT NULL,
+    character TEXT,
+    notes TEXT NOT NULL,
+    evaluation TEXT
+);
+
+CREATE TABLE recipe_ingredients (
+    recipe TEXT NOT NULL REFERENCES recipes (name) ON DELETE CASCADE,
+    ingredient TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (recipe, ingredient)
+);
+
+CREATE TABLE recipe_tags (
+    recipe TEXT NOT NULL REFERENCES recipes (name) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (recipe, tag)
+);
@@ -0,0 +1,368 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use error_stack::{Report, Result, IntoReport, ResultExt};
+use evalexpr::Node;
+use geneticalchemy::prelude::AlchemyFitnessElement;
+use grimoire2::modify::command::Commands;
+use grimoire2::prelude::{Grimoire, Character};
+use grimoire2::validate::skill_cycles;
+use serde::Serialize;
+use clap::*;
+use thiserror::Error;
+
+use crate::fs::load;
+use crate::output::{self, Output};
+use crate::mix::{MixConfig, or_not_found};
+use crate::optimize2::config::OptimizatorConfig;
+use crate::optimize2::eexpr::EvalExpressionFitnessElement;
+
+#[derive(Error, Debug)]
+pub enum AdviseError {
+    #[error("Could not load file: {0}")]
+    Load(String),
+    #[error("Could not evaluate recipe")]
+    Mix,
+    #[error("Invalid expression: {0}")]
+    BadExpression(String),
+    #[error("Skills form a cycle: {0}")]
+    SkillCycle(String),
+}
+
+#[derive(Serialize, Clone)]
+pub struct TrainingStep {
+    pub skill: String,
+    pub from: u8,
+    pub to: u8,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub raised_parents: Vec<String>,
+    pub cost: u32,
+    pub gain: f64,
+    pub gain_per_point: f64,
+    pub objective: f64,
+}
+
+#[derive(Serialize)]
+pub struct Advice {
+    pub objective: f64,
+    pub candidates: Vec<TrainingStep>,
+    pub plan: Vec<TrainingStep>,
+}
+
+pub fn command() -> Command {
+    Command::new("advise")
+        .before_help("Find out which skills to train next to improve the given recipes")
+        .arg(
+            Arg::new("character")
+                .short('c')
+                .long("character")
+                .required(true)
+                .help("Character name")
+                .env("ALRUST_CHARACTER")
+        )
+        .arg(
+            Arg::new("mixfiles")
+                .index(1)
+                .required(true)
+                .num_args(1..)
+                .help("Target recipes (see help for `mix` command)")
+        )
+        .arg(
+            Arg::new("objective")
+                .short('o')
+                .long("objective")
+                .action(ArgAction::Append)
+                .help("Expression to maximize, may be repeated (same identifiers as `effects` of \
+                       `optimize`; default: dh)")
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .help("Take the objective and grimoire changes from an `optimize` config file")
+        )
+        .arg(
+            Arg::new("step")
+                .long("step")
+                .value_parser(value_parser!(u8))
+                .default_value("10")
+                .help("How many levels to raise a skill by in a single step")
+        )
+        .arg(
+            Arg::new("steps")
+                .long("steps")
+                .value_parser(value_parser!(usize))
+                .default_value("5")
+                .help("Length of the training plan")
+        )
+}
+
+pub fn matched_command(mut grimoire: Grimoire, args: &ArgMatches) {
+    let character_name = args.get_one::<String>("character").unwrap();
+    let step = *args.get_one::<u8>("step").unwrap();
+    let steps = *args.get_one::<usize>("steps").unwrap();
+
+    let recipes = args.get_many::<String>("mixfiles").unwrap().map(|filename| {
+        load::<MixConfig>(Path::new(filename)).change_context(AdviseError::Load(filename.clone()))
+    }).collect::<Result<Vec<MixConfig>, AdviseError>>().unwrap();
+
+    let mut objectives: Vec<Node> = args.get_many::<String>("objective")
+        .map(|x| x.map(|expr| {
+            evalexpr::build_operator_tree(expr)
+                .into_report()
+                .change_context(AdviseError::BadExpression(expr.clone()))
+        }).collect::<Result<Vec<Node>, AdviseError>>().unwrap())
+        .unwrap_or_default();
+
+    if let Some(filename) = args.get_one::<String>("config") {
+        let config: OptimizatorConfig = load(Path::new(filename))
+            .change_context(AdviseError::Load(filename.clone()))
+            .unwrap();
+        config.grimoire.to_update(&grimoire.effects)
+            .into_report()
+            .change_context(AdviseError::Load(filename.clone()))
+            .unwrap()
+            .update(&mut grimoire);
+        objectives.extend(config.effects);
+    }
+
+    if objectives.is_empty() {
+        objectives.push(evalexpr::build_operator_tree("dh").unwrap());
+    }
+
+    let Some(character) = grimoire.characters.get(character_name.as_str()).cloned() else {
+        output::not_found("Character", character_name)
+    };
+
+    let advisor = Advisor::new(grimoire, recipes, objectives);
+    let advice = or_not_found(advisor.advise(&character, step, steps));
+
+    Output::from_args(args).print(&advice);
+}
+
+pub struct Advisor {
+    grimoire: Grimoire,
+    recipes: Vec<MixConfig>,
+    objectives: Vec<EvalExpressionFitnessElement>,
+}
+
+impl Advisor {
+    pub fn new(grimoire: Grimoire, recipes: Vec<MixConfig>, objectives: Vec<Node>) -> Self {
+        Self {
+            grimoire,
+            recipes,
+            objectives: objectives
+                .into_iter()
+                .map(|x| EvalExpressionFitnessElement::new(x, 1.))
+                .collect(),
+        }
+    }
+
+    pub fn advise(&self, character: &Character, step: u8, steps: usize) -> Result<Advice, AdviseError> {
+        // Skill values follow the parents, so a cycle would never end
+        if let Some(cycle) = skill_cycles(&self.grimoire).into_iter().next() {
+            return Err(Report::new(AdviseError::SkillCycle(cycle.join(" -> "))));
+        }
+
+        let objective = self.objective(character)?;
+        let candidates = self.candidates(character, step)?;
+
+        let mut plan = Vec::default();
+        let mut current = character.clone();
+
+        for _ in 0..steps {
+            let best = self.candidates(&current, step)?.into_iter().next();
+
+            match best {
+                Some(x) if x.gain > 0. => {
+                    current = self.train(&current, &x.skill, x.to);
+                    plan.push(x);
+                }
+                _ => break,
+            }
+        }
+
+        Ok(Advice { objective, candidates, plan })
+    }
+
+    /// Every skill that may affect the recipes, raised by `step` levels, ordered by the gain
+    /// per skill point spent. Steps that break the skill point rules of the grimoire are skipped
+    pub fn candidates(&self, character: &Character, step: u8) -> Result<Vec<TrainingStep>, AdviseError> {
+        let objective = self.objective(character)?;
+        let mut result = Vec::default();
+
+        for skill in self.relevant_skills() {
+            let from = character.raw_skill(&skill);
+            let to = from.saturating_add(step).min(100);
+
+            if to == from && character.skill(&self.grimoire.skills, &skill) == from {
+                continue;
+            }
+
+            let trained = self.train(character, &skill, to);
+            let cost = character.points_to(&trained, &self.grimoire.skills);
+
+            if cost == 0 || trained.check(&self.grimoire).is_err() {
+                continue;
+            }
+
+            let trained_objective = self.objective(&trained)?;
+            let gain = trained_objective - objective;
+
+            let mut raised_parents: Vec<String> = trained.skills
+                .iter()
+                .filter(|(name, value)| **name != skill && **value > character.raw_skill(name))
+                .map(|(name, _)| name.clone())
+                .collect();
+            raised_parents.sort();
+
+            result.push(TrainingStep {
+                skill,
+                from,
+                to,
+                raised_parents,
+                cost,
+                gain,
+                gain_per_point: gain / cost as f64,
+                objective: trained_objective,
+            });
+        }
+
+        result.sort_by(|a, b| b.gain_per_point.total_cmp(&a.gain_per_point));
+
+        Ok(result)
+    }
+
+    fn train(&self, character: &Character, skill: &str, value: u8) -> Character {
+        character.with_skill_raised(&self.grimoire.skills, skill, value)
+    }
+
+    /// Lores of the ingredients used by the recipes, their parents and Advanced Potion Making
+    /// of the ruleset in effect
+    fn relevant_skills(&self) -> BTreeSet<String> {
+        let mut result = BTreeSet::default();
+        result.insert(self.grimoire.ruleset().advanced_potion_making);
+
+        let mut queue: Vec<String> = self.recipes
+            .iter()
+            .flat_map(|x| x.mix.keys())
+            .filter_map(|x| self.grimoire.ingredients.get(x)?.skill.clone())
+            .collect();
+
+        while let Some(skill) = queue.pop() {
+            if !result.insert(skill.clone()) {
+                continue;
+            }
+
+            if let Some(x) = self.grimoire.skills.get(&skill) {
+                queue.extend(x.parent.iter().cloned());
+                queue.extend(x.parent_2.iter().cloned());
+            }
+        }
+
+        result
+    }
+
+    fn objective(&self, character: &Character) -> Result<f64, AdviseError> {
+        let mut result = 0.;
+
+        for recipe in &self.recipes {
+            result += recipe
+                .with_mix(self.grimoire.clone(), character, |mix| {
+                    self.objectives.iter().map(|x| x.fitness(mix)).sum::<f64>()
+                })
+                .change_context(AdviseError::Mix)?;
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::grimoire::{Ingredient, Skill, SkillPointRules};
+    use grimoire2::theoretical::Theoretical;
+
+    use super::*;
+
+    fn grimoire() -> Grimoire {
+        let mut leaf = Ingredient {
+            skill: Some("Herbology".to_string()),
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        };
+        leaf.modifiers[Effect::DirectHealing].term = Theoretical::Known(2.);
+
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Leaf".to_string(), leaf);
+        grimoire.skills.insert("Herbology".to_string(), Skill::new(Theoretical::Known(1.), Some("Botany".to_string()), None));
+        grimoire.skills.insert("Botany".to_string(), Skill::new(Theoretical::Known(0.5), None, None));
+        grimoire
+    }
+
+    fn advisor(grimoire: Grimoire) -> Advisor {
+        let recipe = MixConfig {
+            mix: [("Leaf".to_string(), 2)].into_iter().collect(),
+            ..MixConfig::default()
+        };
+        Advisor::new(grimoire, vec![recipe], vec![evalexpr::build_operator_tree("dh").unwrap()])
+    }
+
+    #[test]
+    fn test_plan() {
+        let advice = advisor(grimoire()).advise(&Character::default(), 10, 3).unwrap();
+
+        assert_eq!(advice.plan.len(), 3);
+        assert!(advice.plan.iter().all(|x| x.gain > 0.));
+        assert!(advice.plan.windows(2).all(|x| x[0].objective < x[1].objective));
+        assert_eq!(advice.plan[0].skill, advice.candidates[0].skill);
+
+        // Herbology can't get past Botany, so both are raised together
+        let herbology = advice.candidates.iter().find(|x| x.skill == "Herbology").unwrap();
+        assert_eq!((herbology.from, herbology.to, herbology.cost), (0, 10, 20));
+        assert_eq!(herbology.raised_parents, ["Botany"]);
+    }
+
+    #[test]
+    fn test_point_budget_across_steps() {
+        let mut grimoire = grimoire();
+        grimoire.rules = Some(SkillPointRules::new(45, 0, 0));
+
+        let advice = advisor(grimoire.clone()).advise(&Character::default(), 10, 10).unwrap();
+        let spent: u32 = advice.plan.iter().map(|x| x.cost).sum();
+
+        // Every step fits into what is left of the budget after the steps before it
+        assert!(!advice.plan.is_empty());
+        assert!(advice.plan.len() < 10);
+        assert!(spent <= 45, "{spent}");
+
+        let trained = advice.plan.iter().fold(Character::default(), |character, x| {
+            character.with_skill_raised(&grimoire.skills, &x.skill, x.to)
+        });
+        assert!(trained.check(&grimoire).is_ok());
+        assert_eq!(Character::default().points_to(&trained, &grimoire.skills), spent);
+    }
+
+    #[test]
+    fn test_skill_cycle() {
+        let mut grimoire = grimoire();
+        grimoire.skills["Botany"].parent = Some("Herbology".to_string());
+
+        let report = advisor(grimoire).advise(&Character::default(), 10, 5).err().unwrap();
+        assert!(matches!(report.current_context(), AdviseError::SkillCycle(_)));
+    }
+
+    #[test]
+    fn test_character_at_cap() {
+        let grimoire = grimoire();
+        let skills = ["Herbology", "Botany", grimoire.ruleset().advanced_potion_making.as_str()]
+            .map(|x| (x.to_string(), 100))
+            .into_iter()
+            .collect();
+        let character = Character::new(Default::default(), skills);
+
+        let advice = advisor(grimoire).advise(&character, 10, 5).unwrap();
+        assert!(advice.candidates.is_empty());
+        assert!(advice.plan.is_empty());
+        assert!(advice.objective > 0.);
+    }
+}
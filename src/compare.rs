@@ -0,0 +1,355 @@
+use std::path::Path;
+use error_stack::{Report, Result, IntoReport, ResultExt};
+use grimoire2::prelude::{Grimoire, Theoretical};
+use grimoire2::standalone::Mix;
+use grimoire_serde::potion::{PotionSerializableConfig, TotalEffect};
+use grimoire_serde::theoretical::TheoreticalWrapper;
+use indexmap::{IndexMap, IndexSet};
+use serde::Serialize;
+use clap::*;
+use thiserror::Error;
+
+use crate::fs::load;
+use crate::output::{self, Output};
+use crate::mix::{MixConfig, MixError, or_not_found};
+
+#[derive(Error, Debug)]
+pub enum CompareError {
+    #[error("Character not found: {0}")]
+    CharacterNotFound(String),
+    #[error("Ruleset not found: {0}")]
+    RulesetNotFound(String),
+    #[error("Could not load recipe: {0}")]
+    LoadRecipe(String),
+    #[error("Could not evaluate recipe: {0}")]
+    Mix(String),
+}
+
+#[derive(Serialize, Clone)]
+pub struct ComparedPotion {
+    pub recipe: String,
+    pub character: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ruleset: Option<String>,
+    pub values: IndexMap<String, TheoreticalWrapper>,
+    pub deltas: IndexMap<String, f64>,
+}
+
+pub fn command() -> Command {
+    Command::new("compare")
+        .before_help("Compare several recipes, characters and/or rulesets side by side")
+        .arg(
+            Arg::new("character")
+                .short('c')
+                .long("character")
+                .required(true)
+                .action(ArgAction::Append)
+                .help("Character name; may be repeated to compare characters")
+                .env("ALRUST_CHARACTER")
+        )
+        .arg(
+            Arg::new("mixfiles")
+                .index(1)
+                .required(true)
+                .num_args(1..)
+                .help("Mix configuration files (see help for `mix` command)")
+        )
+        .arg(
+            Arg::new("rulesets")
+                .long("rulesets")
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .help("Comma separated rulesets to brew every recipe under, to compare game rules")
+        )
+        .arg(
+            Arg::new("raw")
+                .long("raw")
+                .action(ArgAction::SetTrue)
+                .help("Also show total healing and poison that ignore each other, for every recipe")
+        )
+}
+
+pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+    let characters: Vec<String> = args.get_many::<String>("character").unwrap().cloned().collect();
+    let mixfiles: Vec<String> = args.get_many::<String>("mixfiles").unwrap().cloned().collect();
+    let rulesets: Vec<String> = args.get_many::<String>("rulesets").unwrap_or_default().cloned().collect();
+
+    let recipes = mixfiles.iter().map(|filename| {
+        let path = Path::new(filename);
+        let name = path.file_stem().and_then(|x| x.to_str()).unwrap_or(filename).to_string();
+        let config: MixConfig = load(path)
+            .change_context(CompareError::LoadRecipe(filename.clone()))?;
+        Ok((name, config))
+    }).collect::<Result<Vec<(String, MixConfig)>, CompareError>>().unwrap();
+
+    if let Some(x) = characters.iter().find(|x| !grimoire.characters.contains_key(*x)) {
+        output::not_found("Character", x);
+    }
+
+    if let Some(x) = rulesets.iter().find(|x| grimoire.clone().select_ruleset(x).is_err()) {
+        output::not_found("Ruleset", x);
+    }
+
+    let compared = or_not_found(compare(&grimoire, &recipes, &characters, &rulesets, args.get_flag("raw")));
+
+    let output = Output::from_args(args);
+
+    match output {
+        Output::Yaml | Output::Json => output.print_rows(&compared),
+        Output::Table | Output::Csv => output.print_rows(&rows(&compared, output)),
+    }
+}
+
+/// Evaluate every recipe for every character, under every ruleset or the one in effect if
+/// none are given. Deltas are computed against the first potion, for the columns it has.
+pub fn compare(
+    grimoire: &Grimoire,
+    recipes: &[(String, MixConfig)],
+    characters: &[String],
+    rulesets: &[String],
+    raw: bool,
+) -> Result<Vec<ComparedPotion>, CompareError> {
+    let mut result: Vec<ComparedPotion> = Vec::default();
+
+    let grimoires = match rulesets.is_empty() {
+        true => vec![(None, grimoire.clone())],
+        false => rulesets.iter().map(|name| {
+            let mut grimoire = grimoire.clone();
+            grimoire.select_ruleset(name)
+                .into_report()
+                .change_context(CompareError::RulesetNotFound(name.clone()))?;
+            Ok((Some(name.clone()), grimoire))
+        }).collect::<Result<Vec<_>, CompareError>>()?,
+    };
+
+    for (recipe, config) in recipes {
+        for character_name in characters {
+            for (ruleset, grimoire) in &grimoires {
+                let character = grimoire.characters.get(character_name.as_str())
+                    .ok_or(Report::new(CompareError::CharacterNotFound(character_name.clone())))?;
+
+                let values = config
+                    .with_mix(grimoire.clone(), character, |mix| columns(mix, &config.potion, raw))
+                    .map_err(|err: Report<MixError>| {
+                        let message = err.current_context().to_string();
+                        err.change_context(CompareError::Mix(message))
+                    })?;
+
+                result.push(ComparedPotion {
+                    recipe: recipe.clone(),
+                    character: character_name.clone(),
+                    ruleset: ruleset.clone(),
+                    values,
+                    deltas: IndexMap::default(),
+                });
+            }
+        }
+    }
+
+    let baseline = result.first().cloned();
+
+    if let Some(baseline) = baseline {
+        for potion in result.iter_mut() {
+            potion.deltas = potion.values.iter()
+                .filter_map(|(name, value)| {
+                    let base = baseline.values.get(name)?;
+                    Some((name.clone(), inner(*value) - inner(*base)))
+                })
+                .collect();
+        }
+    }
+
+    Ok(result)
+}
+
+/// Columns of a potion follow the `potion` section of its recipe; `raw` adds the raw totals
+/// to every recipe
+fn columns(mix: &Mix, config: &PotionSerializableConfig, raw: bool) -> IndexMap<String, TheoreticalWrapper> {
+    let potion = config.serialize_mix(mix);
+    let mut values: IndexMap<String, TheoreticalWrapper> = IndexMap::default();
+
+    if potion.volume.is_some() {
+        values.insert("volume".to_string(), mix.volume().into());
+    }
+
+    values.extend(potion.effects.unwrap_or_default());
+
+    let totals = [
+        ("healing", potion.total_healing),
+        ("poison", potion.total_poison),
+        ("healing_raw", potion.total_healing_raw.or_else(|| raw.then(|| config.serialize_total_healing_raw(mix)))),
+        ("poison_raw", potion.total_poison_raw.or_else(|| raw.then(|| config.serialize_total_poison_raw(mix)))),
+    ];
+
+    for (name, total) in totals {
+        if let Some(TotalEffect { over_time_total, per_second_total, length_total }) = total {
+            values.insert(format!("{name}_over_time"), over_time_total);
+            values.insert(format!("{name}_per_second"), per_second_total);
+            values.insert(format!("{name}_length"), length_total);
+        }
+    }
+
+    values
+}
+
+/// Every column of any of the potions, in the order they first appear
+fn column_names(compared: &[ComparedPotion]) -> Vec<String> {
+    let mut names: IndexSet<String> = IndexSet::default();
+    for potion in compared {
+        names.extend(potion.values.keys().cloned());
+    }
+    names.into_iter().collect()
+}
+
+fn inner(value: TheoreticalWrapper) -> f64 {
+    Theoretical::<f64>::from(value).inner()
+}
+
+fn theoretical_to_str(value: TheoreticalWrapper) -> String {
+    match value {
+        TheoreticalWrapper::Known(x) => format!("{x:.3}"),
+        TheoreticalWrapper::Theory(x) => format!("!?{x:.3}"),
+        TheoreticalWrapper::Unknown => "??".to_string(),
+    }
+}
+
+/// Flat rows for tables and CSV. Tables show the difference from the first row next to the
+/// value, CSV has a `<column>_delta` column for each of them and keeps full precision. Columns
+/// a potion doesn't have are empty
+fn rows(compared: &[ComparedPotion], output: Output) -> Vec<IndexMap<String, String>> {
+    let names = column_names(compared);
+    let rulesets = compared.iter().any(|x| x.ruleset.is_some());
+
+    compared.iter().enumerate().map(|(i, potion)| {
+        let mut row: IndexMap<String, String> = IndexMap::default();
+        row.insert("recipe".to_string(), potion.recipe.clone());
+        row.insert("character".to_string(), potion.character.clone());
+
+        if rulesets {
+            row.insert("ruleset".to_string(), potion.ruleset.clone().unwrap_or_default());
+        }
+
+        for name in &names {
+            let value = potion.values.get(name);
+            let delta = potion.deltas.get(name);
+
+            let cell = match output {
+                Output::Csv => value.map(|x| x.to_string()),
+                _ => value.map(|x| match delta {
+                    Some(delta) if i != 0 && *delta != 0. => format!("{} ({delta:+.3})", theoretical_to_str(*x)),
+                    _ => theoretical_to_str(*x),
+                }),
+            };
+            row.insert(name.clone(), cell.unwrap_or_default());
+        }
+
+        if output == Output::Csv {
+            for name in &names {
+                let delta = potion.deltas.get(name).map(|x| x.to_string());
+                row.insert(format!("{name}_delta"), delta.unwrap_or_default());
+            }
+        }
+
+        row
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::grimoire::{Character, Ingredient, Ruleset};
+
+    use super::*;
+
+    fn grimoire() -> Grimoire {
+        let mut leaf = Ingredient {
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        };
+        leaf.modifiers[Effect::DirectHealing].term = Theoretical::Known(2.);
+        leaf.modifiers[Effect::HealingOverTime].term = Theoretical::Theory(1.);
+
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Leaf".to_string(), leaf);
+        grimoire.ingredients.insert("Water".to_string(), Ingredient {
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        });
+        grimoire.characters.insert("Tashka".to_string(), Character::default());
+        grimoire.rulesets.insert("heavy".to_string(), Ruleset { weight_per_volume: 2., ..Ruleset::default() });
+        grimoire
+    }
+
+    fn recipe(name: &str, yaml: &str) -> (String, MixConfig) {
+        (name.to_string(), serde_yaml::from_str(yaml).unwrap())
+    }
+
+    fn recipes() -> Vec<(String, MixConfig)> {
+        vec![
+            recipe("strong", "mix: {Leaf: 2}"),
+            recipe("weak", "mix: {Leaf: 1, Water: 1}\npotion: {volume: false, total_healing: true}"),
+        ]
+    }
+
+    #[test]
+    fn test_compare_deltas() {
+        let compared = compare(&grimoire(), &recipes(), &["Tashka".to_string()], &[], false).unwrap();
+
+        assert_eq!(compared.len(), 2);
+        assert!(compared[0].deltas.values().all(|x| *x == 0.));
+        assert_eq!(compared[1].deltas["dh"], inner(compared[1].values["dh"]) - inner(compared[0].values["dh"]));
+        assert!(compared[1].deltas["dh"] < 0.);
+    }
+
+    #[test]
+    fn test_compare_potion_settings_of_each_recipe() {
+        let compared = compare(&grimoire(), &recipes(), &["Tashka".to_string()], &[], false).unwrap();
+        let (strong, weak) = (&compared[0], &compared[1]);
+
+        assert!(strong.values.contains_key("volume"));
+        assert!(!strong.values.contains_key("healing_over_time"));
+        assert!(!weak.values.contains_key("volume"));
+        assert!(weak.values.contains_key("healing_over_time"));
+
+        // Only columns the first potion has get a difference
+        assert!(!weak.deltas.contains_key("healing_over_time"));
+
+        let raw = compare(&grimoire(), &recipes(), &["Tashka".to_string()], &[], true).unwrap();
+        assert!(raw.iter().all(|x| x.values.contains_key("healing_raw_over_time")));
+        assert!(raw.iter().all(|x| x.values.contains_key("poison_raw_length")));
+    }
+
+    #[test]
+    fn test_compare_rulesets() {
+        let rulesets = ["default".to_string(), "heavy".to_string()];
+        let compared = compare(&grimoire(), &recipes()[..1], &["Tashka".to_string()], &rulesets, false).unwrap();
+
+        let names: Vec<Option<&str>> = compared.iter().map(|x| x.ruleset.as_deref()).collect();
+        assert_eq!(names, vec![Some("default"), Some("heavy")]);
+        assert_ne!(compared[1].deltas["volume"], 0.);
+
+        let missing = compare(&grimoire(), &recipes(), &["Bob".to_string()], &[], false);
+        assert!(matches!(missing.map(|_| ()).unwrap_err().current_context(), CompareError::CharacterNotFound(_)));
+    }
+
+    #[test]
+    fn test_rows() {
+        let compared = compare(&grimoire(), &recipes(), &["Tashka".to_string()], &[], false).unwrap();
+
+        let csv = rows(&compared, Output::Csv);
+        assert_eq!(csv[0]["volume"], compared[0].values["volume"].to_string());
+        assert!(csv[0]["dh"].starts_with("!? "));
+        assert_eq!(csv[0]["healing_over_time"], "");
+        assert_eq!(csv[1]["volume"], "");
+        assert_eq!(csv[1]["volume_delta"], "");
+        assert_eq!(csv[1]["dh_delta"], compared[1].deltas["dh"].to_string());
+        assert!(!csv[0].contains_key("ruleset"));
+
+        let table = rows(&compared, Output::Table);
+        assert_eq!(table[0]["volume"], format!("{:.3}", inner(compared[0].values["volume"])));
+        assert!(table[1]["dh"].ends_with(&format!(" ({:+.3})", compared[1].deltas["dh"])));
+        assert!(table[1]["dh"].starts_with("!?"));
+        assert!(!table.iter().any(|x| x.contains_key("dh_delta")));
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use clap::*;
+use grimoire2::grimoire::Grimoire;
+use grimoire_serde::modify::GrimoireUpdateSerializable;
+
+use crate::fs::{load_grimoire, save};
+
+pub fn command() -> Command {
+    Command::new("diff")
+        .before_help("Print what changed from this grimoire to another one as an update file \
+                      for `update --from`")
+        .arg(
+            Arg::new("other")
+                .index(1)
+                .required(true)
+                .value_name("grimoire")
+                .help("Changed grimoire")
+        )
+        .arg(
+            Arg::new("to")
+                .short('t')
+                .long("to")
+                .value_name("to")
+                .help("Where to save the update file, printed as YAML if not set")
+        )
+}
+
+pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+    let (other, _) = load_grimoire(Path::new(args.get_one::<String>("other").unwrap())).unwrap();
+
+    let update = GrimoireUpdateSerializable::diff(&grimoire, &other);
+
+    match args.get_one::<String>("to") {
+        Some(to) => save(Path::new(to), &update).unwrap(),
+        None => serde_yaml::to_writer(std::io::stdout(), &update).unwrap(),
+    }
+}
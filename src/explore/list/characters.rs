@@ -1,50 +1,80 @@
 use clap::*;
-use evalexpr::context_map;
 use grimoire2::grimoire::{Grimoire, Character};
 use evalexpr::*;
+use serde::Serialize;
+
+use crate::output::Output;
+use super::query::{self, Query};
+
+#[derive(Serialize)]
+pub struct CharacterRow {
+    name: String,
+    clades: Vec<String>,
+}
 
 pub fn command() -> Command {
-    Command::new("characters")
-        .arg(
-            Arg::new("filter")
-            .index(1)
-            .trailing_var_arg(true)
-            .required(false)
-            .value_name("filter")
-        )
+    query::args(Command::new("characters"))
 }
 
 pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
-    list_characters(grimoire, args.get_one::<String>("filter").cloned())
+    list_characters(grimoire, Query::from_args(args), Output::from_args(args))
 }
 
-pub fn list_characters(grimorie: Grimoire, filter: Option<String>) {
-    for (name, _) in grimorie.characters.into_iter().filter(|(_, c)| {
-        match &filter {
-            Some(x) => filter_character(c.clone(), x),
-            None => true,
-        } 
-    }) {
-        println!("{}", name);
+pub fn list_characters(grimoire: Grimoire, query: Query, output: Output) {
+    let items: Vec<((&String, &Character), HashMapContext)> = grimoire.characters.iter().map(|(name, character)| {
+        ((name, character), character_context(&grimoire, name, character))
+    }).collect();
+
+    query.check(&character_context(&grimoire, "", &Character::default())).unwrap();
+    let selected = query.select(items).unwrap();
+
+    if query.columns.is_some() {
+        let rows = selected.iter().map(|((name, _), context)| {
+            query.row(name, context).map(Option::unwrap)
+        }).collect::<Result<Vec<_>, _>>().unwrap();
+        return output.print_rows(&rows);
     }
+
+    let rows: Vec<CharacterRow> = selected.into_iter().map(|((name, character), _)| {
+        let mut clades: Vec<String> = character.clades.iter().cloned().collect();
+        clades.sort();
+        CharacterRow { name: name.clone(), clades }
+    }).collect();
+
+    output.print_rows(&rows);
 }
 
-pub fn filter_character(character: Character, filter: &str) -> bool {
+fn character_context(grimoire: &Grimoire, name: &str, character: &Character) -> HashMapContext {
+    let mut context = HashMapContext::new();
+
+    context.set_value("name".to_string(), name.into()).unwrap();
+    context.set_value("clades".to_string(), query::strings(&character.clades)).unwrap();
+
+    let character_1 = character.clone();
+    context.set_function("raw_skill".to_string(), Function::new(move |skill| {
+        let skill_str = skill.as_string()?;
+        let result: i64 = character_1.raw_skill(&skill_str).into();
+        Ok(result.into())
+    })).unwrap();
+
+    let (character_2, skills) = (character.clone(), grimoire.skills.clone());
+    context.set_function("skill".to_string(), Function::new(move |skill| {
+        let skill_str = skill.as_string()?;
+        let result: i64 = character_2.skill(&skills, &skill_str).into();
+        Ok(result.into())
+    })).unwrap();
+
+    let (character_3, skills, ruleset) = (character.clone(), grimoire.skills.clone(), grimoire.ruleset());
+    context.set_function("lore".to_string(), Function::new(move |skill| {
+        let skill_str = skill.as_string()?;
+        Ok(character_3.lore_multiplier(&skills, &skill_str, &ruleset).inner().into())
+    })).unwrap();
 
     let clades = character.clades.clone();
+    context.set_function("clade".to_string(), Function::new(move |clade| {
+        let clade_str = clade.as_string()?;
+        Ok(clades.contains(&clade_str).into())
+    })).unwrap();
 
-    let context = context_map! {
-        "raw_skill" => Function::new(move |skill| {
-            let skill_str = skill.as_string()?;
-            let result: i64 = character.raw_skill(&skill_str).into();
-            Ok(result.into())
-        }),
-        "clade" => Function::new(move |clade| {
-            let clade_str = clade.as_string()?;
-            let result = clades.contains(&clade_str);
-            Ok(result.into())
-        }),
-    }.unwrap();
-
-    eval_boolean_with_context(filter, &context).unwrap()
-}
\ No newline at end of file
+    context
+}
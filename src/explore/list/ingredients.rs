@@ -1,22 +1,34 @@
-use std::io::stdout;
 use evalexpr::*;
-use grimoire2::effect::Effect;
+use grimoire2::effect::Effects;
 use grimoire2::grimoire::{Ingredient, Grimoire};
+use grimoire2::standalone::OptimizedGrimoire;
+use grimoire2::theoretical::Theoretical;
 use grimoire_serde::grimoire::ingredient::IngredientHumanReadable;
+use grimoire_serde::theoretical::TheoreticalWrapper;
 use indexmap::IndexMap;
-use serde_yaml::to_writer;
+use serde::Serialize;
 use clap::*;
 
-use crate::explore::view::ingredient;
+use crate::optimize2::build::Optimizator;
+use crate::output::{self, Output};
+use super::query::{self, Query};
+
+#[derive(Serialize)]
+pub struct IngredientRow {
+    name: String,
+    skill: Option<String>,
+    weight: String,
+    tags: Vec<String>,
+}
 
 pub fn command() -> Command {
-    Command::new("ingredients")
+    query::args(Command::new("ingredients"))
         .arg(
-            Arg::new("filter")
-            .index(1)
-            .trailing_var_arg(true)
-            .required(false)
-            .value_name("filter")
+            Arg::new("tag")
+            .help("Only list ingredients with this tag, may be repeated")
+            .short('t')
+            .long("tag")
+            .action(ArgAction::Append)
         )
         .arg(
             Arg::new("detailed")
@@ -25,146 +37,148 @@ pub fn command() -> Command {
             .long("detailed")
             .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("character")
+            .help("Apply the lore multipliers and Advanced Potion Making of this character to the \
+                   base values")
+            .short('c')
+            .long("character")
+        )
 }
 
 pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+    let character = args.get_one::<String>("character").map(|name| {
+        match grimoire.characters.get(name) {
+            Some(x) => OptimizedGrimoire::from((x, &grimoire)),
+            None => output::not_found("Character", name),
+        }
+    });
+
     list_ingredients(
         grimoire, 
-        args.get_one::<String>("filter").cloned(), 
-        args.get_flag("detailed")
+        Query::from_args(args),
+        args.get_many::<String>("tag").map(|x| x.cloned().collect()).unwrap_or_default(),
+        args.get_flag("detailed"),
+        character.as_ref(),
+        Output::from_args(args),
     )
 }
 
-pub fn list_ingredients(grimoire: Grimoire, filter: Option<String>, detailed: bool) {
-    let filtered = match filter {
-        Some(filter) => {
-            grimoire.ingredients.into_iter().filter(|(_, ingredient)| {
-                filter_ingredient(ingredient, &filter)
-            }).collect()
-        }
-        None => grimoire.ingredients,
-    };
+pub fn list_ingredients(
+    grimoire: Grimoire,
+    query: Query,
+    tags: Vec<String>,
+    detailed: bool,
+    character: Option<&OptimizedGrimoire>,
+    output: Output,
+) {
+    let items: Vec<((String, Ingredient), HashMapContext)> = grimoire.ingredients
+        .into_iter()
+        .filter(|(_, ingredient)| tags.iter().all(|x| ingredient.has_tag(x)))
+        .map(|(name, ingredient)| {
+            let context = ingredient_context(&name, &ingredient, &grimoire.effects, character);
+            ((name, ingredient), context)
+        })
+        .collect();
+
+    query.check(&ingredient_context("", &Ingredient::default(), &grimoire.effects, character)).unwrap();
+    let selected = query.select(items).unwrap();
+
+    if query.columns.is_some() {
+        let rows = selected.iter().map(|((name, _), context)| {
+            query.row(name, context).map(Option::unwrap)
+        }).collect::<Result<Vec<_>, _>>().unwrap();
+        return output.print_rows(&rows);
+    }
 
     if detailed {
-        let filtered: IndexMap<String, IngredientHumanReadable> = filtered.into_iter()
-            .map(|(n, v)| (n, v.into()))
+        let filtered: IndexMap<String, IngredientHumanReadable> = selected.into_iter()
+            .map(|((n, v), _)| (n, IngredientHumanReadable::new(v, &grimoire.effects)))
             .collect();
-        return to_writer(stdout(), &filtered).unwrap();
+        return output.print(&filtered);
     } 
-    for name in filtered.keys() {
-        println!("{name}");
-    }
+
+    let rows: Vec<IngredientRow> = selected.into_iter().map(|((name, ingredient), _)| IngredientRow {
+        name,
+        skill: ingredient.skill,
+        weight: TheoreticalWrapper::from(ingredient.weight).to_string(),
+        tags: ingredient.tags.into_iter().collect(),
+    }).collect();
+
+    output.print_rows(&rows);
 }
 
-fn filter_ingredient(ingredient: &Ingredient, filter: &str) -> bool {
+/// Base values and multipliers by their codes. With a character, base values are the ones the
+/// character actually gets, after the lore multiplier and Advanced Potion Making
+fn ingredient_context(
+    name: &str,
+    ingredient: &Ingredient,
+    effects: &Effects,
+    character: Option<&OptimizedGrimoire>,
+) -> HashMapContext {
     let mut context = HashMapContext::new();
 
-    context.set_value("weight".to_string(), ingredient.weight.into()).unwrap();
+    context.set_value("name".to_string(), name.into()).unwrap();
+    context.set_value("skill".to_string(), ingredient.skill.clone().unwrap_or_default().into()).unwrap();
+    query::set_theoretical(&mut context, "weight", ingredient.weight);
+    context.set_value("source".to_string(), ingredient.source.clone().unwrap_or_default().into()).unwrap();
+    context.set_value("tags".to_string(), query::strings(&ingredient.tags)).unwrap();
+    context.set_function("tag".to_string(), Function::new(Optimizator::tag_function(ingredient))).unwrap();
+
+    let multiplier = match character {
+        Some(grimoire) => {
+            let lore = grimoire.ingredients.by_name(name)
+                .map(|i| grimoire.ingredients[i].lore_multiplier)
+                .unwrap_or(Theoretical::Known(1.));
+            query::set_theoretical(&mut context, "lore", lore);
+            Some(lore * Theoretical::Known(grimoire.advanced_potion_making_mod))
+        },
+        None => None,
+    };
+
+    for (code, effect, is_multiplier) in effects.modifier_codes() {
+        let value = match is_multiplier {
+            true => ingredient.modifiers[effect].multiplier,
+            false => multiplier
+                .map(|x| x * ingredient.modifiers[effect].term)
+                .unwrap_or(ingredient.modifiers[effect].term),
+        };
+        query::set_theoretical(&mut context, &code, value);
+    }
 
-    {
-        let effect = Effect::DirectHealing;
-        let term = "dh";
+    context
+}
 
-        context.set_value("dh".to_string(), ingredient.modifiers[effect].term.inner().into()).unwrap();
-        context.set_value(format!("{term}_known"), ingredient.modifiers[effect].term.is_known().into()).unwrap();
-        context.set_value(format!("{term}_theory"), ingredient.modifiers[effect].term.is_theory().into()).unwrap();
-        context.set_value(format!("{term}_unknown"), ingredient.modifiers[effect].term.is_unknown().into()).unwrap();
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
 
-        context.set_value(format!("m{term}"), ingredient.modifiers[effect].multiplier.inner().into()).unwrap();
-        context.set_value(format!("m{term}_known"), ingredient.modifiers[effect].multiplier.is_known().into()).unwrap();
-        context.set_value(format!("m{term}_theory"), ingredient.modifiers[effect].multiplier.is_theory().into()).unwrap();
-        context.set_value(format!("m{term}_unknown"), ingredient.modifiers[effect].multiplier.is_unknown().into()).unwrap();
-    };
+    use crate::fs::tests::grimoire;
 
-    {
-        let effect = Effect::DirectPoison;
-        let term = "dp";
-
-        context.set_value(term.to_string(), ingredient.modifiers[effect].term.inner().into()).unwrap();
-        context.set_value(format!("{term}_known"), ingredient.modifiers[effect].term.is_known().into()).unwrap();
-        context.set_value(format!("{term}_theory"), ingredient.modifiers[effect].term.is_theory().into()).unwrap();
-        context.set_value(format!("{term}_unknown"), ingredient.modifiers[effect].term.is_unknown().into()).unwrap();
-
-        context.set_value(format!("m{term}"), ingredient.modifiers[effect].multiplier.inner().into()).unwrap();
-        context.set_value(format!("m{term}_known"), ingredient.modifiers[effect].multiplier.is_known().into()).unwrap();
-        context.set_value(format!("m{term}_theory"), ingredient.modifiers[effect].multiplier.is_theory().into()).unwrap();
-        context.set_value(format!("m{term}_unknown"), ingredient.modifiers[effect].multiplier.is_unknown().into()).unwrap();
-    };    
-
-    {
-        let effect = Effect::HealingOverTime;
-        let term = "hot";
-
-        context.set_value(term.to_string(), ingredient.modifiers[effect].term.inner().into()).unwrap();
-        context.set_value(format!("{term}_known"), ingredient.modifiers[effect].term.is_known().into()).unwrap();
-        context.set_value(format!("{term}_theory"), ingredient.modifiers[effect].term.is_theory().into()).unwrap();
-        context.set_value(format!("{term}_unknown"), ingredient.modifiers[effect].term.is_unknown().into()).unwrap();
-
-        context.set_value(format!("m{term}"), ingredient.modifiers[effect].multiplier.inner().into()).unwrap();
-        context.set_value(format!("m{term}_known"), ingredient.modifiers[effect].multiplier.is_known().into()).unwrap();
-        context.set_value(format!("m{term}_theory"), ingredient.modifiers[effect].multiplier.is_theory().into()).unwrap();
-        context.set_value(format!("m{term}_unknown"), ingredient.modifiers[effect].multiplier.is_unknown().into()).unwrap();
-    };    
-
-    {
-        let effect = Effect::PoisonOverTime;
-        let term = "pot";
-
-        context.set_value(term.to_string(), ingredient.modifiers[effect].term.inner().into()).unwrap();
-        context.set_value(format!("{term}_known"), ingredient.modifiers[effect].term.is_known().into()).unwrap();
-        context.set_value(format!("{term}_theory"), ingredient.modifiers[effect].term.is_theory().into()).unwrap();
-        context.set_value(format!("{term}_unknown"), ingredient.modifiers[effect].term.is_unknown().into()).unwrap();
-
-        context.set_value(format!("m{term}"), ingredient.modifiers[effect].multiplier.inner().into()).unwrap();
-        context.set_value(format!("m{term}_known"), ingredient.modifiers[effect].multiplier.is_known().into()).unwrap();
-        context.set_value(format!("m{term}_theory"), ingredient.modifiers[effect].multiplier.is_theory().into()).unwrap();
-        context.set_value(format!("m{term}_unknown"), ingredient.modifiers[effect].multiplier.is_unknown().into()).unwrap();
-    };    
-
-    {
-        let effect = Effect::HealingLength;
-        let term = "hl";
-
-        context.set_value(term.to_string(), ingredient.modifiers[effect].term.inner().into()).unwrap();
-        context.set_value(format!("{term}_known"), ingredient.modifiers[effect].term.is_known().into()).unwrap();
-        context.set_value(format!("{term}_theory"), ingredient.modifiers[effect].term.is_theory().into()).unwrap();
-        context.set_value(format!("{term}_unknown"), ingredient.modifiers[effect].term.is_unknown().into()).unwrap();
-
-        context.set_value(format!("m{term}"), ingredient.modifiers[effect].multiplier.inner().into()).unwrap();
-        context.set_value(format!("m{term}_known"), ingredient.modifiers[effect].multiplier.is_known().into()).unwrap();
-        context.set_value(format!("m{term}_theory"), ingredient.modifiers[effect].multiplier.is_theory().into()).unwrap();
-        context.set_value(format!("m{term}_unknown"), ingredient.modifiers[effect].multiplier.is_unknown().into()).unwrap();
-    };    
-
-    {
-        let effect = Effect::PoisonLength;
-        let term = "pl";
-
-        context.set_value(term.to_string(), ingredient.modifiers[effect].term.inner().into()).unwrap();
-        context.set_value(format!("{term}_known"), ingredient.modifiers[effect].term.is_known().into()).unwrap();
-        context.set_value(format!("{term}_theory"), ingredient.modifiers[effect].term.is_theory().into()).unwrap();
-        context.set_value(format!("{term}_unknown"), ingredient.modifiers[effect].term.is_unknown().into()).unwrap();
-
-        context.set_value(format!("m{term}"), ingredient.modifiers[effect].multiplier.inner().into()).unwrap();
-        context.set_value(format!("m{term}_known"), ingredient.modifiers[effect].multiplier.is_known().into()).unwrap();
-        context.set_value(format!("m{term}_theory"), ingredient.modifiers[effect].multiplier.is_theory().into()).unwrap();
-        context.set_value(format!("m{term}_unknown"), ingredient.modifiers[effect].multiplier.is_unknown().into()).unwrap();
-    };    
-
-    {
-        let effect = Effect::Alcohol;
-        let term = "a";
-
-        context.set_value(term.to_string(), ingredient.modifiers[effect].term.inner().into()).unwrap();
-        context.set_value(format!("{term}_known"), ingredient.modifiers[effect].term.is_known().into()).unwrap();
-        context.set_value(format!("{term}_theory"), ingredient.modifiers[effect].term.is_theory().into()).unwrap();
-        context.set_value(format!("{term}_unknown"), ingredient.modifiers[effect].term.is_unknown().into()).unwrap();
-
-        context.set_value(format!("m{term}"), ingredient.modifiers[effect].multiplier.inner().into()).unwrap();
-        context.set_value(format!("m{term}_known"), ingredient.modifiers[effect].multiplier.is_known().into()).unwrap();
-        context.set_value(format!("m{term}_theory"), ingredient.modifiers[effect].multiplier.is_theory().into()).unwrap();
-        context.set_value(format!("m{term}_unknown"), ingredient.modifiers[effect].multiplier.is_unknown().into()).unwrap();
-    };    
-
-    eval_boolean_with_context(filter, &context).unwrap()
-}
\ No newline at end of file
+    use super::*;
+
+    fn assert_value(context: &HashMapContext, name: &str, expected: f64) {
+        let value = context.get_value(name).unwrap().as_number().unwrap();
+        assert!((value - expected).abs() < 1e-9, "{name}: {value} != {expected}");
+    }
+
+    #[test]
+    fn test_character_values() {
+        let mut grimoire = grimoire();
+        grimoire.skills["Herbology"].effectiveness = Theoretical::Known(0.6);
+        grimoire.characters["Tashka"].skills.insert("Herbology".to_string(), 50);
+        let salvia = &grimoire.ingredients["Salvia Oil"];
+
+        let context = ingredient_context("Salvia Oil", salvia, &grimoire.effects, None);
+        assert_value(&context, "dh", 2.4);
+        assert!(context.get_value("lore").is_none());
+
+        let character = OptimizedGrimoire::from((&grimoire.characters["Tashka"], &grimoire));
+        let context = ingredient_context("Salvia Oil", salvia, &grimoire.effects, Some(&character));
+        assert_value(&context, "lore", 1.3);
+        assert_eq!(context.get_value("lore_known"), Some(&Value::Boolean(true)));
+        assert_value(&context, "dh", 2.4 * 1.3 * character.advanced_potion_making_mod);
+        // Multipliers are not raised by lore
+        assert_value(&context, "mdh", salvia.modifiers[Effect::DirectHealing].multiplier.inner());
+    }
+}
@@ -1,6 +1,7 @@
 mod characters;
 mod skills;
 mod ingredients;
+mod query;
 
 use clap::*;
 use grimoire2::grimoire::Grimoire;
@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+
+use clap::*;
+use error_stack::{Result, IntoReport, ResultExt};
+use evalexpr::*;
+use grimoire2::theoretical::Theoretical;
+use indexmap::IndexMap;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum QueryError {
+    #[error("Invalid expression: {0}")]
+    BadExpression(String),
+    #[error("Unknown name {0} in expression: {1}")]
+    UnknownName(String, String),
+}
+
+/// Filter, sorting and columns shared by the `list` subcommands. All of them are evalexpr
+/// expressions evaluated in the context of each listed item
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub filter: Option<String>,
+    pub sort: Option<String>,
+    pub reverse: bool,
+    pub columns: Option<Vec<String>>,
+}
+
+pub fn args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("filter")
+            .help("Only list what this expression is true for. Matching nothing is not an error")
+            .index(1)
+            .required(false)
+            .value_name("filter")
+        )
+        .arg(
+            Arg::new("sort")
+            .help("Sort by the value of this expression, lowest first")
+            .short('s')
+            .long("sort")
+        )
+        .arg(
+            Arg::new("reverse")
+            .help("Sort highest first")
+            .short('r')
+            .long("reverse")
+            .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("columns")
+            .help("Comma separated expressions to show instead of the default columns")
+            .long("columns")
+            .value_delimiter(',')
+        )
+}
+
+impl Query {
+    pub fn from_args(args: &ArgMatches) -> Self {
+        Self {
+            filter: args.get_one::<String>("filter").cloned(),
+            sort: args.get_one::<String>("sort").cloned(),
+            reverse: args.get_flag("reverse"),
+            columns: args.get_many::<String>("columns").map(|x| x.map(|x| x.trim().to_string()).collect()),
+        }
+    }
+
+    /// Refuses expressions that don't parse or use names missing from `sample`, the context of
+    /// any item, so that mistakes are reported even when nothing is listed
+    pub fn check(&self, sample: &HashMapContext) -> Result<(), QueryError> {
+        let expressions = self.filter.iter()
+            .chain(self.sort.iter())
+            .chain(self.columns.iter().flatten());
+
+        for expression in expressions {
+            let tree = build_operator_tree(expression)
+                .into_report()
+                .change_context_lazy(|| QueryError::BadExpression(expression.clone()))?;
+
+            let unknown = tree.iter_read_variable_identifiers()
+                .find(|x| sample.get_value(x).is_none())
+                .map(str::to_string);
+
+            if let Some(name) = unknown {
+                return Err(QueryError::UnknownName(name, expression.clone()).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Items matching the filter, in the requested order. Items that sort the same keep their
+    /// order, also in reverse
+    pub fn select<T>(&self, items: Vec<(T, HashMapContext)>) -> Result<Vec<(T, HashMapContext)>, QueryError> {
+        let mut result = Vec::default();
+
+        for (item, context) in items {
+            let matches = match &self.filter {
+                Some(filter) => eval_boolean_with_context(filter, &context)
+                    .into_report()
+                    .change_context_lazy(|| QueryError::BadExpression(filter.clone()))?,
+                None => true,
+            };
+
+            if matches {
+                result.push((item, context));
+            }
+        }
+
+        if let Some(sort) = &self.sort {
+            let mut keyed = result.into_iter().map(|(item, context)| {
+                let key = eval_with_context(sort, &context)
+                    .into_report()
+                    .change_context_lazy(|| QueryError::BadExpression(sort.clone()))?;
+                Ok((key, (item, context)))
+            }).collect::<Result<Vec<_>, QueryError>>()?;
+
+            match self.reverse {
+                true => keyed.sort_by(|(a, _), (b, _)| compare(b, a)),
+                false => keyed.sort_by(|(a, _), (b, _)| compare(a, b)),
+            }
+            result = keyed.into_iter().map(|(_, x)| x).collect();
+        } else if self.reverse {
+            result.reverse();
+        }
+
+        Ok(result)
+    }
+
+    /// The name followed by the value of every requested column, `None` without `--columns`
+    pub fn row(
+        &self,
+        name: &str,
+        context: &HashMapContext,
+    ) -> Result<Option<IndexMap<String, serde_json::Value>>, QueryError> {
+        let Some(columns) = &self.columns else {
+            return Ok(None);
+        };
+
+        let mut result = IndexMap::default();
+        result.insert("name".to_string(), name.into());
+
+        for column in columns {
+            let value = eval_with_context(column, context)
+                .into_report()
+                .change_context_lazy(|| QueryError::BadExpression(column.clone()))?;
+            result.insert(column.clone(), json(value));
+        }
+
+        Ok(Some(result))
+    }
+}
+
+/// Sets `name` to the value and `name_known`, `name_theory`, `name_unknown` to its kind
+pub fn set_theoretical(context: &mut HashMapContext, name: &str, value: Theoretical<f64>) {
+    context.set_value(name.to_string(), value.inner().into()).unwrap();
+    context.set_value(format!("{name}_known"), value.is_known().into()).unwrap();
+    context.set_value(format!("{name}_theory"), value.is_theory().into()).unwrap();
+    context.set_value(format!("{name}_unknown"), value.is_unknown().into()).unwrap();
+}
+
+pub fn strings<'a>(values: impl IntoIterator<Item = &'a String>) -> Value {
+    let mut values: Vec<&String> = values.into_iter().collect();
+    values.sort();
+    Value::Tuple(values.into_iter().map(|x| x.as_str().into()).collect())
+}
+
+/// Numbers are compared by value, everything else by its text
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a.as_number(), b.as_number()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => text(a).cmp(&text(b)),
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(x) => x.clone(),
+        x => x.to_string(),
+    }
+}
+
+fn json(value: Value) -> serde_json::Value {
+    match value {
+        Value::String(x) => x.into(),
+        Value::Float(x) => x.into(),
+        Value::Int(x) => x.into(),
+        Value::Boolean(x) => x.into(),
+        Value::Tuple(x) => x.into_iter().map(json).collect(),
+        Value::Empty => serde_json::Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<(&'static str, HashMapContext)> {
+        [("Salvia Oil", 2., "Herbology"), ("Troll Sweat", 1., ""), ("Sea Dew Leaves", 2., "Herbology"), ("Bone Dust", 3., "")]
+            .into_iter()
+            .map(|(name, weight, skill)| {
+                let mut context = HashMapContext::new();
+                context.set_value("name".to_string(), name.into()).unwrap();
+                context.set_value("skill".to_string(), skill.into()).unwrap();
+                set_theoretical(&mut context, "weight", Theoretical::Known(weight));
+                (name, context)
+            })
+            .collect()
+    }
+
+    fn names(query: &Query) -> Vec<&'static str> {
+        query.select(items()).unwrap().into_iter().map(|(x, _)| x).collect()
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = |x: &str| Query { filter: Some(x.to_string()), ..Query::default() };
+
+        assert_eq!(names(&filter(r#"skill == "Herbology" && weight > 1"#)), ["Salvia Oil", "Sea Dew Leaves"]);
+        assert_eq!(names(&filter("weight_known && weight >= 3")), ["Bone Dust"]);
+        assert!(names(&filter("weight > 10")).is_empty());
+
+        let report = filter("weight +").select(items()).unwrap_err();
+        assert!(matches!(report.current_context(), QueryError::BadExpression(x) if x == "weight +"));
+
+        // Filters must be true or false
+        assert!(filter("weight").select(items()).is_err());
+    }
+
+    #[test]
+    fn test_sort() {
+        let sort = |x: &str, reverse| Query { sort: Some(x.to_string()), reverse, ..Query::default() };
+
+        // Ties keep the order they are listed in, either way
+        assert_eq!(names(&sort("weight", false)), ["Troll Sweat", "Salvia Oil", "Sea Dew Leaves", "Bone Dust"]);
+        assert_eq!(names(&sort("weight", true)), ["Bone Dust", "Salvia Oil", "Sea Dew Leaves", "Troll Sweat"]);
+        assert_eq!(names(&sort("name", false)), ["Bone Dust", "Salvia Oil", "Sea Dew Leaves", "Troll Sweat"]);
+
+        let reverse = Query { reverse: true, ..Query::default() };
+        assert_eq!(names(&reverse), ["Bone Dust", "Sea Dew Leaves", "Troll Sweat", "Salvia Oil"]);
+    }
+
+    #[test]
+    fn test_columns() {
+        let query = Query { columns: Some(vec!["weight * 2".to_string(), "skill".to_string()]), ..Query::default() };
+        let (name, context) = &items()[0];
+
+        let row = query.row(name, context).unwrap().unwrap();
+        assert_eq!(row.keys().collect::<Vec<_>>(), ["name", "weight * 2", "skill"]);
+        assert_eq!(row["weight * 2"], 4.);
+        assert_eq!(row["skill"], "Herbology");
+
+        assert!(Query::default().row(name, context).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_check_unknown_names() {
+        let (_, sample) = &items()[0];
+        let columns = |x: &[&str]| Query { columns: Some(x.iter().map(|x| x.to_string()).collect()), ..Query::default() };
+
+        assert!(columns(&["name", "weight_theory", "weight / 2"]).check(sample).is_ok());
+
+        let report = columns(&["name", "wieght"]).check(sample).unwrap_err();
+        assert!(matches!(report.current_context(), QueryError::UnknownName(x, y) if x == "wieght" && y == "wieght"));
+
+        let filter = Query { filter: Some("lore > 1".to_string()), ..Query::default() };
+        assert!(matches!(filter.check(sample).unwrap_err().current_context(), QueryError::UnknownName(x, _) if x == "lore"));
+
+        let sort = Query { sort: Some("(weight".to_string()), ..Query::default() };
+        assert!(matches!(sort.check(sample).unwrap_err().current_context(), QueryError::BadExpression(_)));
+    }
+}
@@ -1,43 +1,76 @@
 use clap::*;
 use evalexpr::*;
-use grimoire2::grimoire::{Grimoire, Skill};
+use grimoire2::grimoire::{Grimoire, Skill, SkillCategory};
+use grimoire_serde::theoretical::TheoreticalWrapper;
+use serde::Serialize;
+
+use crate::output::Output;
+use super::query::{self, Query};
+
+#[derive(Serialize)]
+pub struct SkillRow {
+    name: String,
+    effectiveness: String,
+    parent: Option<String>,
+    parent_2: Option<String>,
+    category: SkillCategory,
+    cost: u8,
+}
 
 pub fn command() -> Command {
-    Command::new("skills")
-        .arg(
-            Arg::new("filter")
-            .index(1)
-            .trailing_var_arg(true)
-            .required(false)
-            .value_name("filter")
-        )        
+    query::args(Command::new("skills"))
 }
 
 pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
-    list_skills(grimoire, args.get_one::<String>("filter").cloned())
+    list_skills(grimoire, Query::from_args(args), Output::from_args(args))
 }
 
-pub fn list_skills(grimoire: Grimoire, filter: Option<String>) {
-    let filter_ = |skill: &Skill| {
-        match &filter {
-            Some(x) => filter_skill(&grimoire, skill.clone(), x),
-            None => true
+pub fn list_skills(grimoire: Grimoire, query: Query, output: Output) {
+    let items: Vec<((&String, &Skill), HashMapContext)> = grimoire.skills.iter().map(|(name, skill)| {
+        ((name, skill), skill_context(&grimoire, name, skill))
+    }).collect();
+
+    query.check(&skill_context(&grimoire, "", &Skill::default())).unwrap();
+    let selected = query.select(items).unwrap();
+
+    if query.columns.is_some() {
+        let rows = selected.iter().map(|((name, _), context)| {
+            query.row(name, context).map(Option::unwrap)
+        }).collect::<Result<Vec<_>, _>>().unwrap();
+        return output.print_rows(&rows);
+    }
+
+    let rows: Vec<SkillRow> = selected.into_iter().map(|((name, skill), _)| {
+        SkillRow {
+            name: name.clone(),
+            effectiveness: TheoreticalWrapper::from(skill.effectiveness).to_string(),
+            parent: skill.parent.clone(),
+            parent_2: skill.parent_2.clone(),
+            category: skill.category,
+            cost: skill.cost,
         }
-    };
+    }).collect();
 
-    for (name, skill) in grimoire.skills.iter().filter(|(_, skill)| filter_(skill)) {
-        println!("{}", name);
-    }   
+    output.print_rows(&rows);
 }
 
-fn filter_skill(grimoire: &Grimoire, skill: Skill, filter: &str) -> bool {
-    let grimoire_1 = grimoire.clone();
-    let context = context_map! {
-        "is_child" => Function::new(move |arg| {
-            let arg_str = arg.as_string()?;
-            let result = skill.is_child(&grimoire_1, &arg_str);
-            Ok(result.into())
-        }),
-    }.unwrap();
-    eval_boolean_with_context(filter, &context).unwrap()
-}
\ No newline at end of file
+fn skill_context(grimoire: &Grimoire, name: &str, skill: &Skill) -> HashMapContext {
+    let mut context = HashMapContext::new();
+
+    context.set_value("name".to_string(), name.into()).unwrap();
+    query::set_theoretical(&mut context, "effectiveness", skill.effectiveness);
+    context.set_value("parent".to_string(), skill.parent.clone().unwrap_or_default().into()).unwrap();
+    context.set_value("parent_2".to_string(), skill.parent_2.clone().unwrap_or_default().into()).unwrap();
+    context.set_value("category".to_string(), format!("{:?}", skill.category).into()).unwrap();
+    context.set_value("cost".to_string(), (skill.cost as i64).into()).unwrap();
+
+    let grimoire = grimoire.clone();
+    let skill = skill.clone();
+    context.set_function("is_child".to_string(), Function::new(move |arg| {
+        let arg_str = arg.as_string()?;
+        let result = skill.is_child(&grimoire, &arg_str);
+        Ok(result.into())
+    })).unwrap();
+
+    context
+}
@@ -1,10 +1,10 @@
-use std::io::stdout;
-
 use grimoire2::grimoire::Grimoire;
 use grimoire_serde::grimoire::character::CharacterHumanReadable;
-use serde_yaml::to_writer;
 use clap::*;
-use tracing::info;
+
+use crate::layers::Layers;
+use crate::output::{self, Output};
+use super::Layered;
 
 pub fn command() -> Command {
     Command::new("character")
@@ -16,14 +16,14 @@ pub fn command() -> Command {
         )
 }
 
-pub fn matched_command(mut grimoire: Grimoire, args: &ArgMatches) {
-    match grimoire.characters.remove(args.get_one::<String>("name").unwrap()) {
+pub fn matched_command(mut grimoire: Grimoire, layers: &Layers, args: &ArgMatches) {
+    let name = args.get_one::<String>("name").unwrap();
+
+    match grimoire.characters.remove(name) {
         Some(x) => {
             let hr: CharacterHumanReadable = x.into();
-            to_writer(stdout(), &hr).unwrap();
+            Output::from_args(args).print(&Layered::new(hr, layers, &format!("characters.{name}")));
         },
-        None => {
-            info!("Character not found");
-        }
+        None => output::not_found("Character", name),
     }
 }
\ No newline at end of file
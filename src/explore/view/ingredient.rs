@@ -1,10 +1,11 @@
-use std::io::stdout;
-
-use grimoire2::grimoire::Grimoire;
+use grimoire2::grimoire::{Character, Grimoire};
+use grimoire2::standalone::OptimizedGrimoire;
 use grimoire_serde::grimoire::ingredient::IngredientHumanReadable;
-use serde_yaml::to_writer;
 use clap::*;
-use tracing::info;
+
+use crate::layers::Layers;
+use crate::output::{self, Output};
+use super::Layered;
 
 pub fn command() -> Command {
     Command::new("ingredient")
@@ -16,14 +17,27 @@ pub fn command() -> Command {
         )
 }
 
-pub fn matched_command(mut grimoire: Grimoire, args: &ArgMatches) {
-    match grimoire.ingredients.remove(args.get_one::<String>("name").unwrap()) {
+pub fn matched_command(mut grimoire: Grimoire, layers: &Layers, args: &ArgMatches) {
+    let name = resolve(&grimoire, args.get_one::<String>("name").unwrap());
+
+    match grimoire.ingredients.remove(&name) {
         Some(x) => {
-            let hr: IngredientHumanReadable = x.into();
-            to_writer(stdout(), &hr).unwrap();
+            let hr = IngredientHumanReadable::new(x, &grimoire.effects);
+            Output::from_args(args).print(&Layered::new(hr, layers, &format!("ingredients.{name}")));
         },
-        None => {
-            info!("Ingredient not found");
-        }
+        None => output::not_found("Ingredient", &name),
+    }
+}
+
+/// Name of the ingredient called `name` or aliased so, suggesting the closest one otherwise
+fn resolve(grimoire: &Grimoire, name: &str) -> String {
+    let optimized = OptimizedGrimoire::from((&Character::default(), grimoire));
+    let ingredients = &optimized.ingredients;
+
+    match ingredients.by_name(name) {
+        Ok(x) => ingredients.name(x).to_string(),
+        Err(_) => output::not_found_suggest(
+            "Ingredient", name, ingredients.by_fuzzy_name(name).ok().map(|x| ingredients.name(x)),
+        ),
     }
 }
\ No newline at end of file
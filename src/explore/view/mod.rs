@@ -4,6 +4,25 @@ pub mod ingredient;
 
 use clap::{Command, ArgMatches};
 use grimoire2::grimoire::Grimoire;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::layers::Layers;
+
+/// Viewed entry with the overlays that set its values
+#[derive(Serialize)]
+pub struct Layered<T> {
+    #[serde(flatten)]
+    value: T,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    layers: IndexMap<String, String>,
+}
+
+impl<T> Layered<T> {
+    pub fn new(value: T, layers: &Layers, prefix: &str) -> Self {
+        Self { value, layers: layers.origins_of(prefix) }
+    }
+}
 
 pub fn command() -> Command {
     Command::new("view")
@@ -13,11 +32,11 @@ pub fn command() -> Command {
         .subcommand_required(true)
 }
 
-pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+pub fn matched_command(grimoire: Grimoire, layers: &Layers, args: &ArgMatches) {
     match args.subcommand() {
-        Some(("character", args)) => character::matched_command(grimoire, args),
-        Some(("skill", args)) => skill::matched_command(grimoire, args),
-        Some(("ingredient", args)) => ingredient::matched_command(grimoire, args),
+        Some(("character", args)) => character::matched_command(grimoire, layers, args),
+        Some(("skill", args)) => skill::matched_command(grimoire, layers, args),
+        Some(("ingredient", args)) => ingredient::matched_command(grimoire, layers, args),
         None | Some(_) => {}
     }
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::io::{stdout, Write};
+
+use clap::*;
+use error_stack::{Result, IntoReport, ResultExt};
+use grimoire2::grimoire::Grimoire;
+use grimoire_serde::theoretical::TheoreticalWrapper;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Could not write output")]
+    Output,
+}
+
+pub fn command() -> Command {
+    Command::new("export")
+        .before_help("Export ingredients as a CSV table with columns name, skill, weight, dh, mdh, \
+                      ..., ma (see `import`)")
+        .arg(
+            Arg::new("to")
+                .short('t')
+                .long("to")
+                .help("CSV file; standard output if omitted")
+        )
+}
+
+pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+    let writer: Box<dyn Write> = match args.get_one::<String>("to") {
+        Some(x) => Box::new(
+            File::create(x)
+                .into_report()
+                .change_context(ExportError::Output)
+                .unwrap()
+        ),
+        None => Box::new(stdout()),
+    };
+
+    export(&grimoire, csv::Writer::from_writer(writer)).unwrap();
+}
+
+pub fn export<W: Write>(grimoire: &Grimoire, mut writer: csv::Writer<W>) -> Result<(), ExportError> {
+    let codes = grimoire.effects.modifier_codes();
+    let mut header = vec!["name", "skill", "weight"];
+    header.extend(codes.iter().map(|x| x.0.as_str()));
+
+    writer.write_record(&header)
+        .into_report()
+        .change_context(ExportError::Output)?;
+
+    for (name, ingredient) in &grimoire.ingredients {
+        let mut record = vec![
+            name.clone(),
+            ingredient.skill.clone().unwrap_or_default(),
+            TheoreticalWrapper::from(ingredient.weight).to_string(),
+        ];
+
+        record.extend(codes.iter().map(|(_, effect, multiplier)| {
+            let modifier = &ingredient.modifiers[*effect];
+            let value = if *multiplier { modifier.multiplier } else { modifier.term };
+            TheoreticalWrapper::from(value).to_string()
+        }));
+
+        writer.write_record(&record)
+            .into_report()
+            .change_context(ExportError::Output)?;
+    }
+
+    writer.flush()
+        .into_report()
+        .change_context(ExportError::Output)
+}
@@ -1,108 +1,430 @@
-use std::{fs::File, io::stdout};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{stdin, stdout, Read, Write};
 use std::path::Path;
 
 use serde::Serialize;
-use serde::de::DeserializeOwned;
+use serde::de::{DeserializeOwned, IgnoredAny};
 use error_stack::{Result, IntoReport, ResultExt, Report};
+use grimoire2::grimoire::Grimoire;
+use grimoire2::grimoire::versioned::GrimoireVersioned;
+use grimoire2::migrate::{migrate, MigrationReport, SchemaProbe};
+use grimoire_sqlite::GrimoireStore;
+
+
+/// Path standing for standard input when loading and standard output when saving
+pub const STDIO: &str = "-";
 
 
 #[derive(Debug, thiserror::Error)]
 pub enum FSOperationError {
-    #[error("File IO error")]
-    FileIO,
+    #[error("Could not access {0}")]
+    FileIO(String),
     #[error("Unsupported file extension: {0}")]
     FileExtension(String),
     #[error("Files without extension are not supported")]
     NullExtension,
     #[error("Bad file name")]
     BadFileName,
+    #[error("Unsupported grimoire schema")]
+    Schema,
+    #[error("Could not parse {path} as {format}")]
+    Parse { path: String, format: Format },
+    #[error("Could not write {format}")]
+    Serialize { format: Format },
+    #[error("Could not use database {0}")]
+    Database(String),
+    #[error("Update file {0} does not fit the grimoire")]
+    BadUpdate(String),
+    #[error("Only the last overlay is written while overlays are in use, not {0}")]
+    NotLastOverlay(String),
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    Ron,
 }
 
+impl Format {
+    /// Format given by the file extension, `None` for standard input and output
+    pub fn from_path(path: &Path) -> Result<Option<Format>, FSOperationError> {
+        if path == Path::new(STDIO) {
+            return Ok(None);
+        }
 
+        match path.extension() {
+            Some(x) => match x.to_str().ok_or(Report::new(FSOperationError::BadFileName))? {
+                "yaml" | "yml" => Ok(Some(Format::Yaml)),
+                "json" => Ok(Some(Format::Json)),
+                "toml" => Ok(Some(Format::Toml)),
+                "ron" => Ok(Some(Format::Ron)),
+                other => Err(Report::new(FSOperationError::FileExtension(other.to_string())))
+            },
+            None => Err(Report::new(FSOperationError::NullExtension))
+        }
+    }
+
+    /// Guesses the format of a document. YAML is a superset of JSON and accepts most plain
+    /// text, so it is tried last
+    pub fn sniff(content: &str) -> Format {
+        if serde_json::from_str::<IgnoredAny>(content).is_ok() {
+            Format::Json
+        } else if ron::from_str::<IgnoredAny>(content).is_ok() {
+            Format::Ron
+        } else if toml::from_str::<toml::Value>(content).is_ok() {
+            Format::Toml
+        } else {
+            Format::Yaml
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Json => write!(f, "JSON"),
+            Format::Yaml => write!(f, "YAML"),
+            Format::Toml => write!(f, "TOML"),
+            Format::Ron => write!(f, "RON"),
+        }
+    }
+}
+
+
+/// Saves in the format given by the extension, `-` prints YAML to the standard output.
+/// Files are written to a temporary file first and then renamed, so that a failed write
+/// never leaves a truncated grimoire behind
 pub fn save(path: &Path, value: &impl Serialize) -> Result<(), FSOperationError> {
-    match path.extension() {
-        Some(x) => match x.to_str().ok_or(Report::new(FSOperationError::BadFileName))? {
-            "yaml" => save_yaml(path, value),
-            "json" => save_json(path, value),
-            other => Err(Report::new(FSOperationError::FileExtension(other.to_string())))
-        },
-        None => Err(Report::new(FSOperationError::NullExtension))
+    let format = Format::from_path(path)?;
+    let content = serialize(value, format.unwrap_or(Format::Yaml))?;
+
+    match format {
+        Some(_) => write_atomically(path, &content),
+        None => stdout().write_all(content.as_bytes())
+            .into_report()
+            .change_context(FSOperationError::FileIO(describe(path))),
     }
 }
 
 
+/// Loads in the format given by the extension, `-` reads the standard input and guesses
+/// the format
 pub fn load<T: DeserializeOwned>(path: &Path) -> Result<T, FSOperationError> {
-    match path.extension() {
-        Some(x) => match x.to_str().ok_or(Report::new(FSOperationError::BadFileName))? {
-            "yaml" => load_yaml(path),
-            "json" => load_json(path),
-            other => Err(Report::new(FSOperationError::FileExtension(other.to_string())))
-        },
-        None => Err(Report::new(FSOperationError::NullExtension))
-    }
+    let (content, format) = read(path)?;
+    parse(path, &content, format)
 }
 
 
-pub fn print_yaml(value: &impl Serialize) -> Result<(), FSOperationError> {
-    let writer = stdout();
+/// Loads a grimoire of any supported schema version, upgrading it to the current one.
+/// `sqlite://path` loads it from a database, which is always up to date
+pub fn load_grimoire(path: &Path) -> Result<(Grimoire, MigrationReport), FSOperationError> {
+    if let Some(database) = database_path(path) {
+        let grimoire = open_store(database)?
+            .load()
+            .into_report()
+            .change_context_lazy(|| FSOperationError::Database(database.to_string()))?;
+        return Ok((grimoire, MigrationReport::default()));
+    }
+
+    let (content, format) = read(path)?;
+    let probe: SchemaProbe = parse(path, &content, format)?;
 
-    serde_json::to_writer_pretty(writer, value)
+    probe.version()
         .into_report()
-        .change_context(FSOperationError::FileIO)?;
+        .change_context(FSOperationError::Schema)
+        .attach_printable_lazy(|| describe(path))?;
+
+    let grimoire: GrimoireVersioned = parse(path, &content, format)?;
 
-    Ok(())
+    Ok(migrate(grimoire))
 }
 
 
-pub fn save_json(path: &Path, value: &impl Serialize) -> Result<(), FSOperationError> {
-    let writer = create_file(path)?;
-    
-    serde_json::to_writer_pretty(writer, value)
-        .into_report()
-        .change_context(FSOperationError::FileIO)?;
+/// Saves a grimoire with the current schema version, to a database for `sqlite://path`
+pub fn save_grimoire(path: &Path, grimoire: &Grimoire) -> Result<(), FSOperationError> {
+    match database_path(path) {
+        Some(database) => open_store(database)?
+            .save(grimoire)
+            .into_report()
+            .change_context_lazy(|| FSOperationError::Database(database.to_string())),
+        None => save(path, &GrimoireVersioned::from(grimoire.clone())),
+    }
+}
+
 
-    Ok(())
+fn database_path(path: &Path) -> Option<&str> {
+    path.to_str().and_then(GrimoireStore::path_from_url)
 }
 
 
-pub fn save_yaml(path: &Path, value: &impl Serialize) -> Result<(), FSOperationError> {
-    let writer = create_file(path)?;
-    
-    serde_json::to_writer_pretty(writer, value)
+fn open_store(database: &str) -> Result<GrimoireStore, FSOperationError> {
+    GrimoireStore::open(database)
         .into_report()
-        .change_context(FSOperationError::FileIO)?;
+        .change_context_lazy(|| FSOperationError::Database(database.to_string()))
+}
+
+
+/// YAML, TOML and RON can't all represent nested enums natively, so values go through the JSON
+/// data model first, where enums are maps with a single key
+pub fn serialize(value: &impl Serialize, format: Format) -> Result<String, FSOperationError> {
+    let error = || FSOperationError::Serialize { format };
 
-    Ok(())
+    match format {
+        Format::Json => serde_json::to_string_pretty(value)
+            .into_report()
+            .change_context_lazy(error),
+        Format::Yaml => serde_json::to_value(value)
+            .into_report()
+            .change_context_lazy(error)
+            .and_then(|x| serde_yaml::to_string(&x).into_report().change_context_lazy(error)),
+        // TOML has no null, and tables have to come after plain values, which `toml::Value`
+        // takes care of
+        Format::Toml => serde_json::to_value(value)
+            .into_report()
+            .change_context_lazy(error)
+            .and_then(|x| toml::Value::try_from(without_nulls(x))
+                .and_then(|x| toml::to_string_pretty(&x))
+                .into_report()
+                .change_context_lazy(error)
+            ),
+        Format::Ron => serde_json::to_value(value)
+            .into_report()
+            .change_context_lazy(error)
+            .and_then(|x| ron::ser::to_string_pretty(&x, ron::ser::PrettyConfig::default())
+                .into_report()
+                .change_context_lazy(error)
+            ),
+    }
 }
 
 
-pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<T, FSOperationError> {
-    let reader = open_file(path)?;
+fn without_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(x) => serde_json::Value::Object(
+            x.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, without_nulls(v)))
+                .collect()
+        ),
+        serde_json::Value::Array(x) => serde_json::Value::Array(x.into_iter().map(without_nulls).collect()),
+        x => x,
+    }
+}
 
-    serde_json::from_reader(reader)
-        .into_report()
-        .change_context(FSOperationError::FileIO)
+
+/// Parse errors name the file, the location of the error is part of the underlying error.
+/// Documents are read directly first, to keep YAML tags such as `!? 0.5`, and then through the
+/// JSON data model, where enums are maps with a single key as written by `save`. If both fail,
+/// the error of the direct attempt is kept, since only it knows where in the file it happened
+pub fn parse<T: DeserializeOwned>(path: &Path, content: &str, format: Format) -> Result<T, FSOperationError> {
+    let error = || FSOperationError::Parse { path: describe(path), format };
+
+    let (direct, value) = match format {
+        Format::Json => return serde_json::from_str(content)
+            .into_report()
+            .change_context_lazy(error),
+        Format::Yaml => (
+            serde_yaml::from_str(content).into_report().change_context_lazy(error),
+            serde_yaml::from_str::<serde_json::Value>(content).ok(),
+        ),
+        Format::Toml => (
+            toml::from_str(content).into_report().change_context_lazy(error),
+            toml::from_str::<serde_json::Value>(content).ok(),
+        ),
+        Format::Ron => (
+            ron::from_str(content).into_report().change_context_lazy(error),
+            ron::from_str::<serde_json::Value>(content).ok(),
+        ),
+    };
+
+    match direct {
+        Ok(x) => Ok(x),
+        Err(report) => value
+            .and_then(|x| serde_json::from_value(x).ok())
+            .ok_or(report),
+    }
 }
 
 
-pub fn load_yaml<T: DeserializeOwned>(path: &Path) -> Result<T, FSOperationError> {
-    let reader = open_file(path)?;
-    
-    serde_yaml::from_reader(reader)
+fn read(path: &Path) -> Result<(String, Format), FSOperationError> {
+    let format = Format::from_path(path);
+    let mut content = String::default();
+
+    let mut reader: Box<dyn Read> = match format {
+        Ok(None) => Box::new(stdin()),
+        _ => Box::new(open_file(path)?),
+    };
+
+    reader.read_to_string(&mut content)
         .into_report()
-        .change_context(FSOperationError::FileIO)
+        .change_context(FSOperationError::FileIO(describe(path)))?;
+
+    // Unknown extensions are guessed like the standard input
+    let format = format.ok().flatten().unwrap_or_else(|| Format::sniff(&content));
+
+    Ok((content, format))
+}
+
+
+fn write_atomically(path: &Path, content: &str) -> Result<(), FSOperationError> {
+    let file_name = path.file_name()
+        .ok_or(Report::new(FSOperationError::BadFileName))?
+        .to_string_lossy();
+    let temporary = path.with_file_name(format!(".{file_name}.tmp"));
+
+    let result = create_file(&temporary)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())
+                .and_then(|_| file.sync_all())
+                .into_report()
+                .change_context(FSOperationError::FileIO(describe(&temporary)))
+        })
+        .and_then(|_| {
+            std::fs::rename(&temporary, path)
+                .into_report()
+                .change_context(FSOperationError::FileIO(describe(path)))
+        });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+    }
+
+    result
+}
+
+
+fn describe(path: &Path) -> String {
+    match path == Path::new(STDIO) {
+        true => "standard input/output".to_string(),
+        false => path.display().to_string(),
+    }
 }
 
 
 pub fn open_file(path: &Path) -> Result<File, FSOperationError> {
     File::open(path)
         .into_report()
-        .change_context(FSOperationError::FileIO)
+        .change_context(FSOperationError::FileIO(describe(path)))
 }
 
 
 pub fn create_file(path: &Path) -> Result<File, FSOperationError> {
     File::create(path)
         .into_report()
-        .change_context(FSOperationError::FileIO)
-}
\ No newline at end of file
+        .change_context(FSOperationError::FileIO(describe(path)))
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::PathBuf;
+
+    use grimoire2::effect::Effect;
+    use grimoire2::grimoire::{Character, Ingredient, Skill};
+    use grimoire2::theoretical::Theoretical;
+    use indexmap::IndexMap;
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// Empty directory of its own for a test, under the temporary directory of the system
+    pub fn temp_dir(name: &str) -> PathBuf {
+        let result = std::env::temp_dir().join(format!("alrust-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&result);
+        std::fs::create_dir_all(&result).unwrap();
+        result
+    }
+
+    pub fn grimoire() -> Grimoire {
+        let mut salvia = Ingredient {
+            skill: Some("Herbology".to_string()),
+            weight: Theoretical::Theory(0.5),
+            ..Ingredient::default()
+        };
+        salvia.modifiers[Effect::DirectHealing].term = Theoretical::Known(2.4);
+        salvia.modifiers[Effect::DirectHealing].multiplier = Theoretical::Theory(1.2);
+
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Salvia Oil".to_string(), salvia);
+        grimoire.skills.insert("Herbology".to_string(), Skill::new(Theoretical::Unknown, None, None));
+        grimoire.characters.insert("Tashka".to_string(), Character::default());
+        grimoire
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = temp_dir("round-trip");
+
+        for extension in ["json", "yaml", "toml", "ron"] {
+            let path = dir.join(format!("grimoire.{extension}"));
+            save_grimoire(&path, &grimoire()).unwrap();
+
+            let (loaded, report) = load_grimoire(&path).unwrap();
+            assert_eq!(loaded, grimoire(), "{extension}");
+            assert!(report.is_empty());
+
+            let content = std::fs::read_to_string(&path).unwrap();
+            let format = Format::from_path(&path).unwrap().unwrap();
+            assert_eq!(Format::sniff(&content), format, "{extension}");
+        }
+
+        assert!(std::fs::read_dir(&dir).unwrap().all(|x| !x.unwrap().file_name().to_string_lossy().ends_with(".tmp")));
+    }
+
+    #[test]
+    fn test_from_path() {
+        let format = |x: &str| Format::from_path(Path::new(x));
+
+        assert_eq!(format("grimoire.yml").unwrap(), Some(Format::Yaml));
+        assert_eq!(format("grimoire.ron").unwrap(), Some(Format::Ron));
+        assert_eq!(format(STDIO).unwrap(), None);
+        assert!(matches!(format("grimoire.txt").unwrap_err().current_context(), FSOperationError::FileExtension(_)));
+        assert!(matches!(format("grimoire").unwrap_err().current_context(), FSOperationError::NullExtension));
+        assert_eq!(describe(Path::new(STDIO)), "standard input/output");
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(Format::sniff(r#"{"mix": {"Salvia Oil": 2}}"#), Format::Json);
+        assert_eq!(Format::sniff(r#"(mix: {"Salvia Oil": 2})"#), Format::Ron);
+        assert_eq!(Format::sniff("[mix]\n\"Salvia Oil\" = 2\n"), Format::Toml);
+        assert_eq!(Format::sniff("mix:\n  Salvia Oil: 2\n"), Format::Yaml);
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Mix {
+        mix: IndexMap<String, u64>,
+    }
+
+    #[test]
+    fn test_parse_keeps_location() {
+        let path = Path::new("mix.yaml");
+
+        let parsed: Mix = parse(path, "mix:\n  Salvia Oil: 2\n", Format::Yaml).unwrap();
+        assert_eq!(parsed.mix["Salvia Oil"], 2);
+
+        let report = parse::<Mix>(path, "mix:\n  Salvia Oil: two\n", Format::Yaml).unwrap_err();
+        assert!(format!("{report:?}").contains("line 2"), "{report:?}");
+
+        let report = parse::<Mix>(path, "[mix]\n\"Salvia Oil\" = \"two\"\n", Format::Toml).unwrap_err();
+        assert!(format!("{report:?}").contains("line 2"), "{report:?}");
+    }
+
+    #[test]
+    fn test_write_atomically_cleans_up() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("grimoire.json");
+
+        // Renaming over a directory fails after the temporary file is written
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("keep"), "").unwrap();
+
+        assert!(write_atomically(&path, "{}").is_err());
+        assert!(!dir.join(".grimoire.json.tmp").exists());
+        assert!(path.join("keep").exists());
+    }
+}
@@ -0,0 +1,250 @@
+use std::io::{stdin, Write};
+use std::path::Path;
+
+use clap::*;
+use error_stack::{Report, Result, IntoReport, ResultExt};
+use grimoire2::effect::Effects;
+use grimoire2::grimoire::{Grimoire, Ingredient};
+use grimoire2::modify::GrimoireUpdate;
+use grimoire2::modify::command::Commands;
+use grimoire2::modify::ingredient::IngredientUpdate;
+use grimoire2::theoretical::Theoretical;
+use grimoire_serde::theoretical::TheoreticalWrapper;
+use thiserror::Error;
+
+use crate::layers::Layers;
+use crate::update::check_grimoire;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("Could not read CSV file")]
+    Read,
+    #[error("CSV file has no `name` column")]
+    NoNameColumn,
+    #[error("Bad value in row {row}, column {column}: {value}")]
+    BadValue { row: usize, column: String, value: String },
+    #[error("Imported grimoire is invalid")]
+    Invalid,
+    #[error("Could not save grimoire")]
+    Save,
+    #[error("No output file given")]
+    NoOutput,
+}
+
+pub fn command() -> Command {
+    Command::new("import")
+        .before_help("Import ingredients from a CSV table with columns name, skill, weight, dh, mdh, \
+                      ..., ma. Empty cells leave values as they are, `??` makes them unknown and \
+                      `!? <value>` theoretical")
+        .arg(
+            Arg::new("csv")
+                .index(1)
+                .required(true)
+                .help("CSV file")
+        )
+        .arg(
+            Arg::new("to")
+                .short('t')
+                .long("to")
+                .help("Where to save the grimoire; the last overlay if omitted")
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Only show the changes")
+        )
+        .arg(
+            Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .action(ArgAction::SetTrue)
+                .help("Don't ask for confirmation")
+        )
+}
+
+pub fn matched_command(layers: &Layers, args: &ArgMatches) {
+    let grimoire = layers.grimoire();
+
+    let reader = csv::Reader::from_path(args.get_one::<String>("csv").unwrap())
+        .into_report()
+        .change_context(ImportError::Read)
+        .unwrap();
+
+    let update = import(&grimoire, reader).unwrap();
+
+    let mut updated = grimoire.clone();
+    update.update(&mut updated);
+
+    let preview = preview(&grimoire, &updated);
+
+    if preview.is_empty() {
+        println!("Nothing to import");
+        return;
+    }
+
+    for line in &preview {
+        println!("{line}");
+    }
+
+    if args.get_flag("dry-run") {
+        return;
+    }
+
+    let to = layers.target(args.get_one::<String>("to").map(Path::new))
+        .ok_or(Report::new(ImportError::NoOutput))
+        .unwrap();
+
+    if !(args.get_flag("yes") || confirm()) {
+        return;
+    }
+
+    check_grimoire(&updated).change_context(ImportError::Invalid).unwrap();
+
+    layers.save(to, &updated)
+        .change_context(ImportError::Save)
+        .unwrap();
+}
+
+/// Turns every row of the table into an update of the ingredient with the same name
+pub fn import<R: std::io::Read>(
+    grimoire: &Grimoire,
+    mut reader: csv::Reader<R>,
+) -> Result<GrimoireUpdate, ImportError> {
+    let header: Vec<String> = reader.headers()
+        .into_report()
+        .change_context(ImportError::Read)?
+        .iter()
+        .map(|x| x.trim().to_string())
+        .collect();
+
+    let name_column = header.iter().position(|x| x == "name")
+        .ok_or(Report::new(ImportError::NoNameColumn))?;
+
+    let mut result = GrimoireUpdate::default();
+
+    for (i, record) in reader.records().enumerate() {
+        let record = record.into_report().change_context(ImportError::Read)?;
+        let row = i + 2;
+        let name = record.get(name_column).unwrap_or_default().trim();
+
+        if name.is_empty() {
+            continue;
+        }
+
+        let old = grimoire.ingredients.get(name).cloned().unwrap_or_default();
+        let mut new = old.clone();
+
+        for (column, value) in header.iter().zip(record.iter()) {
+            let value = value.trim();
+
+            if value.is_empty() {
+                continue;
+            }
+
+            let bad_value = || ImportError::BadValue {
+                row,
+                column: column.clone(),
+                value: value.to_string(),
+            };
+
+            match column.as_str() {
+                "name" => {},
+                "skill" => new.skill = Some(value.to_string()),
+                "weight" => new.weight = match value.to_lowercase().as_str() {
+                    "true" | "yes" => Theoretical::Known(1.),
+                    "false" | "no" => Theoretical::Known(0.),
+                    _ => value.parse::<TheoreticalWrapper>()
+                        .map_err(|_| Report::new(bad_value()))?
+                        .into(),
+                },
+                code => {
+                    let Some((effect, multiplier)) = grimoire.effects.modifier(code) else {
+                        continue;
+                    };
+                    let value: TheoreticalWrapper = value.parse()
+                        .map_err(|_| Report::new(bad_value()))?;
+
+                    if multiplier {
+                        new.modifiers[effect].multiplier = value.into();
+                    } else {
+                        new.modifiers[effect].term = value.into();
+                    }
+                },
+            }
+        }
+
+        let update = IngredientUpdate::diff(&old, &new);
+
+        if !update.is_empty() || !grimoire.ingredients.contains_key(name) {
+            result.ingredient(name, update);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Human-readable list of the ingredient changes between two grimoires
+pub fn preview(old: &Grimoire, new: &Grimoire) -> Vec<String> {
+    let mut result = Vec::default();
+
+    for (name, ingredient) in &new.ingredients {
+        match old.ingredients.get(name) {
+            None => result.push(format!("+ {name}")),
+            Some(x) if x != ingredient => {
+                result.push(format!("~ {name}"));
+                result.extend(changes(x, ingredient, &new.effects).into_iter().map(|x| format!("    {x}")));
+            },
+            Some(_) => {},
+        }
+    }
+
+    result
+}
+
+fn changes(old: &Ingredient, new: &Ingredient, effects: &Effects) -> Vec<String> {
+    let mut result = Vec::default();
+
+    if old.skill != new.skill {
+        result.push(format!(
+            "skill: {} -> {}",
+            old.skill.as_deref().unwrap_or("-"),
+            new.skill.as_deref().unwrap_or("-"),
+        ));
+    }
+
+    if old.weight != new.weight {
+        result.push(format!(
+            "weight: {} -> {}",
+            TheoreticalWrapper::from(old.weight),
+            TheoreticalWrapper::from(new.weight),
+        ));
+    }
+
+    for (code, effect, multiplier) in effects.modifier_codes() {
+        let (old, new) = match multiplier {
+            true => (old.modifiers[effect].multiplier, new.modifiers[effect].multiplier),
+            false => (old.modifiers[effect].term, new.modifiers[effect].term),
+        };
+
+        if old != new {
+            result.push(format!(
+                "{code}: {} -> {}",
+                TheoreticalWrapper::from(old),
+                TheoreticalWrapper::from(new),
+            ));
+        }
+    }
+
+    result
+}
+
+fn confirm() -> bool {
+    print!("Apply these changes? [y/N] ");
+    std::io::stdout().flush().unwrap();
+
+    let mut answer = String::default();
+    stdin().read_line(&mut answer).unwrap();
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
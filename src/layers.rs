@@ -0,0 +1,240 @@
+use std::path::{Path, PathBuf};
+
+use clap::*;
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use grimoire2::grimoire::Grimoire;
+use grimoire2::migrate::MigrationReport;
+use grimoire2::modify::GrimoireUpdate;
+use grimoire2::modify::changes::diff_changes;
+use grimoire2::modify::command::Commands;
+use grimoire_serde::modify::GrimoireUpdateSerializable;
+use indexmap::IndexMap;
+
+use crate::fs::{load, load_grimoire, save, save_grimoire, FSOperationError};
+
+/// Key of `origins_of` for entries added as a whole by an overlay
+pub const WHOLE_ENTRY: &str = "*";
+
+pub fn arg() -> Arg {
+    Arg::new("overlay")
+        .long("overlay")
+        .global(true)
+        .action(ArgAction::Append)
+        .env("ALRUST_OVERLAY")
+        .help("Update file applied on top of the grimoire, may be repeated. Changes are saved to \
+               the last one")
+}
+
+/// Update file applied on top of the grimoire
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    pub path: PathBuf,
+    /// Contents of the file, with modifier codes looked up in the effects of the layers below
+    pub changes: GrimoireUpdate,
+}
+
+/// A base grimoire, usually shared, with personal overlays on top of it. Every overlay shadows
+/// the layers below it field by field
+#[derive(Debug, Clone)]
+pub struct Layers {
+    pub base: Grimoire,
+    pub overlays: Vec<Overlay>,
+}
+
+impl Layers {
+    /// Overlays that don't exist yet are empty, so that the first save creates them
+    pub fn load(base: &Path, overlays: &[PathBuf]) -> Result<(Self, MigrationReport), FSOperationError> {
+        let (base, report) = load_grimoire(base)?;
+        let mut below = base.clone();
+
+        let overlays = overlays.iter().map(|path| {
+            let update: GrimoireUpdateSerializable = match path.exists() {
+                true => load(path)?,
+                false => GrimoireUpdateSerializable::default(),
+            };
+            let changes = update.to_update(&below.effects)
+                .into_report()
+                .change_context_lazy(|| FSOperationError::BadUpdate(path.display().to_string()))?;
+            changes.update(&mut below);
+
+            Ok(Overlay { path: path.clone(), changes })
+        }).collect::<Result<Vec<Overlay>, FSOperationError>>()?;
+
+        Ok((Self { base, overlays }, report))
+    }
+
+    /// The grimoire with every overlay applied
+    pub fn grimoire(&self) -> Grimoire {
+        self.compose(self.overlays.len())
+    }
+
+    /// The base with the first `count` overlays applied
+    fn compose(&self, count: usize) -> Grimoire {
+        let mut result = self.base.clone();
+        self.overlays[..count].iter().for_each(|x| x.changes.update(&mut result));
+        result
+    }
+
+    /// Where `to` is missing, changes go to the last overlay
+    pub fn target<'a>(&'a self, to: Option<&'a Path>) -> Option<&'a Path> {
+        to.or(self.overlays.last().map(|x| x.path.as_path()))
+    }
+
+    /// Saves the whole grimoire without overlays. With overlays, only what differs from the
+    /// layers below the last overlay is saved to it, in the format of an update file, and any
+    /// other `to` is refused so that the base is never overwritten
+    pub fn save(&self, to: &Path, grimoire: &Grimoire) -> Result<(), FSOperationError> {
+        let Some(last) = self.overlays.last() else {
+            return save_grimoire(to, grimoire);
+        };
+
+        if !same_file(to, &last.path) {
+            return Err(Report::new(FSOperationError::NotLastOverlay(to.display().to_string())));
+        }
+
+        save(to, &GrimoireUpdateSerializable::diff(&self.compose(self.overlays.len() - 1), grimoire))
+    }
+
+    /// Overlay that last changed each field of the entry at `prefix`, such as
+    /// `ingredients.Salvia Oil`, keyed by the rest of the field path. Fields from the base are
+    /// left out
+    pub fn origins_of(&self, prefix: &str) -> IndexMap<String, String> {
+        let mut result = IndexMap::default();
+        let mut below = self.base.clone();
+
+        for overlay in &self.overlays {
+            let mut above = below.clone();
+            overlay.changes.update(&mut above);
+
+            for change in diff_changes(&below, &above) {
+                let field = match change.path.strip_prefix(prefix) {
+                    Some("") => WHOLE_ENTRY,
+                    Some(x) => match x.strip_prefix('.') {
+                        Some(x) => x,
+                        None => continue,
+                    },
+                    None => continue,
+                };
+
+                result.insert(field.to_string(), overlay.path.display().to_string());
+            }
+
+            below = above;
+        }
+
+        result
+    }
+}
+
+/// Whether both paths lead to the same file, which may not exist yet
+fn same_file(a: &Path, b: &Path) -> bool {
+    let resolve = |x: &Path| {
+        let parent = match x.parent() {
+            Some(x) if !x.as_os_str().is_empty() => x,
+            _ => Path::new("."),
+        };
+        parent.canonicalize().ok().zip(x.file_name().map(|x| x.to_owned()))
+    };
+
+    a == b || matches!((resolve(a), resolve(b)), (Some(a), Some(b)) if a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::theoretical::Theoretical;
+
+    use crate::fs::tests::{grimoire, temp_dir};
+
+    use super::*;
+
+    /// Saves `change` of the composed grimoire to the last of `overlays`
+    fn overlay(base: &Path, overlays: &[PathBuf], change: impl FnOnce(&mut Grimoire)) {
+        let (layers, _) = Layers::load(base, overlays).unwrap();
+        let mut grimoire = layers.grimoire();
+        change(&mut grimoire);
+        layers.save(overlays.last().unwrap(), &grimoire).unwrap();
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let dir = temp_dir("layers-shadowing");
+        let base = dir.join("base.json");
+        let first = dir.join("first.yaml");
+        let second = dir.join("second.yaml");
+        save_grimoire(&base, &grimoire()).unwrap();
+
+        overlay(&base, std::slice::from_ref(&first), |x| {
+            let salvia = &mut x.ingredients["Salvia Oil"];
+            salvia.weight = Theoretical::Known(0.4);
+            salvia.modifiers[Effect::DirectHealing].term = Theoretical::Known(3.);
+        });
+        overlay(&base, &[first.clone(), second.clone()], |x| {
+            x.ingredients["Salvia Oil"].weight = Theoretical::Known(0.3);
+        });
+
+        let (layers, _) = Layers::load(&base, &[first.clone(), second.clone()]).unwrap();
+        let salvia = &layers.grimoire().ingredients["Salvia Oil"];
+        assert_eq!(salvia.weight, Theoretical::Known(0.3));
+        assert_eq!(salvia.modifiers[Effect::DirectHealing].term, Theoretical::Known(3.));
+        assert_eq!(salvia.modifiers[Effect::DirectHealing].multiplier, Theoretical::Theory(1.2));
+        assert_eq!(salvia.skill.as_deref(), Some("Herbology"));
+
+        let origins = layers.origins_of("ingredients.Salvia Oil");
+        assert_eq!(origins["weight"], second.display().to_string());
+        assert_eq!(origins["DirectHealing.term"], first.display().to_string());
+        assert_eq!(origins.len(), 2, "{origins:?}");
+
+        // The base is never written
+        assert_eq!(load_grimoire(&base).unwrap().0, grimoire());
+    }
+
+    #[test]
+    fn test_not_last_overlay() {
+        let dir = temp_dir("layers-not-last");
+        let base = dir.join("base.json");
+        let first = dir.join("first.yaml");
+        let second = dir.join("second.yaml");
+        save_grimoire(&base, &grimoire()).unwrap();
+        overlay(&base, std::slice::from_ref(&first), |x| { x.ingredients["Salvia Oil"].weight = Theoretical::Known(0.4); });
+
+        let (layers, _) = Layers::load(&base, &[first.clone(), second.clone()]).unwrap();
+        let before = std::fs::read_to_string(&first).unwrap();
+
+        for to in [&base, &first] {
+            let report = layers.save(to, &Grimoire::default()).unwrap_err();
+            assert!(matches!(report.current_context(), FSOperationError::NotLastOverlay(x) if *x == to.display().to_string()));
+        }
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), before);
+        assert_eq!(load_grimoire(&base).unwrap().0, grimoire());
+
+        // Another spelling of the last overlay is the same file
+        layers.save(&dir.join(".").join("second.yaml"), &layers.grimoire()).unwrap();
+        assert!(second.exists());
+    }
+
+    #[test]
+    fn test_migrate_base_only() {
+        let dir = temp_dir("layers-migrate");
+        let base = dir.join("base.json");
+        let first = dir.join("first.yaml");
+        std::fs::write(&base, include_str!("../grimoire2/tests/data/grimoire_v0.json")).unwrap();
+        overlay(&base, std::slice::from_ref(&first), |x| { x.characters.insert("Rowan".to_string(), Default::default()); });
+
+        let (layers, report) = Layers::load(&base, std::slice::from_ref(&first)).unwrap();
+        assert_eq!((report.from, report.to), (0, 1));
+        assert!(!layers.base.characters.contains_key("Rowan"));
+        assert!(layers.grimoire().characters.contains_key("Rowan"));
+
+        // Only the base is upgraded, the overlay is left as it was
+        let overlay = std::fs::read_to_string(&first).unwrap();
+        let migrated = dir.join("migrated.json");
+        save_grimoire(&migrated, &layers.base).unwrap();
+
+        let (loaded, report) = Layers::load(&migrated, std::slice::from_ref(&first)).unwrap();
+        assert!(report.is_empty());
+        assert_eq!(loaded.base, layers.base);
+        assert_eq!(loaded.grimoire(), layers.grimoire());
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), overlay);
+    }
+}
@@ -1,15 +1,25 @@
 mod fs;
 mod update;
+mod validate;
+mod migrate;
+mod import;
+mod export;
+mod diff;
+mod output;
+mod layers;
 mod explore;
 mod mix;
+mod substitute;
+mod compare;
+mod advise;
+mod scenario;
+mod recipe;
 //mod optimize;
 mod optimize2;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing_subscriber::*;
 use clap::*;
-use grimoire2::grimoire::versioned::GrimoireVersioned;
-use grimoire2::grimoire::Grimoire;
 
 
 pub fn main() {
@@ -38,6 +48,39 @@ pub fn main() {
                     remove_characters:\n\t<name>\n\t...\n\n\
                     remove_skills:\n\t<name>\n\t...\n\n\
                     remove_ingredients:\n\t<name>\n\t...\n\n\
+                    remove_clades:\n\t<name>\n\t...\n\n\
+                    remove_rules: bool  # lift the skill point caps\n\n\
+                    clades:\n\
+                    \t<clade name>:\n\
+                    \t\tvolume_multiplier: <multiplier of potion volume>\n\
+                    \t\tdescription: <text>\n\n\
+                    rules:  # skill point caps per skill category\n\
+                    \tprimary: <points>\n\
+                    \tsecondary: <points>\n\
+                    \ttertiary: <points>\n\n\
+                    remove_rulesets:\n\t<name>\n\t...\n\n\
+                    rulesets:  # constants of the brewing formula, missing ones are the current rules\n\
+                    \t<ruleset name>:\n\
+                    \t\tdefault_lore_effectiveness: <effectiveness of lores without a known one>\n\
+                    \t\tadvanced_potion_making: <name of the skill>\n\
+                    \t\tadvanced_potion_making_bonus: <bonus to effects at 100 points>\n\
+                    \t\tadvanced_potion_making_steps: <bool, only whole hundreds of points count, default true>\n\
+                    \t\tclades:\n\t\t\t<clade>: <volume multiplier>\n\
+                    \t\tweight_offset: <total weight that brews no volume>\n\
+                    \t\tweight_per_volume: <weight per unit of volume>\n\n\
+                    ruleset: <name of the ruleset to put in effect>\n\n\
+                    remove_productions:\n\t<name of the ingredient made>\n\t...\n\n\
+                    productions:  # how ingredients are made from others\n\
+                    \t<name of the ingredient made>:\n\
+                    \t\tinputs:\n\t\t\t<ingredient>: <amount per batch>\n\
+                    \t\tyields: <units made by a batch, default 1>\n\n\
+                    remove_recipes:\n\t<name>\n\t...\n\n\
+                    recipes:  # mixes kept by name, replaced whole\n\
+                    \t<recipe name>:\n\
+                    \t\tingredients:\n\t\t\t<ingredient>: <amount>\n\
+                    \t\tcharacter: <character the recipe is meant for>\n\
+                    \t\tnotes: <text>\n\
+                    \t\ttags: [<tag>, ...]\n\n\
                     characters:\n\
                     \t<character name>:\n\
                     \t\tremove_clades:\n\t\t\t- <clade>\n\t\t\t...\n\n\
@@ -50,12 +93,27 @@ pub fn main() {
                     \t\tparent: <name of parent 1>\n\
                     \t\tparent_2: <name of parent 2>\n\
                     \t\tremove_parent: bool\n\
-                    \t\tremove_parent_2: bool\n\n\
+                    \t\tremove_parent_2: bool\n\
+                    \t\tcategory: <Primary|Secondary|Tertiary>\n\
+                    \t\tcost: <skill points per level>\n\
+                    \t\teffectiveness_provenance: (provenance, see ingredients)\n\n\
                     ingredients:\n\
                     \t\t<name of ingredient>:\n\
                     \t\t\tskill: <lore of ingredient>\n\
                     \t\t\tremove_skill: bool  # remove lore\n\
-                    \t\t\tweight: bool  # whether the ingredient has alchemical weight\n\
+                    \t\t\tweight: (alchemical weight, theoretical*)  # true and false read as 1 and 0\n\
+                    \t\t\ttags: [<tag>, ...]  # e.g. herb, mineral, vendor-bought\n\
+                    \t\t\tremove_tags: [<tag>, ...]\n\
+                    \t\t\taliases: [<alternate name>, ...]\n\
+                    \t\t\tremove_aliases: [<alternate name>, ...]\n\
+                    \t\t\tsource: <where to find or buy it>\n\
+                    \t\t\tremove_source: bool\n\
+                    \t\t\tprovenance:  # where the values set here come from\n\
+                    \t\t\t\tsource: <observation, wiki, name of a guildmate...>\n\
+                    \t\t\t\tdate: <YYYY-MM-DD>\n\
+                    \t\t\t\tconfidence: <note>\n\
+                    \t\t\tprovenance_for:  # provenance of single values\n\
+                    \t\t\t\t<dh, mdh, ...>: (provenance)\n\
                     \t\t\tdh: (direct healing, theoretical*)\n\
                     \t\t\tmdh: (direct healing multiplier, theoretical*)\n\
                     \t\t\t<... dp, mdp, hot, mhot, pot, mpot, hl, mhl, pl, mpl, a, ma>\
@@ -72,7 +130,19 @@ pub fn main() {
                 .short('t')
                 .long("to")
                 .value_name("to")
-                .required(true)
+                .help("Where to save the grimoire; the last overlay if omitted")
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Only show what would change")
+        )
+        .arg(
+            Arg::new("fail-on-downgrade")
+                .long("fail-on-downgrade")
+                .action(ArgAction::SetTrue)
+                .help("Refuse updates that replace known values with theories or unknown values")
         )
         .arg_required_else_help(true);
 
@@ -82,12 +152,32 @@ pub fn main() {
         .value_name("grimoire")
         .required(true);
 
+    let ruleset_arg = Arg::new("ruleset")
+        .long("ruleset")
+        .global(true)
+        .env("ALRUST_RULESET")
+        .help("Ruleset of the grimoire to brew with instead of the one in effect, `default` for \
+               the current game rules");
+
     let app = Command::new("Alrust")
         .arg(grimoire_arg)
+        .arg(output::arg())
+        .arg(ruleset_arg)
+        .arg(layers::arg())
         .subcommand(update_subcommand)
+        .subcommand(validate::command())
+        .subcommand(migrate::command())
+        .subcommand(import::command())
+        .subcommand(export::command())
+        .subcommand(diff::command())
         .subcommand(explore::list::command())
         .subcommand(explore::view::command())
         .subcommand(mix::command())
+        .subcommand(substitute::command())
+        .subcommand(compare::command())
+        .subcommand(advise::command())
+        .subcommand(scenario::command())
+        .subcommand(recipe::command())
         .subcommand(optimize2::command_run())
         .subcommand(optimize2::command_explore())
         .subcommand_required(true)
@@ -96,31 +186,81 @@ pub fn main() {
 
     let matches = app.get_matches();
     let grimoire_path = Path::new(matches.get_one::<String>("grimoire").unwrap());
-    let grimoire_versioned: GrimoireVersioned = fs::load(grimoire_path).unwrap();
-    let grimoire: Grimoire = grimoire_versioned.into();
+    let overlays: Vec<PathBuf> = matches.get_many::<String>("overlay")
+        .map(|x| x.map(PathBuf::from).collect())
+        .unwrap_or_default();
+    let (layers, report) = layers::Layers::load(grimoire_path, &overlays).unwrap();
+    let mut grimoire = layers.grimoire();
+
+    if let Some(name) = matches.get_one::<String>("ruleset") {
+        if grimoire.select_ruleset(name).is_err() {
+            output::not_found("Ruleset", name);
+        }
+    }
+
+    if !report.is_empty() && !matches!(matches.subcommand(), Some(("migrate", _))) {
+        eprintln!(
+            "{} uses grimoire schema {}, it was upgraded to {} in memory; \
+             run `migrate` to see the changes and save it",
+            grimoire_path.display(), report.from, report.to,
+        );
+    }
 
     match matches.subcommand() {
         Some(("update", args)) => {
             update::update_grimoire(
-                grimoire, 
+                &layers, 
                 Path::new(args.get_one::<String>("from").unwrap()), 
-                Path::new(args.get_one::<String>("to").unwrap()),
+                args.get_one::<String>("to").map(Path::new),
+                args.get_flag("dry-run"),
+                args.get_flag("fail-on-downgrade"),
             ).unwrap();
         },
+        Some(("migrate", args)) => {
+            migrate::matched_command(layers.base, report, args)
+        },
+        Some(("import", args)) => {
+            import::matched_command(&layers, args)
+        },
+        Some(("export", args)) => {
+            export::matched_command(grimoire, args)
+        },
+        Some(("diff", args)) => {
+            // `--ruleset` is for brewing, not a change of the grimoire
+            diff::matched_command(layers.grimoire(), args)
+        },
+        Some(("validate", args)) => {
+            validate::matched_command(grimoire, args)
+        },
         Some(("list", args)) => {
             explore::list::matched_command(grimoire, args)
         },
         Some(("view", args)) => {
-            explore::view::matched_command(grimoire, args)
+            explore::view::matched_command(grimoire, &layers, args)
         },
         Some(("mix", args)) => {
             mix::matched_command(grimoire, args)
         },
+        Some(("substitute", args)) => {
+            substitute::matched_command(grimoire, args)
+        },
+        Some(("compare", args)) => {
+            compare::matched_command(grimoire, args)
+        },
+        Some(("advise", args)) => {
+            advise::matched_command(grimoire, args)
+        },
+        Some(("scenario", args)) => {
+            scenario::matched_command(grimoire, args)
+        },
+        Some(("recipe", args)) => {
+            recipe::matched_command(grimoire, &layers, args)
+        },
         Some(("optimize", args)) => {
-            optimize2::matched_command_run(grimoire, args)
+            optimize2::matched_command_run(grimoire, &layers, args)
         },
         Some(("explore", args)) => {
-            optimize2::matched_command_explore(args)
+            optimize2::matched_command_explore(&layers, args)
         }
         None | Some(_) => {}
     }
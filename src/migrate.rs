@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use clap::*;
+use grimoire2::grimoire::Grimoire;
+use grimoire2::migrate::MigrationReport;
+
+use crate::fs::save_grimoire;
+
+pub fn command() -> Command {
+    Command::new("migrate")
+        .before_help("Upgrade the grimoire to the current schema and show what was changed; \
+                      overlays are left out")
+        .arg(
+            Arg::new("to")
+                .short('t')
+                .long("to")
+                .value_name("to")
+                .help("Where to save the upgraded grimoire, only the report is printed if not set")
+        )
+}
+
+pub fn matched_command(grimoire: Grimoire, report: MigrationReport, args: &ArgMatches) {
+    serde_yaml::to_writer(std::io::stdout(), &report).unwrap();
+
+    if let Some(to) = args.get_one::<String>("to") {
+        save_grimoire(Path::new(to), &grimoire).unwrap();
+    }
+}
@@ -3,12 +3,19 @@ use error_stack::{Report, Result, IntoReport, ResultExt};
 use grimoire2::modify::command::Commands;
 use grimoire2::prelude::{Grimoire, Character};
 use grimoire2::standalone::OptimizedGrimoire;
-use grimoire2::standalone::Mix;
-use serde::Deserialize;
+use grimoire2::standalone::{Mix, mix_for_every_character};
+use grimoire2::bill::{Bill, Planner, Supplies};
+use grimoire2::timeline::Timeline;
+use geneticalchemy::prelude::AlchemyFitnessElement;
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
 use grimoire_serde::potion::{PotionSerializableConfig, PotionSerializable};
 use grimoire_serde::modify::GrimoireUpdateSerializable;
 use grimoire_serde::mix::MixIngredients;
+use grimoire_serde::theoretical::TheoreticalWrapper;
 use crate::fs::load;
+use crate::output::{self, Output};
+use crate::optimize2::eexpr::EvalExpressionFitnessElement;
 use clap::*;
 use thiserror::Error;
 
@@ -16,17 +23,46 @@ use thiserror::Error;
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct MixConfig {
-    potion: PotionSerializableConfig,
-    grimoire: GrimoireUpdateSerializable,
-    mix: MixIngredients
+    pub potion: PotionSerializableConfig,
+    pub grimoire: GrimoireUpdateSerializable,
+    pub mix: MixIngredients,
+    pub supplies: Supplies,
 }
 
 #[derive(Error, Debug)]
 pub enum MixError {
     #[error("Ingredient not found: {0}")]
     IngredientNotFound(String),
-    #[error("Character not found: {0}")]
-    CharacterNotFound(String),
+    #[error("Invalid expression: {0}")]
+    BadExpression(String),
+    #[error("The grimoire update of the recipe does not fit the grimoire")]
+    BadUpdate,
+    #[error("Cannot work out the bill of the mix")]
+    BadBill,
+    #[error("Cannot brew the mix")]
+    BadMix,
+}
+
+#[derive(Serialize)]
+pub struct CharacterPotion {
+    score: f64,
+    behind_best: f64,
+    advanced_potion_making_mod: f64,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    lore_multipliers: IndexMap<String, TheoreticalWrapper>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    lore_differences: IndexMap<String, f64>,
+    potion: PotionSerializable,
+}
+
+#[derive(Serialize)]
+pub struct AllCharactersPotion {
+    by: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    best: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ahead_of_next: Option<f64>,
+    characters: IndexMap<String, CharacterPotion>,
 }
 
 pub fn command() -> Command {
@@ -36,10 +72,45 @@ pub fn command() -> Command {
             Arg::new("character")
                 .short('c')
                 .long("character")
-                .required(true)
+                .required_unless_present_any(["all-characters", "bill"])
                 .help("Character name")
                 .env("ALRUST_CHARACTER")            
         )
+        .arg(
+            Arg::new("all-characters")
+                .short('a')
+                .long("all-characters")
+                .action(ArgAction::SetTrue)
+                .help("Calculate the potion for every character in the grimoire")
+        )
+        .arg(
+            Arg::new("by")
+                .long("by")
+                .default_value("dh")
+                .help("Expression used to pick the best character with --all-characters \
+                       (same identifiers as `effects` of `optimize`)")
+        )
+        .arg(
+            Arg::new("bill")
+                .long("bill")
+                .action(ArgAction::SetTrue)
+                .help("Expand the mix into the raw materials to buy, make or take from stock \
+                       (see `productions` of the grimoire)")
+        )
+        .arg(
+            Arg::new("timeline")
+                .long("timeline")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["all-characters", "bill"])
+                .help("Show healing and poison second by second instead of the potion")
+        )
+        .arg(
+            Arg::new("volume")
+                .long("volume")
+                .value_parser(value_parser!(f64))
+                .requires("timeline")
+                .help("Volume to simulate with --timeline, the volume of the mix by default")
+        )
         .arg(
             Arg::new("mixfile")
                 .index(1)
@@ -58,8 +129,14 @@ pub fn command() -> Command {
                     \ttotal_healing_raw: bool (default false)  # healing stats\n\
                     \ttotal_poison_raw: bool (default false)  # poison stats\n\
                     \ttotal_healing: bool (default false)  # healing-poison stats\n\
-                    \ttotal_poison: bool (default false)  # poison-healing stats\n\n\
+                    \ttotal_poison: bool (default false)  # poison-healing stats\n\
+                    \tbreakdown: bool (default false)  # part of every ingredient in every effect\n\n\
                     grimoire: grimoire update configuration (see help for `update` command)\n\n\
+                    supplies: (optional)  # used by --bill and `cost` of --by\n\
+                    \tprices:\n\
+                    \t\t<name of ingredient>: <price of a unit>\n\
+                    \tstock:\n\
+                    \t\t<name of ingredient>: <units at hand>\n\n\
                     mix:\n\
                     \t<name of ingredient>: <amount>\n\
                     \t<name of ingredient>: <amount>\n\
@@ -69,22 +146,173 @@ pub fn command() -> Command {
 }
 
 pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
-    let character_name = args.get_one::<String>("character").unwrap();
     let config: MixConfig = load(Path::new(args.get_one::<String>("mixfile").unwrap())).unwrap();
 
-    let character = grimoire.characters.get(character_name.as_str()).ok_or(
-        Report::new(MixError::CharacterNotFound(character_name.clone()))
-    ).unwrap().clone();
+    if args.get_flag("bill") {
+        let character = match args.get_one::<String>("character") {
+            Some(name) => match grimoire.characters.get(name.as_str()) {
+                Some(x) => x.clone(),
+                None => output::not_found("Character", name),
+            },
+            None => Character::default(),
+        };
+        let bill = or_not_found(config.bill(grimoire, &character));
+        Output::from_args(args).print(&bill);
+        return;
+    }
 
-    let potion = config.run(grimoire, character).unwrap();
-    serde_yaml::to_writer(std::io::stdout(), &potion).unwrap();
+    if args.get_flag("all-characters") {
+        let by = args.get_one::<String>("by").unwrap();
+        let potions = or_not_found(config.run_all(grimoire, by));
+        Output::from_args(args).print(&potions);
+        return;
+    }
+
+    let character_name = args.get_one::<String>("character").unwrap();
+
+    let Some(character) = grimoire.characters.get(character_name.as_str()).cloned() else {
+        output::not_found("Character", character_name)
+    };
+
+    if args.get_flag("timeline") {
+        let volume = args.get_one::<f64>("volume").copied();
+        let timeline = or_not_found(config.with_mix(grimoire, &character, |mix| {
+            Timeline::simulate(mix, volume.unwrap_or_else(|| mix.volume().inner()))
+        }));
+        Output::from_args(args).print_rows(&timeline.ticks);
+        return;
+    }
+
+    let potion = or_not_found(config.run(grimoire, character));
+    Output::from_args(args).print(&potion);
+}
+
+/// Unwraps the outcome of brewing a mix, exiting with `NOT_FOUND_EXIT_CODE` if an ingredient
+/// of the mix is not in the grimoire. The mix error may be behind the context of the caller
+pub fn or_not_found<T, C>(result: Result<T, C>) -> T {
+    match result {
+        Ok(x) => x,
+        Err(report) => match report.downcast_ref::<MixError>() {
+            Some(MixError::IngredientNotFound(name)) => output::not_found("Ingredient", name),
+            _ => panic!("{report:?}"),
+        },
+    }
 }
 
 impl MixConfig {
-    pub fn run(&self, mut grimoire: Grimoire, character: Character) -> Result<PotionSerializable, MixError> {
-        self.grimoire.to_update().update(&mut grimoire);
-      
-        let optimized = OptimizedGrimoire::from((&character, &grimoire));
+    pub fn run(&self, grimoire: Grimoire, character: Character) -> Result<PotionSerializable, MixError> {
+        self.with_mix(grimoire, &character, |mix| self.potion.serialize_mix(mix))
+    }
+
+    /// Raw materials of the mix, the character only matters for the names of the ingredients
+    pub fn bill(&self, mut grimoire: Grimoire, character: &Character) -> Result<Bill, MixError> {
+        self.update(&mut grimoire)?;
+
+        let planner = Planner::new(grimoire.productions.clone(), self.supplies.clone());
+
+        self.with_updated_mix(&grimoire, character, |mix| planner.plan(mix.named_ingredients_iter()))?
+            .into_report()
+            .change_context(MixError::BadBill)
+    }
+
+    /// Calculate the potion for every character and rank them by the `by` expression
+    pub fn run_all(&self, mut grimoire: Grimoire, by: &str) -> Result<AllCharactersPotion, MixError> {
+        self.update(&mut grimoire)?;
+
+        let node = evalexpr::build_operator_tree(by)
+            .into_report()
+            .change_context(MixError::BadExpression(by.to_string()))?;
+        let fitness = EvalExpressionFitnessElement::new(node, 1.)
+            .with_planner(Planner::new(grimoire.productions.clone(), self.supplies.clone()));
+
+        let ingredients: Vec<(String, u64)> = self.mix.iter().map(|(n, a)| (n.clone(), *a)).collect();
+        let mixes = mix_for_every_character(&grimoire, &ingredients)
+            .map_err(|err| {
+                let context = match &err {
+                    grimoire2::error::Error::IngredientNotFound(name) => MixError::IngredientNotFound(name.clone()),
+                    _ => MixError::BadMix,
+                };
+                Report::new(err).change_context(context)
+            })?;
+
+        let mut scored: Vec<(f64, &grimoire2::standalone::CharacterMix)> = mixes
+            .iter()
+            .map(|x| (fitness.fitness(&x.mix()), x))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let best = scored.first().map(|(score, x)| (*score, *x));
+        let ahead_of_next = match (scored.first(), scored.get(1)) {
+            (Some((first, _)), Some((second, _))) => Some(first - second),
+            _ => None
+        };
+
+        let characters = scored.iter().map(|(score, x)| {
+            let mut lore_multipliers: IndexMap<String, TheoreticalWrapper> = x.lore_multipliers
+                .iter()
+                .map(|(n, v)| (n.clone(), (*v).into()))
+                .collect();
+            lore_multipliers.sort_keys();
+
+            let mut lore_differences: IndexMap<String, f64> = match best {
+                Some((_, best)) if best.character != x.character => x.lore_multipliers
+                    .iter()
+                    .map(|(skill, value)| {
+                        let best_value = best.lore_multipliers.get(skill).cloned().unwrap_or_default();
+                        (skill.clone(), value.inner() - best_value.inner())
+                    })
+                    .filter(|(_, delta)| *delta != 0.)
+                    .collect(),
+                _ => IndexMap::default(),
+            };
+            lore_differences.sort_keys();
+
+            let potion = CharacterPotion {
+                score: *score,
+                behind_best: best.map(|(best_score, _)| score - best_score).unwrap_or_default(),
+                advanced_potion_making_mod: x.grimoire.advanced_potion_making_mod,
+                lore_multipliers,
+                lore_differences,
+                potion: self.potion.serialize_mix(&x.mix()),
+            };
+
+            (x.character.clone(), potion)
+        }).collect();
+
+        Ok(AllCharactersPotion {
+            by: by.to_string(),
+            best: best.map(|(_, x)| x.character.clone()),
+            ahead_of_next,
+            characters,
+        })
+    }
+
+    pub fn with_mix<T>(
+        &self, 
+        mut grimoire: Grimoire, 
+        character: &Character, 
+        f: impl FnOnce(&Mix) -> T
+    ) -> Result<T, MixError> {
+        self.update(&mut grimoire)?;
+        self.with_updated_mix(&grimoire, character, f)
+    }
+
+    fn update(&self, grimoire: &mut Grimoire) -> Result<(), MixError> {
+        self.grimoire.to_update(&grimoire.effects)
+            .into_report()
+            .change_context(MixError::BadUpdate)?
+            .update(grimoire);
+
+        Ok(())
+    }
+
+    fn with_updated_mix<T>(
+        &self,
+        grimoire: &Grimoire,
+        character: &Character,
+        f: impl FnOnce(&Mix) -> T
+    ) -> Result<T, MixError> {
+        let optimized = OptimizedGrimoire::from((character, grimoire));
 
         let mut ingredients: Vec<(usize, u64)> = Vec::default();
 
@@ -100,6 +328,79 @@ impl MixConfig {
 
         let mix = Mix::new(&optimized, ingredients);
 
-        Ok(self.potion.serialize_mix(&mix))
+        Ok(f(&mix))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::error::Error as GrimoireError;
+    use grimoire2::grimoire::Ingredient;
+    use grimoire2::theoretical::Theoretical;
+
+    use super::*;
+
+    fn grimoire() -> Grimoire {
+        let mut leaf = Ingredient {
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        };
+        leaf.modifiers[Effect::DirectHealing].term = Theoretical::Known(2.);
+
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Leaf".to_string(), leaf);
+        grimoire.characters.insert("Tashka".to_string(), Character::default());
+        grimoire
+    }
+
+    fn config(ingredient: &str) -> MixConfig {
+        MixConfig {
+            mix: [(ingredient.to_string(), 2)].into_iter().collect(),
+            ..MixConfig::default()
+        }
+    }
+
+    fn not_found<T, C>(result: Result<T, C>) -> Option<String> {
+        match result.map(|_| ()).unwrap_err().downcast_ref::<MixError>() {
+            Some(MixError::IngredientNotFound(name)) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_ingredient_not_found() {
+        let character = Character::default();
+        let missing = Some("Moss".to_string());
+
+        assert!(config("Leaf").run(grimoire(), character.clone()).is_ok());
+        assert_eq!(not_found(config("Moss").run(grimoire(), character.clone())), missing);
+        assert_eq!(not_found(config("Moss").bill(grimoire(), &character)), missing);
+        assert_eq!(not_found(config("Moss").run_all(grimoire(), "dh")), missing);
+        assert_eq!(not_found(config("Moss").with_mix(grimoire(), &character, |x| x.volume())), missing);
+    }
+
+    #[test]
+    fn test_run_all_keeps_grimoire_error() {
+        let mut grimoire = grimoire();
+        grimoire.characters.insert("Rowan".to_string(), Character::default());
+
+        let potions = config("Leaf").run_all(grimoire.clone(), "dh").unwrap();
+        assert_eq!(potions.characters.keys().collect::<Vec<_>>(), ["Tashka", "Rowan"]);
+        assert!(potions.ahead_of_next.is_some());
+
+        let report = config("Moss").run_all(grimoire, "dh").err().unwrap();
+        assert!(matches!(report.downcast_ref::<GrimoireError>(), Some(GrimoireError::IngredientNotFound(x)) if x == "Moss"));
+    }
+
+    #[test]
+    fn test_ingredient_not_found_behind_context() {
+        #[derive(Error, Debug)]
+        #[error("Could not evaluate")]
+        struct Outer;
+
+        let result = config("Moss").run(grimoire(), Character::default()).change_context(Outer);
+
+        assert_eq!(not_found(result), Some("Moss".to_string()));
+    }
+}
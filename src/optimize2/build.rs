@@ -1,9 +1,11 @@
 use std::sync::{Arc, Mutex};
 
-use evalexpr::{context_map, Node};
+use evalexpr::{context_map, ContextWithMutableVariables, Node};
 use rand::{rngs::ThreadRng, thread_rng};
 use geneticalchemy::{prelude::*};
 use grimoire2::prelude::*;
+use grimoire2::bill::Planner;
+use crate::scenario::ScenarioObjective;
 use tracing::info;
 use crate::fs::save;
 use std::sync::mpsc::Receiver;
@@ -26,6 +28,8 @@ use super::{
 
 pub struct Optimizator {
     grimoire: Grimoire,
+    planner: Planner,
+    scenario: Option<Arc<ScenarioObjective>>,
     optimized_grimoire: OptimizedGrimoire,
     config: OptimizatorConfig,
     pub populations: Arc<Mutex<PopulationsSerializable>>,
@@ -150,10 +154,15 @@ impl Optimizator {
             .effects
             .iter()
             .map(|x| {
-                Box::new(EvalExpressionFitnessElement::new(
+                let element = EvalExpressionFitnessElement::new(
                     x.clone(),
                     self.config.unknown_multiplier,
-                )) as Box<dyn AlchemyFitnessElement>
+                ).with_planner(self.planner.clone());
+
+                match &self.scenario {
+                    Some(scenario) => Box::new(element.with_scenario(scenario.clone())),
+                    None => Box::new(element) as Box<dyn AlchemyFitnessElement>,
+                }
             })
             .collect();
 
@@ -165,10 +174,22 @@ impl Optimizator {
     }
 
     pub fn new(mut grimoire: Grimoire, character: Character, config: OptimizatorConfig) -> Self {
+        // Inputs of productions are priced even if they can't go into the potion
+        let planner = Planner::new(grimoire.productions.clone(), config.supplies.clone());
+
+        // Potions of the scenario may well be brewed from excluded ingredients
+        let unknown_multiplier = config.unknown_multiplier;
+        let scenario = config.scenario.clone().map(|x| Arc::new(ScenarioObjective::new(
+            x,
+            &(&character, &grimoire).into(),
+            move |x| x.known_or(|x| x * unknown_multiplier),
+        ).unwrap()));
+
         if let Some(node) = &config.include_ingredients {
+            let effects = &grimoire.effects;
             grimoire
                 .ingredients
-                .retain(|_, ingredient| Self::should_include_ingredient(node, ingredient).unwrap())
+                .retain(|_, ingredient| Self::should_include_ingredient(node, ingredient, effects).unwrap())
         }
 
         grimoire.ingredients.retain(|name, _| !config.exclude_ingredients.contains(name));
@@ -183,38 +204,35 @@ impl Optimizator {
 
         Self {
             grimoire,
+            planner,
+            scenario,
             optimized_grimoire,
             config,
             populations,
         }
     }
 
-    fn should_include_ingredient(node: &Node, ingredient: &Ingredient) -> Result<bool> {
-        let context = context_map! {
-            "dh" => ingredient.modifiers[Effect::DirectHealing].term.inner(),
-            "mdh" => ingredient.modifiers[Effect::DirectHealing].multiplier.inner(),
-
-            "dp" => ingredient.modifiers[Effect::DirectPoison].term.inner(),
-            "mdp" => ingredient.modifiers[Effect::DirectPoison].multiplier.inner(),
-
-            "hot" => ingredient.modifiers[Effect::HealingOverTime].term.inner(),
-            "mhot" => ingredient.modifiers[Effect::HealingOverTime].multiplier.inner(),
-
-            "pot" => ingredient.modifiers[Effect::PoisonOverTime].term.inner(),
-            "mpot" => ingredient.modifiers[Effect::PoisonOverTime].multiplier.inner(),
-
-            "hl" => ingredient.modifiers[Effect::HealingLength].term.inner(),
-            "mhl" => ingredient.modifiers[Effect::HealingLength].multiplier.inner(),
-
-            "pl" => ingredient.modifiers[Effect::PoisonLength].term.inner(),
-            "mpl" => ingredient.modifiers[Effect::PoisonLength].multiplier.inner(),
-
-            "a" => ingredient.modifiers[Effect::Alcohol].term.inner(),
-            "ma" => ingredient.modifiers[Effect::Alcohol].multiplier.inner(),
-
-            "w" => ingredient.weight as i64,
+    pub fn should_include_ingredient(node: &Node, ingredient: &Ingredient, effects: &Effects) -> Result<bool> {
+        let mut context = context_map! {
+            "w" => ingredient.weight.inner(),
+            "source" => ingredient.source.clone().unwrap_or_default(),
+            "tag" => Function::new(Self::tag_function(ingredient)),
         }?;
 
+        for (code, effect, multiplier) in effects.modifier_codes() {
+            let modifier = &ingredient.modifiers[effect];
+            let value = if multiplier { modifier.multiplier } else { modifier.term };
+            context.set_value(code, value.inner().into())?;
+        }
+
         Ok(node.eval_boolean_with_context(&context)?)
     }
+
+    /// `tag("herb")` is true when the ingredient has the tag
+    pub fn tag_function(
+        ingredient: &Ingredient,
+    ) -> impl Fn(&evalexpr::Value) -> evalexpr::EvalexprResult<evalexpr::Value> + Clone + Send + Sync {
+        let tags = ingredient.tags.clone();
+        move |argument| Ok(tags.contains(&argument.as_string()?).into())
+    }
 }
\ No newline at end of file
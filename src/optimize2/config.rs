@@ -2,6 +2,8 @@ use std::collections::HashSet;
 
 use serde::Deserialize;
 use grimoire_serde::modify::GrimoireUpdateSerializable;
+use grimoire2::bill::Supplies;
+use grimoire2::scenario::Scenario;
 use genetic::operators::TournamentSelector;
 use evalexpr::Node;
 
@@ -28,6 +30,8 @@ pub struct OptimizatorConfig {
     pub include_ingredients: Option<Node>,
     pub exclude_ingredients: Vec<String>,
     pub unknown_multiplier: f64,
+    pub supplies: Supplies,
+    pub scenario: Option<Scenario>,
 }
 
 #[derive(Deserialize)]
@@ -53,6 +57,8 @@ impl Default for OptimizatorConfig {
             unknown_multiplier: 1.,
             num_children: 2,
             exclude_ingredients: Vec::default(),
+            supplies: Supplies::default(),
+            scenario: None,
         }
     }    
 }
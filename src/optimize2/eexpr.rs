@@ -1,8 +1,12 @@
 use evalexpr::*;
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, sync::Arc};
 
 use geneticalchemy::prelude::AlchemyFitnessElement;
 use grimoire2::prelude::*;
+use grimoire2::bill::Planner;
+use grimoire2::timeline::Timeline;
+
+use crate::scenario::ScenarioObjective;
 use serde::Deserialize;
 
 #[derive(Debug)]
@@ -26,10 +30,52 @@ impl Display for UnknownIdentifierError {
 
 impl Error for UnknownIdentifierError {}
 
+const SCENARIO_IDENTIFIERS: [&str; 6] = [
+    "survived", "survival_time", "end_health", "lowest_health", "healing_delivered", "potions_drunk",
+];
+
+/// Identifiers that look up the timeline of the potion, `heal_at_10s`, `poison_at_10s` and
+/// `net_at_10s` for the totals after ten seconds, `time_to_heal_100` for the seconds it takes
+/// to heal 100 more than it poisons
+enum TimelineQuery {
+    HealAt(u64),
+    PoisonAt(u64),
+    NetAt(u64),
+    TimeToHeal(f64),
+}
+
+impl TimelineQuery {
+    fn parse(identifier: &str) -> Option<Self> {
+        let at = |prefix: &str| identifier
+            .strip_prefix(prefix)
+            .and_then(|x| x.strip_suffix('s'))
+            .and_then(|x| x.parse().ok());
+
+        at("heal_at_").map(Self::HealAt)
+            .or_else(|| at("poison_at_").map(Self::PoisonAt))
+            .or_else(|| at("net_at_").map(Self::NetAt))
+            .or_else(|| identifier.strip_prefix("time_to_heal_")?.parse().ok().map(Self::TimeToHeal))
+    }
+
+    /// Potions that never get there take `f64::MAX` seconds
+    fn evaluate(&self, timeline: &Timeline) -> f64 {
+        match self {
+            Self::HealAt(x) => timeline.at(*x).total_healing,
+            Self::PoisonAt(x) => timeline.at(*x).total_poison,
+            Self::NetAt(x) => timeline.at(*x).total_net,
+            Self::TimeToHeal(x) => timeline.time_to_net(*x).map(|x| x as f64).unwrap_or(f64::MAX),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct EvalExpressionFitnessElement {
     expression: Node,
     unknown_multiplier: f64,
+    #[serde(skip)]
+    planner: Planner,
+    #[serde(skip)]
+    scenario: Option<Arc<ScenarioObjective>>,
 }
 
 impl EvalExpressionFitnessElement {
@@ -37,38 +83,65 @@ impl EvalExpressionFitnessElement {
         Self {
             expression,
             unknown_multiplier,
+            planner: Planner::default(),
+            scenario: None,
         }
     }
 
+    /// Prices the mix for the `cost` identifier
+    pub fn with_planner(mut self, planner: Planner) -> Self {
+        self.planner = planner;
+        self
+    }
+
+    /// Drinks the mix through the scenario for `survived`, `survival_time`, `end_health`,
+    /// `lowest_health`, `healing_delivered` and `potions_drunk`
+    pub fn with_scenario(mut self, scenario: Arc<ScenarioObjective>) -> Self {
+        self.scenario = Some(scenario);
+        self
+    }
+
     fn get_identifier_value(
         &self,
         identifier: &str,
         mix: &Mix,
     ) -> Result<f64, UnknownIdentifierError> {
-        match identifier {
-            "dh" => Ok(mix
-                .effect(Effect::DirectHealing)
-                .known_or(|x| x * self.unknown_multiplier)),
-            "dp" => Ok(mix
-                .effect(Effect::DirectPoison)
-                .known_or(|x| x * self.unknown_multiplier)),
-            "hot" => Ok(mix
-                .effect(Effect::HealingOverTime)
-                .known_or(|x| x * self.unknown_multiplier)),
-            "pot" => Ok(mix
-                .effect(Effect::PoisonOverTime)
-                .known_or(|x| x * self.unknown_multiplier)),
-            "hl" => Ok(mix
-                .effect(Effect::HealingLength)
-                .known_or(|x| x * self.unknown_multiplier)),
-            "pl" => Ok(mix
-                .effect(Effect::PoisonLength)
-                .known_or(|x| x * self.unknown_multiplier)),
-            "a" => Ok(mix
-                .effect(Effect::Alcohol)
-                .known_or(|x| x * self.unknown_multiplier)),
-            "volume" => Ok(mix.volume()),
-            _ => Err(UnknownIdentifierError::new(identifier)),
+        if identifier == "volume" {
+            return Ok(mix.volume().known_or(|x| x * self.unknown_multiplier));
+        }
+
+        // Productions that form a cycle can't be planned, such mixes cost so much that they
+        // are never picked
+        if identifier == "cost" {
+            let cost = self.planner.plan(mix.named_ingredients_iter()).map(|x| x.cost);
+            return Ok(cost.unwrap_or(f64::INFINITY));
+        }
+
+        if let Some(scenario) = self.scenario.as_ref().filter(|_| SCENARIO_IDENTIFIERS.contains(&identifier)) {
+            let outcome = scenario.outcome(Some(mix), |x| x.known_or(|x| x * self.unknown_multiplier));
+
+            return Ok(match identifier {
+                "survived" => if outcome.survived { 1. } else { 0. },
+                "survival_time" => outcome.died_at.unwrap_or(scenario.length()) as f64,
+                "end_health" => outcome.health,
+                "lowest_health" => outcome.lowest_health,
+                "healing_delivered" => outcome.healing,
+                _ => outcome.potions_drunk() as f64,
+            });
+        }
+
+        if let Some(query) = TimelineQuery::parse(identifier) {
+            let timeline = Timeline::simulate_with(
+                mix,
+                mix.volume().known_or(|x| x * self.unknown_multiplier),
+                |x| x.known_or(|x| x * self.unknown_multiplier),
+            );
+            return Ok(query.evaluate(&timeline));
+        }
+
+        match mix.effects().by_code(identifier) {
+            Some(effect) => Ok(mix.effect(effect).known_or(|x| x * self.unknown_multiplier)),
+            None => Err(UnknownIdentifierError::new(identifier)),
         }
     }
 
@@ -91,4 +164,43 @@ impl AlchemyFitnessElement for EvalExpressionFitnessElement {
         let context = self.context(mix);
         self.expression.eval_float_with_context(&context).unwrap()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::bill::Supplies;
+    use grimoire2::grimoire::{Ingredient, Production};
+    use grimoire2::standalone::OptimizedGrimoire;
+
+    use super::*;
+
+    fn fitness(grimoire: &Grimoire, expression: &str) -> f64 {
+        let supplies = Supplies {
+            prices: [("Salvia".to_string(), 2.)].into_iter().collect(),
+            ..Supplies::default()
+        };
+        let element = EvalExpressionFitnessElement::new(build_operator_tree(expression).unwrap(), 1.)
+            .with_planner(Planner::new(grimoire.productions.clone(), supplies));
+
+        let optimized = OptimizedGrimoire::from((&Character::default(), grimoire));
+        let mix = Mix::new(&optimized, vec![(optimized.ingredients.by_name("Salvia").unwrap(), 3)]);
+
+        element.fitness(&mix)
+    }
+
+    #[test]
+    fn test_cost() {
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Salvia".to_string(), Ingredient::default());
+        grimoire.ingredients.insert("Salvia Seeds".to_string(), Ingredient::default());
+
+        assert_eq!(fitness(&grimoire, "cost"), 6.);
+
+        let production = |input: &str| Production::new([(input.to_string(), 1)].into_iter().collect(), 1);
+        grimoire.productions.insert("Salvia".to_string(), production("Salvia Seeds"));
+        grimoire.productions.insert("Salvia Seeds".to_string(), production("Salvia"));
+
+        assert_eq!(fitness(&grimoire, "cost"), f64::INFINITY);
+        assert_eq!(fitness(&grimoire, "-cost"), f64::NEG_INFINITY);
+    }
+}
@@ -1,16 +1,21 @@
-mod config;
+pub mod config;
 mod printer;
-mod build;
+pub mod build;
 mod error;
-mod eexpr;
+pub mod eexpr;
 mod repl;
 mod message;
 
 use std::{sync::mpsc, thread};
 
+use std::path::PathBuf;
+
 use grimoire2::grimoire::Grimoire;
 use clap::*;
 
+use crate::layers::Layers;
+use crate::recipe::RecipeBook;
+
 const CONFIG_HELP: &str = "
 Config file format:
 
@@ -25,8 +30,15 @@ volume: float  # desired volume; NOTE: it doesn't take alvarin clade into accoun
 effects:  # what will the algorithm optimize for
     - <expression using dh, mdh, dp, mdp, hot, mhot, pot, mpot, hl, mhl, pl, mpl, a, ma>
     - ...
+    # volume and cost may be used as well, cost is the price of the raw materials of the mix
+    # (see supplies)
+    # heal_at_<N>s, poison_at_<N>s, net_at_<N>s: healing, poison and healing minus poison after N seconds
+    # time_to_heal_<N>: seconds until healing is N ahead of poison, very large if it never is
+    # survived (1 or 0), survival_time, end_health, lowest_health, healing_delivered, potions_drunk:
+    # how the potion does in the scenario
 
-include_ingredients: expression  # Not required, expression that returns bool to determine whether ingredient will be included
+include_ingredients: expression  # Not required, expression that returns bool to determine whether ingredient will be included,
+                                 # may also use w, source and tag(\"<tag>\")
 
 exclude_ingredients:
 
@@ -37,6 +49,15 @@ unknown_multiplier: float  # Theoretical values will be multiplied by this facto
 
 num_children: int  # Number of children
 
+scenario:  # Not required, a fight to drink the potion in (see help for `scenario` command),
+           # the potions without a mix are the one being optimized
+
+supplies:  # Not required, prices and stock used by cost, made ingredients follow `productions` of the grimoire
+    prices:
+        <ingredient name>: float  # price of a unit
+    stock:
+        <ingredient name>: int  # units at hand, free of cost
+
 ";
 
 pub fn command_run() -> Command {
@@ -56,9 +77,23 @@ pub fn command_run() -> Command {
                 .env("ALRUST_CHARACTER")
                 .required(true)
         )
+        .arg(recipes_to_arg())
+}
+
+fn recipes_to_arg() -> Arg {
+    Arg::new("to")
+        .env("ALRUST_TO")
+        .short('t')
+        .long("to")
+        .value_name("to")
+        .help("Where `keep` saves recipes; the last overlay if omitted")
+}
+
+fn recipe_book(layers: &Layers, args: &ArgMatches) -> RecipeBook {
+    RecipeBook::new(layers.clone(), args.get_one::<String>("to").map(PathBuf::from))
 }
 
-pub fn matched_command_run(grimoire: Grimoire, args: &ArgMatches) {
+pub fn matched_command_run(grimoire: Grimoire, layers: &Layers, args: &ArgMatches) {
     let config_filename = std::path::Path::new(args.get_one::<String>("config").unwrap());
     let config: config::OptimizatorConfig = crate::fs::load(config_filename).unwrap();
 
@@ -73,7 +108,7 @@ pub fn matched_command_run(grimoire: Grimoire, args: &ArgMatches) {
 
     let handle = thread::spawn(move || optimizator.run(receiver).unwrap());
 
-    repl::run_repl(populations);
+    repl::run_repl(populations, recipe_book(layers, args), Some(character_name.clone()));
 
     sender.send(message::Message::Stop).unwrap();
 
@@ -88,10 +123,18 @@ pub fn command_explore() -> Command {
                 .index(1)
                 .required(true)
         )
+        .arg(
+            Arg::new("character")
+                .short('c')
+                .long("character")
+                .env("ALRUST_CHARACTER")
+                .help("Character the results were optimized for, kept with the recipes saved by `keep`")
+        )
+        .arg(recipes_to_arg())
 }
 
 
-pub fn matched_command_explore(args: &ArgMatches) {
+pub fn matched_command_explore(layers: &Layers, args: &ArgMatches) {
     use std::path::Path;
     use crate::fs::load;
     use printer::PopulationsSerializable;
@@ -100,5 +143,5 @@ pub fn matched_command_explore(args: &ArgMatches) {
     let filename = args.get_one::<String>("filename").unwrap();
     let populations: PopulationsSerializable = load(Path::new(filename)).unwrap();
     let arched_populations = Arc::new(Mutex::new(populations));
-    repl::run_repl(arched_populations);
+    repl::run_repl(arched_populations, recipe_book(layers, args), args.get_one::<String>("character").cloned());
 }
\ No newline at end of file
@@ -1,15 +1,19 @@
 use genetic::NotNan;
 use geneticalchemy::prelude::Mix;
-use grimoire2::prelude::Effect;
+use indexmap::IndexMap;
 use grimoire2::theoretical::Theoretical;
 use reedline_repl_rs::*;
+use serde::Serialize;
 use reedline_repl_rs::clap::{Arg, ArgMatches, Command};
 use super::printer::*;
+use crate::output::Output;
 use super::error::{OptimizationError, Result};
 use std::ops::Neg;
 use std::sync::{Arc, Mutex};
 use super::eexpr::EvalExpressionFitnessElement;
 use geneticalchemy::prelude::AlchemyFitnessElement;
+use grimoire2::grimoire::{Evaluation, Recipe};
+use crate::recipe::RecipeBook;
 
 
 
@@ -19,6 +23,9 @@ struct Context {
     last_table: Option<PopulationSerializable>,
     sort: Option<EvalExpressionFitnessElement>,
     truncate: Option<usize>,
+    recipes: RecipeBook,
+    /// Character the populations are optimized for, if known
+    character: Option<String>,
 }
 
 impl Context {
@@ -36,7 +43,7 @@ impl Context {
     }
 }
 
-pub fn run_repl(populations: Arc<Mutex<PopulationsSerializable>>) {
+pub fn run_repl(populations: Arc<Mutex<PopulationsSerializable>>, recipes: RecipeBook, character: Option<String>) {
 
     let context = Context {
         populations,
@@ -44,6 +51,8 @@ pub fn run_repl(populations: Arc<Mutex<PopulationsSerializable>>) {
         last_table: None,
         sort: None,
         truncate: None,
+        recipes,
+        character,
     };
 
     Repl::new(context)
@@ -75,10 +84,25 @@ pub fn run_repl(populations: Arc<Mutex<PopulationsSerializable>>) {
             Command::new("last"), 
             select_last
         )
-        .with_command(Command::new("table"), table)
+        .with_command(
+            Command::new("table")
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_parser(["table", "yaml", "json", "csv"])
+                ),
+            table
+        )
         .with_command(Command::new("sort").arg(Arg::new("value").index(1)), set_sort)
         .with_command(Command::new("truncate").arg(Arg::new("value").index(1)), truncate)
         .with_command(Command::new("show").arg(Arg::new("index").index(1).required(true)), show)
+        .with_command(
+            Command::new("keep")
+                .about("Save an individual of the last table as a recipe of the grimoire")
+                .arg(Arg::new("index").index(1).required(true))
+                .arg(Arg::new("name").index(2).required(true)),
+            keep
+        )
         .run().unwrap();
 }
 
@@ -116,9 +140,16 @@ fn generations(args: ArgMatches, context_: &mut Context) -> Result<Option<String
     Ok(Some(result.to_string()))
 }
 
-fn table(args: ArgMatches, context_: &mut Context) -> Result<Option<String>> {
-    use cli_table::{format::Justify, Cell, Style, Table, CellStruct};
+#[derive(Serialize)]
+struct TableRow {
+    idx: usize,
+    volume: f64,
+    /// Value of every effect by code
+    #[serde(flatten)]
+    effects: IndexMap<String, String>,
+}
 
+fn table(args: ArgMatches, context_: &mut Context) -> Result<Option<String>> {
     let grimoire = context_.populations.lock().unwrap().grimoire.clone();
 
     let mut population = context_.population()?;
@@ -138,34 +169,21 @@ fn table(args: ArgMatches, context_: &mut Context) -> Result<Option<String>> {
 
     println!("{}", population.individuals.len());
 
-    let cells: Vec<Vec<CellStruct>> = population.individuals.into_iter().enumerate().map(|(i, individual)| {
+    let rows: Vec<TableRow> = population.individuals.into_iter().enumerate().map(|(idx, individual)| {
         let mix = Mix::new(&grimoire, individual.genome);
-        vec![
-            i.cell(),
-            mix.volume().cell(),
-            theoretical_to_str(mix.effect(Effect::DirectHealing)).cell().justify(Justify::Right),
-            theoretical_to_str(mix.effect(Effect::DirectPoison)).cell().justify(Justify::Right),
-            theoretical_to_str(mix.effect(Effect::HealingOverTime)).cell().justify(Justify::Right),
-            theoretical_to_str(mix.effect(Effect::PoisonOverTime)).cell().justify(Justify::Right),
-            theoretical_to_str(mix.effect(Effect::HealingLength)).cell().justify(Justify::Right),
-            theoretical_to_str(mix.effect(Effect::PoisonLength)).cell().justify(Justify::Right),
-            theoretical_to_str(mix.effect(Effect::Alcohol)).cell().justify(Justify::Right),
-        ]
+        TableRow {
+            idx,
+            volume: mix.volume().inner(),
+            effects: mix.effects()
+                .iter()
+                .map(|(effect, _, x)| (x.code.clone(), theoretical_to_str(mix.effect(effect))))
+                .collect(),
+        }
     }).collect();
-    let table = cells.table().title(vec![
-        "volume".cell().bold(true), 
-        "idx".cell().bold(true), 
-        "dh".cell().bold(true),
-        "dp".cell().bold(true),
-        "hot".cell().bold(true),
-        "pot".cell().bold(true),
-        "hl".cell().bold(true),
-        "pl".cell().bold(true),
-        "a".cell().bold(true),
-    ])
-    .bold(true);
-
-    Ok(Some(format!("{}", table.display().unwrap())))
+
+    let output = Output::from_name(args.get_one::<String>("output").map(String::as_str).unwrap_or("table"));
+
+    Ok(Some(output.render_rows(&rows).trim_end().to_string()))
 }
 
 fn theoretical_to_str(value: Theoretical<f64>) -> String {
@@ -202,12 +220,43 @@ fn show(args: ArgMatches, context_: &mut Context) -> Result<Option<String>> {
     use grimoire_serde::potion::PotionSerializableConfig;
     use serde_yaml::to_string;
 
+    let grimoire = context_.populations.lock().unwrap().grimoire.clone();
+    let mix = Mix::new(&grimoire, last_table_genome(&args, context_)?);
+
+    let config = PotionSerializableConfig::default();
+    let potion = config.serialize_mix(&mix);
+    let result = to_string(&potion)
+        .map_err(|_| OptimizationError::GenericError("Serialization failed".to_string()))?;
+    Ok(Some(result))
+}
+
+fn keep(args: ArgMatches, context_: &mut Context) -> Result<Option<String>> {
+    let name = args.get_one::<String>("name").unwrap();
+
+    let grimoire = context_.populations.lock().unwrap().grimoire.clone();
+    let mix = Mix::new(&grimoire, last_table_genome(&args, context_)?);
+
+    let recipe = Recipe {
+        character: context_.character.clone(),
+        evaluation: context_.character.as_ref().map(|x| Evaluation::new(x, &mix)),
+        ..Recipe::new(mix.named_ingredients_iter().map(|(n, a)| (n.to_string(), a)).collect())
+    };
+
+    if let Err(x) = context_.recipes.save(name, recipe) {
+        return Ok(Some(x.to_string()))
+    }
+
+    Ok(Some(format!("Kept as {name}")))
+}
+
+/// Genome of the individual at `index` of the last table
+fn last_table_genome(args: &ArgMatches, context_: &Context) -> Result<Vec<(usize, u64)>> {
     let value = args.get_one::<String>("index").cloned().unwrap();
     let index = match value.parse::<usize>() {
         Ok(x) => x,
         Err(_) => return Err(OptimizationError::GenericError("Could not convert to integer".to_string()))
     };
-    let population = context_.last_table.clone()
+    let population = context_.last_table.as_ref()
         .ok_or(
             OptimizationError::GenericError(
                 "You did not open any tables yet (use table command)".to_string())
@@ -218,13 +267,5 @@ fn show(args: ArgMatches, context_: &mut Context) -> Result<Option<String>> {
         )
     )?;
 
-    let grimoire = context_.populations.lock().unwrap().grimoire.clone();
-
-    let mix = Mix::new(&grimoire, individual.genome.clone());
-
-    let config = PotionSerializableConfig::default();
-    let potion = config.serialize_mix(&mix);
-    let result = to_string(&potion)
-        .map_err(|_| OptimizationError::GenericError("Serialization failed".to_string()))?;
-    Ok(Some(result))
+    Ok(individual.genome.clone())
 }
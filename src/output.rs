@@ -0,0 +1,204 @@
+use clap::*;
+use cli_table::{Cell, CellStruct, Style, Table};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Exit code when the asked character, skill, ingredient... does not exist
+pub const NOT_FOUND_EXIT_CODE: i32 = 3;
+
+/// Output format chosen with the global `--output` flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Table,
+    Yaml,
+    Json,
+    Csv,
+}
+
+pub fn arg() -> Arg {
+    Arg::new("output")
+        .long("output")
+        .global(true)
+        .default_value("table")
+        .value_parser(["table", "yaml", "json", "csv"])
+        .help("Output format: tables and YAML documents for people, the others for scripts")
+}
+
+impl Output {
+    pub fn from_args(args: &ArgMatches) -> Self {
+        Self::from_name(args.get_one::<String>("output").map(String::as_str).unwrap_or("table"))
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "yaml" => Output::Yaml,
+            "json" => Output::Json,
+            "csv" => Output::Csv,
+            _ => Output::Table,
+        }
+    }
+
+    /// A single document, such as a potion or an ingredient. Tables show it as YAML, CSV as
+    /// `key,value` rows with nested keys joined with dots
+    pub fn render<T: Serialize>(self, value: &T) -> String {
+        match self {
+            Output::Table | Output::Yaml => yaml_string(value),
+            Output::Json => serde_json::to_string_pretty(value).unwrap() + "\n",
+            Output::Csv => {
+                let mut fields = IndexMap::default();
+                flatten(&serde_json::to_value(value).unwrap(), "", &mut fields);
+
+                csv_string(
+                    &["key".to_string(), "value".to_string()],
+                    fields.into_iter().map(|(k, v)| vec![k, v]),
+                )
+            },
+        }
+    }
+
+    /// A list of records with the same fields, such as a list of ingredients. An empty list is
+    /// an empty YAML or JSON array, and nothing at all in tables and CSV, which have no columns
+    /// to show then
+    pub fn render_rows<T: Serialize>(self, rows: &[T]) -> String {
+        match self {
+            Output::Yaml => yaml_string(&rows),
+            Output::Json => serde_json::to_string_pretty(rows).unwrap() + "\n",
+            Output::Table | Output::Csv if rows.is_empty() => String::default(),
+            Output::Table | Output::Csv => {
+                let rows: Vec<IndexMap<String, String>> = rows.iter().map(|row| {
+                    let mut fields = IndexMap::default();
+                    flatten(&serde_json::to_value(row).unwrap(), "", &mut fields);
+                    fields
+                }).collect();
+
+                // Rows may skip empty fields, so columns are taken from all of them
+                let mut header: Vec<String> = Vec::default();
+                rows.iter().flat_map(|x| x.keys()).for_each(|x| {
+                    if !header.contains(x) {
+                        header.push(x.clone());
+                    }
+                });
+                let values = rows.iter().map(|x| {
+                    header.iter().map(|k| x.get(k).cloned().unwrap_or_default()).collect::<Vec<_>>()
+                });
+
+                match self {
+                    Output::Csv => csv_string(&header, values),
+                    _ => table_string(&header, values),
+                }
+            },
+        }
+    }
+
+    pub fn print<T: Serialize>(self, value: &T) {
+        print!("{}", self.render(value));
+    }
+
+    pub fn print_rows<T: Serialize>(self, rows: &[T]) {
+        print!("{}", self.render_rows(rows));
+    }
+}
+
+/// Reports a missing character, skill, ingredient... and exits with `NOT_FOUND_EXIT_CODE`
+pub fn not_found(what: &str, name: &str) -> ! {
+    eprintln!("{what} not found: {name}");
+    std::process::exit(NOT_FOUND_EXIT_CODE)
+}
+
+/// Same as `not_found`, suggesting the closest name if there is one
+pub fn not_found_suggest(what: &str, name: &str, suggestion: Option<&str>) -> ! {
+    match suggestion {
+        Some(x) => eprintln!("{what} not found: {name}, did you mean {x}?"),
+        None => eprintln!("{what} not found: {name}"),
+    }
+    std::process::exit(NOT_FOUND_EXIT_CODE)
+}
+
+fn flatten(value: &Value, prefix: &str, result: &mut IndexMap<String, String>) {
+    let key = |x: &str| match prefix {
+        "" => x.to_string(),
+        _ => format!("{prefix}.{x}"),
+    };
+
+    match value {
+        // Theories, as serialized by `TheoreticalWrapper`
+        Value::Object(x) if x.len() == 1 && x.contains_key("?") => {
+            result.insert(prefix.to_string(), format!("!? {}", scalar(&x["?"])));
+        },
+        Value::Object(x) => x.iter().for_each(|(k, v)| flatten(v, &key(k), result)),
+        Value::Array(x) if x.iter().all(|x| !x.is_object() && !x.is_array()) => {
+            result.insert(prefix.to_string(), x.iter().map(scalar).collect::<Vec<_>>().join(";"));
+        },
+        Value::Array(x) => x.iter().enumerate().for_each(|(i, v)| flatten(v, &key(&i.to_string()), result)),
+        x => { result.insert(prefix.to_string(), scalar(x)); },
+    }
+}
+
+/// YAML can't hold nested enums, which are written as maps then
+fn yaml_string<T: Serialize>(value: &T) -> String {
+    serde_yaml::to_string(value)
+        .or_else(|_| serde_yaml::to_string(&serde_json::to_value(value).unwrap()))
+        .unwrap()
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::default(),
+        Value::String(x) => x.clone(),
+        x => x.to_string(),
+    }
+}
+
+fn csv_string(header: &[String], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::default());
+
+    writer.write_record(header).unwrap();
+    rows.for_each(|x| writer.write_record(&x).unwrap());
+
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+fn table_string(header: &[String], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let cells: Vec<Vec<CellStruct>> = rows.map(|x| x.into_iter().map(|x| x.cell()).collect()).collect();
+    let title: Vec<CellStruct> = header.iter().map(|x| x.clone().cell().bold(true)).collect();
+
+    format!("{}\n", cells.table().title(title).bold(true).display().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render_rows_empty() {
+        let rows: Vec<Value> = Vec::default();
+
+        assert_eq!(Output::Table.render_rows(&rows), "");
+        assert_eq!(Output::Csv.render_rows(&rows), "");
+        assert_eq!(Output::Yaml.render_rows(&rows), "[]\n");
+        assert_eq!(Output::Json.render_rows(&rows), "[]\n");
+    }
+
+    #[test]
+    fn test_render_rows_csv() {
+        let rows = [
+            json!({"name": "Salvia Oil", "weight": {"?": 0.5}, "tags": ["oil", "herb"]}),
+            json!({"name": "Troll Sweat", "skill": "Trollology"}),
+        ];
+
+        assert_eq!(
+            Output::Csv.render_rows(&rows),
+            "name,weight,tags,skill\nSalvia Oil,!? 0.5,oil;herb,\nTroll Sweat,,,Trollology\n",
+        );
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let value = json!({"name": "Salvia Oil", "modifiers": {"dh": 2.4}});
+
+        assert_eq!(Output::Csv.render(&value), "key,value\nname,Salvia Oil\nmodifiers.dh,2.4\n");
+    }
+}
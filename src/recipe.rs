@@ -0,0 +1,350 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use error_stack::{Report, Result, IntoReport, ResultExt};
+use grimoire2::grimoire::{Evaluation, Recipe};
+use grimoire2::modify::GrimoireUpdate;
+use grimoire2::modify::command::Commands;
+use grimoire2::prelude::{Grimoire, Character};
+use grimoire2::standalone::OptimizedGrimoire;
+use grimoire_serde::potion::PotionSerializableConfig;
+use grimoire_serde::theoretical::TheoreticalWrapper;
+use indexmap::IndexMap;
+use serde::Serialize;
+use clap::*;
+use thiserror::Error;
+
+use crate::fs::load;
+use crate::layers::Layers;
+use crate::mix::{MixConfig, or_not_found};
+use crate::output::{self, Output};
+use crate::update::check_grimoire;
+
+#[derive(Error, Debug)]
+pub enum RecipeError {
+    #[error("Could not load mix")]
+    Load,
+    #[error("Ingredient not found: {0}")]
+    IngredientNotFound(String),
+    #[error("No character to evaluate the recipe for, give one with -c")]
+    NoCharacter,
+    #[error("Could not evaluate the recipe")]
+    Mix,
+    #[error("The recipe makes the grimoire invalid, see `validate`")]
+    Invalid,
+    #[error("No output file given")]
+    NoOutput,
+    #[error("Could not save grimoire")]
+    Save,
+}
+
+/// The grimoire recipes are saved to, with its layers and the file changes go to
+#[derive(Clone)]
+pub struct RecipeBook {
+    layers: Layers,
+    grimoire: Grimoire,
+    to: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct RecipeListRow {
+    name: String,
+    character: String,
+    tags: String,
+    ingredients: String,
+    evaluated_for: String,
+}
+
+/// A recipe with values written the way the rest of the output writes them
+#[derive(Serialize)]
+struct RecipeSerializable {
+    ingredients: IndexMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    character: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    notes: String,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    tags: BTreeSet<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evaluation: Option<EvaluationSerializable>,
+}
+
+#[derive(Serialize)]
+struct EvaluationSerializable {
+    character: String,
+    volume: TheoreticalWrapper,
+    effects: IndexMap<String, TheoreticalWrapper>,
+}
+
+fn name_arg() -> Arg {
+    Arg::new("name")
+        .index(1)
+        .required(true)
+        .help("Name of the recipe")
+}
+
+fn to_arg() -> Arg {
+    Arg::new("to")
+        .env("ALRUST_TO")
+        .short('t')
+        .long("to")
+        .value_name("to")
+        .help("Where to save the grimoire; the last overlay if omitted")
+}
+
+fn character_arg() -> Arg {
+    Arg::new("character")
+        .short('c')
+        .long("character")
+        .env("ALRUST_CHARACTER")
+}
+
+pub fn command() -> Command {
+    Command::new("recipe")
+        .before_help("Keep mixes in the grimoire by name")
+        .subcommand(
+            Command::new("add")
+                .before_help("Add a recipe from a mix file, or replace the one with the same name")
+                .arg(name_arg())
+                .arg(
+                    Arg::new("mixfile")
+                        .index(2)
+                        .required(true)
+                        .help("Mix configuration file, only its `mix` is kept (see help for `mix` command)")
+                )
+                .arg(character_arg().help("Character the recipe is meant for"))
+                .arg(Arg::new("notes").long("notes").help("Free-form notes"))
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .action(ArgAction::Append)
+                        .help("Tag of the recipe, may be repeated")
+                )
+                .arg(to_arg())
+        )
+        .subcommand(
+            Command::new("list")
+                .before_help("List the recipes")
+                .arg(character_arg().help("Only the recipes meant for the character"))
+                .arg(Arg::new("tag").long("tag").help("Only the recipes with the tag"))
+        )
+        .subcommand(
+            Command::new("show")
+                .before_help("Show a recipe with its last evaluation")
+                .arg(name_arg())
+        )
+        .subcommand(
+            Command::new("eval")
+                .before_help("Brew a recipe and keep the potion as its last evaluation")
+                .arg(name_arg())
+                .arg(character_arg().help("Character to brew for; the one of the recipe if omitted"))
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("Only show the potion")
+                )
+                .arg(to_arg())
+        )
+        .subcommand(
+            Command::new("remove")
+                .before_help("Remove a recipe")
+                .arg(name_arg())
+                .arg(to_arg())
+        )
+        .subcommand_required(true)
+}
+
+pub fn matched_command(grimoire: Grimoire, layers: &Layers, args: &ArgMatches) {
+    let book = |args: &ArgMatches| RecipeBook::new(layers.clone(), args.get_one::<String>("to").map(PathBuf::from));
+
+    match args.subcommand() {
+        Some(("add", args)) => {
+            let name = args.get_one::<String>("name").unwrap();
+            let config: MixConfig = load(Path::new(args.get_one::<String>("mixfile").unwrap()))
+                .change_context(RecipeError::Load)
+                .unwrap();
+
+            let ingredients = match resolve(&grimoire, config.mix.iter().map(|(n, a)| (n.as_str(), *a))) {
+                Ok(x) => x,
+                Err(report) => match report.current_context() {
+                    RecipeError::IngredientNotFound(name) => output::not_found("Ingredient", name),
+                    _ => panic!("{report:?}"),
+                },
+            };
+
+            let recipe = Recipe {
+                character: args.get_one::<String>("character").cloned(),
+                notes: args.get_one::<String>("notes").cloned().unwrap_or_default(),
+                tags: args.get_many::<String>("tag").map(|x| x.cloned().collect()).unwrap_or_default(),
+                ..Recipe::new(ingredients)
+            };
+
+            book(args).save(name, recipe).unwrap();
+        },
+        Some(("list", args)) => {
+            let character = args.get_one::<String>("character");
+            let tag = args.get_one::<String>("tag");
+
+            let rows: Vec<RecipeListRow> = grimoire.recipes.iter()
+                .filter(|(_, x)| character.map(|c| x.character.as_ref() == Some(c)).unwrap_or(true))
+                .filter(|(_, x)| tag.map(|t| x.tags.contains(t)).unwrap_or(true))
+                .map(|(name, x)| RecipeListRow {
+                    name: name.clone(),
+                    character: x.character.clone().unwrap_or_default(),
+                    tags: x.tags.iter().cloned().collect::<Vec<_>>().join(", "),
+                    ingredients: x.ingredients.iter().map(|(n, a)| format!("{n} x{a}")).collect::<Vec<_>>().join(", "),
+                    evaluated_for: x.evaluation.as_ref().map(|x| x.character.clone()).unwrap_or_default(),
+                })
+                .collect();
+
+            Output::from_args(args).print_rows(&rows);
+        },
+        Some(("show", args)) => {
+            let name = args.get_one::<String>("name").unwrap();
+
+            let Some(recipe) = grimoire.recipes.get(name) else {
+                output::not_found("Recipe", name)
+            };
+
+            Output::from_args(args).print(&RecipeSerializable::from(recipe.clone()));
+        },
+        Some(("eval", args)) => {
+            let name = args.get_one::<String>("name").unwrap();
+
+            let Some(recipe) = grimoire.recipes.get(name).cloned() else {
+                output::not_found("Recipe", name)
+            };
+
+            let character_name = args.get_one::<String>("character")
+                .or(recipe.character.as_ref())
+                .ok_or(Report::new(RecipeError::NoCharacter))
+                .unwrap()
+                .clone();
+
+            let Some(character) = grimoire.characters.get(character_name.as_str()).cloned() else {
+                output::not_found("Character", &character_name)
+            };
+
+            let config = MixConfig {
+                mix: recipe.ingredients.iter().map(|(n, a)| (n.clone(), *a)).collect(),
+                ..MixConfig::default()
+            };
+            let (potion, evaluation) = or_not_found(config
+                .with_mix(grimoire, &character, |mix| (
+                    PotionSerializableConfig::default().serialize_mix(mix),
+                    Evaluation::new(&character_name, mix),
+                ))
+                .change_context(RecipeError::Mix));
+
+            Output::from_args(args).print(&potion);
+
+            if !args.get_flag("dry-run") {
+                let recipe = Recipe { evaluation: Some(evaluation), ..recipe };
+                book(args).save(name, recipe).unwrap();
+            }
+        },
+        Some(("remove", args)) => {
+            let name = args.get_one::<String>("name").unwrap();
+
+            if !grimoire.recipes.contains_key(name) {
+                output::not_found("Recipe", name)
+            }
+
+            book(args).remove(name).unwrap();
+        },
+        None | Some(_) => {}
+    }
+}
+
+/// Names of the ingredients as the grimoire has them, so that aliases and typos don't end
+/// up in recipes
+pub fn resolve<'a>(
+    grimoire: &Grimoire,
+    ingredients: impl IntoIterator<Item = (&'a str, u64)>,
+) -> Result<IndexMap<String, u64>, RecipeError> {
+    let optimized = OptimizedGrimoire::from((&Character::default(), grimoire));
+    let mut result = IndexMap::default();
+
+    for (name, amount) in ingredients {
+        let index = optimized
+            .ingredients
+            .by_name(name)
+            .into_report()
+            .change_context(RecipeError::IngredientNotFound(name.to_string()))?;
+
+        *result.entry(optimized.ingredients.name(index).to_string()).or_default() += amount;
+    }
+
+    Ok(result)
+}
+
+impl RecipeBook {
+    pub fn new(layers: Layers, to: Option<PathBuf>) -> Self {
+        Self {
+            grimoire: layers.grimoire(),
+            layers,
+            to,
+        }
+    }
+
+    /// Adds the recipe, or replaces the one with the same name, and saves the grimoire
+    pub fn save(&mut self, name: &str, recipe: Recipe) -> Result<(), RecipeError> {
+        self.commit(GrimoireUpdate::default().recipe(name, recipe).clone())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), RecipeError> {
+        self.commit(GrimoireUpdate::default().remove_recipe(name).clone())
+    }
+
+    fn commit(&mut self, update: GrimoireUpdate) -> Result<(), RecipeError> {
+        let mut updated = self.grimoire.clone();
+        update.update(&mut updated);
+
+        check_grimoire(&updated).change_context(RecipeError::Invalid)?;
+
+        let to = self.layers.target(self.to.as_deref()).ok_or(Report::new(RecipeError::NoOutput))?;
+        self.layers.save(to, &updated).change_context(RecipeError::Save)?;
+
+        self.grimoire = updated;
+        Ok(())
+    }
+}
+
+impl From<Recipe> for RecipeSerializable {
+    fn from(value: Recipe) -> Self {
+        Self {
+            ingredients: value.ingredients,
+            character: value.character,
+            notes: value.notes,
+            tags: value.tags,
+            evaluation: value.evaluation.map(|x| EvaluationSerializable {
+                character: x.character,
+                volume: x.volume.into(),
+                effects: x.effects.into_iter().map(|(n, v)| (n, v.into())).collect(),
+            }),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::grimoire::Ingredient;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Sea Dew Leaves".to_string(), Ingredient {
+            aliases: ["dew".to_string()].into_iter().collect(),
+            ..Ingredient::default()
+        });
+
+        let resolved = resolve(&grimoire, [("dew", 2), ("Sea Dew Leaves", 1)]).unwrap();
+        assert_eq!(resolved.into_iter().collect::<Vec<_>>(), vec![("Sea Dew Leaves".to_string(), 3)]);
+
+        let missing = resolve(&grimoire, [("Moss", 1)]).unwrap_err();
+        assert!(matches!(missing.current_context(), RecipeError::IngredientNotFound(x) if x == "Moss"));
+    }
+}
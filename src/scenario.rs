@@ -0,0 +1,171 @@
+use std::path::Path;
+use error_stack::{Report, Result, IntoReport, ResultExt};
+use grimoire2::prelude::{Grimoire, Theoretical};
+use grimoire2::scenario::{Outcome, Scenario};
+use grimoire2::standalone::{Mix, OptimizedGrimoire};
+use grimoire2::timeline::Timeline;
+use clap::*;
+use thiserror::Error;
+
+use crate::fs::load;
+use crate::output::{self, Output};
+use crate::mix::MixConfig;
+
+#[derive(Error, Debug)]
+pub enum ScenarioError {
+    #[error("Ingredient not found: {0}")]
+    IngredientNotFound(String),
+    #[error("Potion {0} has no recipe, give one with --mix")]
+    NoRecipe(String),
+    #[error("Could not evaluate the mix")]
+    Mix,
+}
+
+/// A scenario with the timelines of its potions worked out, but for the potions without a
+/// recipe, which stand for the one being optimized
+#[derive(Clone, Default)]
+pub struct ScenarioObjective {
+    scenario: Scenario,
+    timelines: Vec<Option<Timeline>>,
+}
+
+pub fn command() -> Command {
+    Command::new("scenario")
+        .before_help("Drink potions through a fight and see whether the character survives")
+        .arg(
+            Arg::new("character")
+                .short('c')
+                .long("character")
+                .required(true)
+                .help("Character name")
+                .env("ALRUST_CHARACTER")
+        )
+        .arg(
+            Arg::new("mix")
+                .long("mix")
+                .help("Mix configuration file of the potions without a recipe (see help for `mix` command)")
+        )
+        .arg(
+            Arg::new("scenario")
+                .index(1)
+                .required(true)
+                .help("Scenario file")
+                .long_help(
+                    "Path to scenario file\n\
+                    \n\
+                    Scenario file format:\n\
+                    \n\
+                    health: float (default 100)  # health at the start and at most\n\
+                    length: int (default 60)  # seconds the fight lasts\n\
+                    drink_below: float (default 0.5)  # part of the health below which a potion is drunk\n\
+                    damage:\n\
+                    \t- from: int  # second the damage starts at\n\
+                    \t  to: int (optional)  # second it stops at, a single hit without it\n\
+                    \t  amount: float  # damage of the hit, or of every second\n\
+                    \t...\n\
+                    potions:  # the first one off cooldown is drunk\n\
+                    \t<name of potion>:\n\
+                    \t\tmix:  # none for the potion of --mix or the one optimized\n\
+                    \t\t\t<name of ingredient>: <amount>\n\
+                    \t\tvolume: float (optional)  # volume of the mix without it\n\
+                    \t\tcooldown: int (default 10)  # seconds before it can be drunk again\n\
+                    \t\tcount: int (optional)  # potions at hand, as many as needed without it"
+                )
+        )
+}
+
+pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+    let scenario: Scenario = load(Path::new(args.get_one::<String>("scenario").unwrap())).unwrap();
+
+    let character_name = args.get_one::<String>("character").unwrap();
+
+    let Some(character) = grimoire.characters.get(character_name.as_str()).cloned() else {
+        output::not_found("Character", character_name)
+    };
+
+    let optimized = OptimizedGrimoire::from((&character, &grimoire));
+    let objective = ScenarioObjective::new(scenario, &optimized, |x| x.inner()).unwrap();
+
+    let outcome = match args.get_one::<String>("mix") {
+        Some(path) => {
+            let config: MixConfig = load(Path::new(path)).unwrap();
+            config.with_mix(grimoire, &character, |mix| objective.outcome(Some(mix), |x| x.inner()))
+                .change_context(ScenarioError::Mix)
+                .unwrap()
+        },
+        None => objective.check_recipes().map(|_| objective.outcome(None, |x| x.inner())).unwrap(),
+    };
+
+    Output::from_args(args).print(&outcome);
+}
+
+impl ScenarioObjective {
+    /// Works out the potions with a recipe, with `value` deciding what theories and unknown
+    /// values are worth
+    pub fn new(
+        scenario: Scenario,
+        grimoire: &OptimizedGrimoire,
+        value: impl Fn(Theoretical<f64>) -> f64 + Copy,
+    ) -> Result<Self, ScenarioError> {
+        let mut timelines = Vec::default();
+
+        for potion in scenario.potions.values() {
+            if potion.mix.is_empty() {
+                timelines.push(None);
+                continue;
+            }
+
+            let mut ingredients: Vec<(usize, u64)> = Vec::default();
+
+            for (name, amount) in &potion.mix {
+                let index = grimoire
+                    .ingredients
+                    .by_name(name)
+                    .into_report()
+                    .change_context(ScenarioError::IngredientNotFound(name.to_string()))?;
+
+                ingredients.push((index, *amount));
+            }
+
+            let mix = Mix::new(grimoire, ingredients);
+            let volume = potion.volume.unwrap_or_else(|| value(mix.volume()));
+            timelines.push(Some(Timeline::simulate_with(&mix, volume, value)));
+        }
+
+        Ok(Self {
+            scenario,
+            timelines,
+        })
+    }
+
+    pub fn length(&self) -> u64 {
+        self.scenario.length
+    }
+
+    /// Fails if a potion has no recipe
+    pub fn check_recipes(&self) -> Result<(), ScenarioError> {
+        match self.timelines.iter().position(Option::is_none) {
+            Some(i) => Err(Report::new(ScenarioError::NoRecipe(
+                self.scenario.potions.get_index(i).unwrap().0.clone()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Plays the scenario with `mix` for the potions without a recipe, which do nothing
+    /// without it
+    pub fn outcome(&self, mix: Option<&Mix>, value: impl Fn(Theoretical<f64>) -> f64 + Copy) -> Outcome {
+        let volume = self.scenario.potions.values()
+            .zip(&self.timelines)
+            .find(|(_, x)| x.is_none())
+            .and_then(|(x, _)| x.volume);
+
+        let candidate = match mix {
+            Some(mix) => Timeline::simulate_with(mix, volume.unwrap_or_else(|| value(mix.volume())), value),
+            None => Timeline::default(),
+        };
+
+        let timelines: Vec<&Timeline> = self.timelines.iter().map(|x| x.as_ref().unwrap_or(&candidate)).collect();
+        self.scenario.simulate(&timelines)
+    }
+}
@@ -0,0 +1,329 @@
+use std::path::Path;
+use error_stack::{Report, Result, IntoReport, ResultExt};
+use grimoire2::modify::command::Commands;
+use grimoire2::prelude::{Grimoire, Character};
+use grimoire2::standalone::{OptimizedGrimoire, Mix};
+use grimoire_serde::potion::PotionSerializable;
+use serde::Serialize;
+use evalexpr::build_operator_tree;
+use clap::*;
+use thiserror::Error;
+
+use crate::fs::load;
+use crate::output::{self, Output};
+use crate::mix::MixConfig;
+use crate::optimize2::build::Optimizator;
+
+#[derive(Error, Debug)]
+pub enum SubstituteError {
+    #[error("Ingredient not found: {0}")]
+    IngredientNotFound(String),
+    #[error("Ingredient {0} is not a part of the recipe")]
+    NotInRecipe(String),
+    #[error("Invalid ingredient filter")]
+    BadFilter,
+    #[error("The grimoire update of the recipe does not fit the grimoire")]
+    BadUpdate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceMetric {
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    pub fn distance(&self, a: &[f64], b: &[f64]) -> f64 {
+        let deltas = a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs());
+
+        match self {
+            Self::Euclidean => deltas.map(|x| x * x).sum::<f64>().sqrt(),
+            Self::Manhattan => deltas.sum(),
+            Self::Chebyshev => deltas.fold(0., f64::max),
+        }
+    }
+}
+
+impl std::str::FromStr for DistanceMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "euclidean" => Ok(Self::Euclidean),
+            "manhattan" => Ok(Self::Manhattan),
+            "chebyshev" => Ok(Self::Chebyshev),
+            other => Err(format!("Unknown distance metric: {other}")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Substitution {
+    pub ingredient: String,
+    pub amount: u64,
+    pub distance: f64,
+    pub potion: PotionSerializable,
+}
+
+pub fn command() -> Command {
+    Command::new("substitute")
+        .before_help("Find the closest replacement for a missing ingredient of a recipe")
+        .arg(
+            Arg::new("character")
+                .short('c')
+                .long("character")
+                .required(true)
+                .help("Character name")
+                .env("ALRUST_CHARACTER")
+        )
+        .arg(
+            Arg::new("mixfile")
+                .index(1)
+                .required(true)
+                .help("Mix configuration file (see help for `mix` command)")
+        )
+        .arg(
+            Arg::new("missing")
+                .short('m')
+                .long("missing")
+                .required(true)
+                .help("Name of the ingredient to replace")
+        )
+        .arg(
+            Arg::new("metric")
+                .long("metric")
+                .default_value("euclidean")
+                .value_parser(["euclidean", "manhattan", "chebyshev"])
+                .help("Distance between the effects of the original and the substituted potion")
+        )
+        .arg(
+            Arg::new("include")
+                .short('i')
+                .long("include")
+                .help("Expression that returns bool to determine whether ingredient may be used \
+                       as a substitute (same identifiers as `include_ingredients` of `optimize`)")
+        )
+        .arg(
+            Arg::new("max-amount")
+                .long("max-amount")
+                .value_parser(value_parser!(u64))
+                .help("Maximum amount of the substitute to try (default: twice the missing amount)")
+        )
+        .arg(
+            Arg::new("top")
+                .short('n')
+                .long("top")
+                .value_parser(value_parser!(usize))
+                .default_value("10")
+                .help("Number of substitutes to show")
+        )
+}
+
+pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+    let character_name = args.get_one::<String>("character").unwrap();
+    let config: MixConfig = load(Path::new(args.get_one::<String>("mixfile").unwrap())).unwrap();
+    let missing = args.get_one::<String>("missing").unwrap();
+    let metric: DistanceMetric = args.get_one::<String>("metric").unwrap().parse().unwrap();
+    let include = args.get_one::<String>("include").cloned();
+    let max_amount = args.get_one::<u64>("max-amount").cloned();
+    let top = *args.get_one::<usize>("top").unwrap();
+
+    let Some(character) = grimoire.characters.get(character_name.as_str()).cloned() else {
+        output::not_found("Character", character_name)
+    };
+
+    let result = substitute(&config, grimoire, &character, missing, metric, include.as_deref(), max_amount);
+
+    let mut substitutions = match result {
+        Ok(x) => x,
+        Err(report) => match report.current_context() {
+            SubstituteError::IngredientNotFound(name) => output::not_found("Ingredient", name),
+            SubstituteError::NotInRecipe(name) => output::not_found("Ingredient of the recipe", name),
+            _ => panic!("{report:?}"),
+        },
+    };
+    substitutions.truncate(top);
+
+    Output::from_args(args).print(&substitutions);
+}
+
+/// Replace `missing` in the recipe with each of the remaining ingredients and rank the results
+/// by how close their effects are to the effects of the original recipe.
+pub fn substitute(
+    config: &MixConfig,
+    mut grimoire: Grimoire,
+    character: &Character,
+    missing: &str,
+    metric: DistanceMetric,
+    include: Option<&str>,
+    max_amount: Option<u64>,
+) -> Result<Vec<Substitution>, SubstituteError> {
+    config.grimoire.to_update(&grimoire.effects)
+        .into_report()
+        .change_context(SubstituteError::BadUpdate)?
+        .update(&mut grimoire);
+
+    let missing_amount = *config.mix.get(missing)
+        .ok_or(Report::new(SubstituteError::NotInRecipe(missing.to_string())))?;
+    let max_amount = max_amount.unwrap_or(missing_amount * 2).max(1);
+
+    let optimized = OptimizedGrimoire::from((character, &grimoire));
+    let original = Mix::new(&optimized, indices(&optimized, config.mix.iter())?);
+    let target = effects(&original);
+
+    let filter = include
+        .map(build_operator_tree)
+        .transpose()
+        .into_report()
+        .change_context(SubstituteError::BadFilter)?;
+
+    let mut remaining: Vec<(&String, u64)> = config.mix.iter()
+        .filter(|(name, _)| name.as_str() != missing)
+        .map(|(name, amount)| (name, *amount))
+        .collect();
+    remaining.sort();
+
+    let mut result = Vec::default();
+
+    for (name, ingredient) in grimoire.ingredients.iter() {
+        if name == missing {
+            continue;
+        }
+
+        if let Some(node) = &filter {
+            let included = Optimizator::should_include_ingredient(node, ingredient, &grimoire.effects)
+                .into_report()
+                .change_context(SubstituteError::BadFilter)?;
+            if !included {
+                continue;
+            }
+        }
+
+        let best = (1..=max_amount)
+            .map(|amount| {
+                let mut ingredients = indices(&optimized, remaining.iter().map(|(n, a)| (*n, a)))?;
+                let index = optimized.ingredients.by_name(name)
+                    .into_report()
+                    .change_context(SubstituteError::IngredientNotFound(name.clone()))?;
+
+                match ingredients.iter_mut().find(|(i, _)| *i == index) {
+                    Some((_, existing)) => *existing += amount,
+                    None => ingredients.push((index, amount)),
+                };
+
+                let mix = Mix::new(&optimized, ingredients);
+                Ok((amount, metric.distance(&target, &effects(&mix)), mix))
+            })
+            .collect::<Result<Vec<_>, SubstituteError>>()?
+            .into_iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((amount, distance, mix)) = best {
+            result.push(Substitution {
+                ingredient: name.clone(),
+                amount,
+                distance,
+                potion: config.potion.serialize_mix(&mix),
+            });
+        }
+    }
+
+    result.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+    Ok(result)
+}
+
+fn indices<'a>(
+    optimized: &OptimizedGrimoire,
+    mix: impl Iterator<Item = (&'a String, &'a u64)>,
+) -> Result<Vec<(usize, u64)>, SubstituteError> {
+    mix.map(|(name, amount)| {
+        let index = optimized.ingredients.by_name(name)
+            .into_report()
+            .change_context(SubstituteError::IngredientNotFound(name.to_string()))?;
+        Ok((index, *amount))
+    }).collect()
+}
+
+fn effects(mix: &Mix) -> Vec<f64> {
+    mix.effects().iter().map(|(effect, ..)| mix.effect(effect).inner()).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use grimoire2::effect::Effect;
+    use grimoire2::grimoire::Ingredient;
+    use grimoire2::theoretical::Theoretical;
+
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        let (a, b) = ([1., 2., 3.], [4., 6., 3.]);
+
+        assert_eq!(DistanceMetric::Euclidean.distance(&a, &b), 5.);
+        assert_eq!(DistanceMetric::Manhattan.distance(&a, &b), 7.);
+        assert_eq!(DistanceMetric::Chebyshev.distance(&a, &b), 4.);
+        assert_eq!(DistanceMetric::Euclidean.distance(&a, &a), 0.);
+        assert_eq!("chebyshev".parse::<DistanceMetric>(), Ok(DistanceMetric::Chebyshev));
+        assert!("hamming".parse::<DistanceMetric>().is_err());
+    }
+
+    fn ingredient(effect: Effect, term: f64) -> Ingredient {
+        let mut result = Ingredient {
+            weight: Theoretical::Known(1.),
+            ..Ingredient::default()
+        };
+        result.modifiers[effect].term = Theoretical::Known(term);
+        result
+    }
+
+    fn grimoire() -> Grimoire {
+        let mut grimoire = Grimoire::default();
+        grimoire.ingredients.insert("Leaf".to_string(), ingredient(Effect::DirectHealing, 2.));
+        grimoire.ingredients.insert("Bark".to_string(), ingredient(Effect::DirectPoison, 2.));
+        grimoire.ingredients.insert("Twin Leaf".to_string(), ingredient(Effect::DirectHealing, 2.));
+        grimoire.ingredients.insert("Moss".to_string(), ingredient(Effect::DirectHealing, 0.5));
+        grimoire.ingredients.insert("Water".to_string(), ingredient(Effect::DirectHealing, 0.));
+        grimoire
+    }
+
+    fn config() -> MixConfig {
+        MixConfig {
+            mix: [("Leaf".to_string(), 2), ("Water".to_string(), 2)].into_iter().collect(),
+            ..MixConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_substitute_ranking() {
+        let result = substitute(
+            &config(), grimoire(), &Character::default(), "Leaf", DistanceMetric::Euclidean, None, None
+        ).unwrap();
+        let names: Vec<&str> = result.iter().map(|x| x.ingredient.as_str()).collect();
+
+        assert_eq!(names, vec!["Twin Leaf", "Moss", "Water", "Bark"]);
+        assert_eq!(result[0].amount, 2);
+        assert_eq!(result[0].distance, 0.);
+        assert_eq!(result[1].amount, 4);
+        assert!(result.windows(2).all(|x| x[0].distance <= x[1].distance));
+    }
+
+    #[test]
+    fn test_substitute_filter_and_errors() {
+        let include = Some("dh > 1");
+        let result = substitute(
+            &config(), grimoire(), &Character::default(), "Leaf", DistanceMetric::Manhattan, include, None
+        ).unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].ingredient, "Twin Leaf");
+
+        let missing = substitute(
+            &config(), grimoire(), &Character::default(), "Moss", DistanceMetric::Euclidean, None, None
+        );
+        assert!(matches!(missing.map(|_| ()).unwrap_err().current_context(), SubstituteError::NotInRecipe(_)));
+    }
+}
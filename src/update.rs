@@ -1,17 +1,118 @@
 use crate::fs::*;
-use grimoire2::grimoire::versioned::GrimoireVersioned;
+use crate::layers::Layers;
 use grimoire2::grimoire::Grimoire;
 use grimoire2::modify::command::Commands;
+use grimoire2::modify::changes::{diff_changes, Change, FieldValue};
+use grimoire2::validate::{validate, is_valid, Severity};
 use grimoire_serde::modify::GrimoireUpdateSerializable;
+use std::io::{stdout, IsTerminal};
 use std::path::Path;
-use error_stack::Result;
+use crossterm::style::Stylize;
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use thiserror::Error;
 
-pub fn update_grimoire(mut grimoire: Grimoire, from: &Path, to: &Path) -> Result<(), FSOperationError> {
-    let from: GrimoireUpdateSerializable = load(from)?;
+#[derive(Error, Debug)]
+pub enum UpdateError {
+    #[error("Could not load update file")]
+    Load,
+    #[error("Could not save grimoire")]
+    Save,
+    #[error("Updated grimoire is invalid, see `validate`")]
+    InvalidGrimoire,
+    #[error("Update replaces known values with theories or unknown values")]
+    Downgrade,
+    #[error("No output file given")]
+    NoOutput,
+}
 
-    from.to_update().update(&mut grimoire);
+pub fn update_grimoire(
+    layers: &Layers,
+    from: &Path,
+    to: Option<&Path>,
+    dry_run: bool,
+    fail_on_downgrade: bool,
+) -> Result<(), UpdateError> {
+    let grimoire = layers.grimoire();
+    let from: GrimoireUpdateSerializable = load(from).change_context(UpdateError::Load)?;
 
-    let output_versioned: GrimoireVersioned = grimoire.into();
+    let mut updated = grimoire.clone();
+    from.to_update(&grimoire.effects)
+        .into_report()
+        .change_context(UpdateError::Load)?
+        .update(&mut updated);
 
-    save(to, &output_versioned)
-}
\ No newline at end of file
+    let changes = diff_changes(&grimoire, &updated);
+
+    if dry_run {
+        print_changes(&changes);
+    }
+
+    if fail_on_downgrade {
+        check_downgrades(&changes)?;
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    check_grimoire(&updated)?;
+
+    layers.save(layers.target(to).ok_or(Report::new(UpdateError::NoOutput))?, &updated)
+        .change_context(UpdateError::Save)
+}
+
+fn print_changes(changes: &[Change]) {
+    if changes.is_empty() {
+        println!("No changes");
+    }
+
+    if !stdout().is_terminal() {
+        changes.iter().for_each(|x| println!("{x}"));
+        return;
+    }
+
+    for change in changes {
+        let old = change.old.to_string();
+        let new = change.new.to_string();
+
+        let (old, new) = match (&change.old, &change.new) {
+            (FieldValue::Absent, _) => (old.dark_grey(), new.green()),
+            (_, FieldValue::Absent) => (old.red(), new.dark_grey()),
+            _ if change.is_downgrade() => (old.yellow(), new.red()),
+            _ => (old.yellow(), new.green()),
+        };
+
+        println!("{}: {} -> {}", change.path.clone().bold(), old, new);
+    }
+}
+
+/// Refuse updates that make known values less certain
+pub fn check_downgrades(changes: &[Change]) -> Result<(), UpdateError> {
+    let downgrades: Vec<_> = changes.iter().filter(|x| x.is_downgrade()).collect();
+
+    if downgrades.is_empty() {
+        return Ok(());
+    }
+
+    Err(downgrades.into_iter().fold(
+        Report::new(UpdateError::Downgrade),
+        |report, x| report.attach_printable(x.to_string()),
+    ))
+}
+
+/// Refuse grimoires with skill cycles, unknown parents or impossible characters
+pub fn check_grimoire(grimoire: &Grimoire) -> Result<(), UpdateError> {
+    let issues = validate(grimoire);
+
+    if is_valid(&issues) {
+        return Ok(());
+    }
+
+    Err(issues
+        .into_iter()
+        .filter(|x| x.severity() == Severity::Error)
+        .fold(
+            Report::new(UpdateError::InvalidGrimoire),
+            |report, x| report.attach_printable(x.to_string()),
+        ))
+}
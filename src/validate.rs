@@ -0,0 +1,37 @@
+use clap::*;
+use grimoire2::grimoire::Grimoire;
+use grimoire2::validate::{validate, Severity};
+
+pub fn command() -> Command {
+    Command::new("validate")
+        .before_help("Check the grimoire for skill cycles, unknown skills, impossible characters \
+                      and suspicious ingredient modifiers")
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .action(ArgAction::SetTrue)
+                .help("Fail on warnings as well")
+        )
+}
+
+pub fn matched_command(grimoire: Grimoire, args: &ArgMatches) {
+    let strict = args.get_flag("strict");
+    let issues = validate(&grimoire);
+
+    for issue in &issues {
+        match issue.severity() {
+            Severity::Warning => println!("warning: {}", issue),
+            Severity::Error => println!("error: {}", issue),
+        }
+    }
+
+    let failed = issues
+        .iter()
+        .any(|x| strict || x.severity() == Severity::Error);
+
+    if failed {
+        std::process::exit(1);
+    }
+
+    println!("Grimoire is valid");
+}